@@ -1,6 +1,42 @@
+use majin_blob_client::beacon::{BeaconClient, BlockId};
+use majin_blob_client::cache::DiskCache;
+use majin_blob_client::execution::ExecutionClient;
+use majin_blob_client::http::HttpConfig;
+use majin_blob_client::state_update::resolve_state_update;
+use majin_blob_client::watch::Watcher;
 use majin_blob_core::blob;
+use majin_blob_core::calldata::decode_legacy_state_update;
+use majin_blob_core::commitment::{state_diff_commitment, StateDiff};
+use majin_blob_core::interpret::ValueInterpreter;
+use majin_blob_core::kzg::{check_point_evaluation, StarknetKzgClaim};
+use majin_blob_core::l1::{decode_state_update, L1StateUpdate};
+use majin_blob_core::preimage::{PreimageResolver, DEFAULT_VARIABLES};
+use majin_blob_core::registry::{LabelRegistry, Network};
+use majin_blob_core::storage_labels::{LabelSpec, StorageLabeller, StorageLabels};
+use majin_blob_core::tokens::{balance_changes, BalanceChange, Token};
+use majin_blob_eip_4844::blob::kzg_to_versioned_hash;
+use majin_blob_eip_4844::fee::{self, Fork};
+use majin_blob_eip_4844::transaction::{
+    decode_blob_transaction, secret_key_to_address, BlobTransaction, BlobTransactionNetworkWrapper,
+    BlobTransactionSidecar,
+};
+use majin_blob_state::classify::{ClassChange, ContractClassifier};
+use majin_blob_state::state_root::{ExpectedRoots, StarknetState};
+use majin_blob_state::store::{read_snapshot, StateStore};
+use majin_blob_types::abi::{
+    parse_logs_json, ContinuousMemoryPage, UpdateState, STARKNET_CORE_CONTRACT,
+};
+use majin_blob_types::os_output::OsOutputVersion;
 use majin_blob_types::serde;
-use std::path::PathBuf;
+use majin_blob_types::sidecar::{self, Authentication};
+use majin_blob_types::state_diffs::{self, DataJson};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
 
 use clap::{Parser, Subcommand};
 
@@ -15,22 +51,1226 @@ struct Cli {
 enum Commands {
     /// Recover the original data from the blob data
     Recover {
-        /// The file containing the blob data
+        /// The file containing the blob data, as hex or as a beacon API `blob_sidecars`
+        /// response in JSON or SSZ
         #[arg(short, long, value_name = "FILE", required = true)]
         blob_file: Option<PathBuf>,
+        /// The root of the block the sidecars must belong to, as hex, from a trusted source
+        /// such as the `parentBeaconBlockRoot` of the next execution block. Without it, the
+        /// sidecars are only checked to be consistent with their own header, which does not
+        /// authenticate them
+        #[arg(long, value_parser = parse_hex::<32>)]
+        block_root: Option<[u8; 32]>,
+        /// A JSON file listing the storage variables of contracts or classes, and the map
+        /// keys to try, to label the storage keys of the state diffs
+        #[arg(long, value_name = "FILE")]
+        labels: Option<PathBuf>,
+        /// Annotate storage values with their likely meanings, such as short strings,
+        /// timestamps or addresses, and how confident each is
+        #[arg(long)]
+        interpret: bool,
+        #[command(flatten)]
+        names: NamesArgs,
     },
+    /// Check the Starknet KZG point evaluation claims of a state update against its blobs
+    CheckKzg {
+        /// The files holding the blobs of the state update, in order, as hex or as beacon
+        /// API `blob_sidecars` responses
+        #[arg(short, long = "blob-file", value_name = "FILE", required = true)]
+        blob_files: Vec<PathBuf>,
+        /// The evaluation point x from the OS output
+        #[arg(long, value_parser = parse_felt)]
+        x: BigUint,
+        /// The low 128 bits of the evaluation y from the OS output, once per blob
+        #[arg(long, value_parser = parse_felt, required = true)]
+        y_low: Vec<BigUint>,
+        /// The high 128 bits of the evaluation y from the OS output, once per blob
+        #[arg(long, value_parser = parse_felt, required = true)]
+        y_high: Vec<BigUint>,
+        /// The low 192 bits of the KZG commitment from the OS output, once per blob
+        #[arg(long, value_parser = parse_felt, required = true)]
+        commitment_low: Vec<BigUint>,
+        /// The high 192 bits of the KZG commitment from the OS output, once per blob
+        #[arg(long, value_parser = parse_felt, required = true)]
+        commitment_high: Vec<BigUint>,
+    },
+    /// Compute what blobs cost at a given excess blob gas
+    BlobFee {
+        /// The excess blob gas of the block including the blobs
+        #[arg(short, long)]
+        excess_blob_gas: u64,
+        /// The number of blobs
+        #[arg(short, long, default_value_t = 1)]
+        blobs: u64,
+        /// The fork of the block (cancun or prague)
+        #[arg(short, long, default_value_t = Fork::Cancun)]
+        fork: Fork,
+    },
+    /// Decode a blob transaction in network form and recover the data of its blobs
+    DecodeTx {
+        /// The raw transaction as hex, `@FILE` to read it from a file, or `-` to read it
+        /// from stdin
+        raw: String,
+    },
+    /// Encode state diffs into blobs and build a blob transaction carrying them
+    BuildTx(BuildTxArgs),
+    /// Fetch the blobs of a block from a beacon node and recover their state diffs
+    Fetch {
+        #[command(flatten)]
+        beacon: BeaconArgs,
+        /// The block, as a slot, a block root, `head` or `finalized`
+        #[arg(long, default_value = "head")]
+        block: BlockId,
+        /// Only recover the blobs with these versioned hashes, in this order
+        #[arg(long = "versioned-hash", value_parser = parse_hex::<32>)]
+        versioned_hashes: Vec<[u8; 32]>,
+    },
+    /// Fetch an L1 transaction publishing a state update and recover its state diffs
+    Resolve {
+        /// The hash of the L1 transaction, as hex
+        #[arg(value_parser = parse_hex::<32>)]
+        transaction_hash: [u8; 32],
+        /// The URL of the JSON-RPC API of the execution node
+        #[arg(long, default_value = "http://localhost:8545")]
+        rpc_url: String,
+        #[command(flatten)]
+        beacon: BeaconArgs,
+    },
+    /// Recover the state diffs of a state update published in calldata, before blobs
+    DecodeCalldata {
+        /// The file holding the input of the `updateState` transaction, as hex or as an
+        /// `eth_getTransactionByHash` response
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+        /// The files holding the inputs of the `registerContinuousMemoryPage` transactions
+        /// publishing the data, in the same formats. Without pages, the data is read from
+        /// the program output
+        #[arg(short, long = "page-file", value_name = "FILE")]
+        page_files: Vec<PathBuf>,
+        /// The Starknet version of the OS output, which sets its layout
+        #[arg(long, default_value_t = OsOutputVersion::V0_13_0)]
+        os_version: OsOutputVersion,
+    },
+    /// Decode an `updateStateKzgDA` call and its events, and check them against its blobs
+    DecodeL1 {
+        /// The file holding the input of the transaction, as hex or as an
+        /// `eth_getTransactionByHash` response
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+        /// The file holding the receipt of the transaction, as an
+        /// `eth_getTransactionReceipt` response, the receipt or its logs
+        #[arg(short, long, value_name = "FILE")]
+        receipt: Option<PathBuf>,
+        /// The files holding the blobs of the transaction, as hex or as beacon API
+        /// `blob_sidecars` responses. Sidecars are matched to the claims by commitment
+        #[arg(short, long = "blob-file", value_name = "FILE", required = true)]
+        blob_files: Vec<PathBuf>,
+        /// The contract whose events are decoded, as hex. Defaults to the Starknet core
+        /// contract on mainnet
+        #[arg(long, value_parser = parse_hex::<20>)]
+        contract: Option<[u8; 20]>,
+        /// The Starknet version of the OS output, which sets its layout
+        #[arg(long, default_value_t = OsOutputVersion::V0_13_2)]
+        os_version: OsOutputVersion,
+    },
+    /// Follow the chain and decode the state updates published in blobs as blocks arrive
+    Watch(WatchArgs),
+    /// Rebuild the Starknet state from state diffs in a local database, and query it
+    State(StateArgs),
+    /// Compute the state diff commitment of block headers, over the state diffs of a range
+    /// of blocks
+    StateDiffCommitment {
+        /// The state diffs of the range, in order, as state diffs JSON or blobs
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// The commitment to check the state diffs against
+        #[arg(long, value_parser = parse_felt)]
+        expected: Option<BigUint>,
+    },
+    /// Tell the contracts deployed by state updates from the contracts whose class was
+    /// replaced
+    Classify {
+        /// The state updates, in order, as state diffs JSON or blobs
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// A snapshot of the updates preceding them, as written by `state export`
+        #[arg(long, value_name = "FILE")]
+        snapshot: Option<PathBuf>,
+        /// The updates, with the snapshot if any, start from genesis. Otherwise, new
+        /// classes of contracts not seen before are reported as unknown
+        #[arg(long)]
+        from_genesis: bool,
+        #[command(flatten)]
+        names: NamesArgs,
+    },
+    /// Recover the map keys of storage writes from the addresses seen in the same state
+    /// diffs, such as the owners of balances
+    ResolveKeys {
+        /// The state diffs, as JSON or blobs
+        file: PathBuf,
+        /// The names of the storage variables to try, ERC20 balances and allowances by
+        /// default
+        #[arg(long = "variable", value_name = "NAME")]
+        variables: Vec<String>,
+        /// The number of map levels to try, 2 for allowances. This computes one Pedersen
+        /// hash per variable and per combination of addresses, variables x addresses^depth,
+        /// so a diff with a thousand addresses takes a million hashes per variable at depth 2
+        #[arg(long, default_value_t = 1)]
+        depth: usize,
+        #[command(flatten)]
+        names: NamesArgs,
+    },
+    /// Report the new ERC20 balances written by state diffs, ETH and STRK by default
+    Balances(BalancesArgs),
+}
+
+#[derive(clap::Args)]
+struct NamesArgs {
+    /// Name the well-known contracts and classes of the output, printed under `names_json`
+    /// by `recover` and under `names` in each entry of the other reports
+    #[arg(long)]
+    names: bool,
+    /// The network of the built-in names, mainnet or sepolia
+    #[arg(long, default_value = "mainnet")]
+    network: Network,
+    /// A label file naming contracts and classes, as JSON or TOML, layered on top of the
+    /// built-in names in order. Implies `--names`
+    #[arg(long = "registry", value_name = "FILE")]
+    registries: Vec<PathBuf>,
+}
+
+impl NamesArgs {
+    /// Builds the registry of names, if names are requested.
+    fn registry(&self) -> Result<Option<LabelRegistry>, String> {
+        if !self.names && self.registries.is_empty() {
+            return Ok(None);
+        }
+        let mut registry = LabelRegistry::defaults(self.network);
+        for file in &self.registries {
+            let layer =
+                LabelRegistry::load(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+            registry.extend(layer);
+        }
+        Ok(Some(registry))
+    }
+}
+
+#[derive(clap::Args)]
+struct BalancesArgs {
+    /// The state diffs, as JSON or blobs
+    file: PathBuf,
+    /// A token to report on, as `NAME=ADDRESS`
+    #[arg(long = "token", value_name = "NAME=ADDRESS", value_parser = parse_token)]
+    tokens: Vec<Token>,
+    /// Only report on the tokens given with `--token`
+    #[arg(long)]
+    no_default_tokens: bool,
+    /// An account to look for, on top of the addresses seen in the state diffs
+    #[arg(long = "account", value_name = "ADDRESS", value_parser = parse_felt)]
+    accounts: Vec<BigUint>,
+    /// A state database holding the state before the state diffs, to compute deltas
+    #[arg(long, value_name = "FILE", conflicts_with = "snapshot")]
+    db: Option<PathBuf>,
+    /// The update of the database after which the previous state is read. Defaults to
+    /// the latest update
+    #[arg(long, requires = "db")]
+    at: Option<u64>,
+    /// A snapshot of the updates before the state diffs, as written by `state export`, to
+    /// compute deltas
+    #[arg(long, value_name = "FILE")]
+    snapshot: Option<PathBuf>,
+    #[command(flatten)]
+    names: NamesArgs,
+}
+
+#[derive(clap::Args)]
+struct StateArgs {
+    /// The database file holding the state
+    #[arg(long, value_name = "FILE")]
+    db: PathBuf,
+    #[command(subcommand)]
+    command: StateCommand,
+}
+
+#[derive(Subcommand)]
+enum StateCommand {
+    /// Apply state updates in order, each file holding state diffs as JSON in the format
+    /// printed by `recover`, or blobs in any format `recover` accepts
+    Apply {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Print the value of a storage slot
+    Storage {
+        #[arg(value_parser = parse_felt)]
+        address: BigUint,
+        #[arg(value_parser = parse_felt)]
+        key: BigUint,
+        /// The update after which the state is read. Defaults to the latest update
+        #[arg(long)]
+        at: Option<u64>,
+    },
+    /// Print the nonce of a contract
+    Nonce {
+        #[arg(value_parser = parse_felt)]
+        address: BigUint,
+        /// The update after which the state is read. Defaults to the latest update
+        #[arg(long)]
+        at: Option<u64>,
+    },
+    /// Print the class hash of a contract
+    ClassHash {
+        #[arg(value_parser = parse_felt)]
+        address: BigUint,
+        /// The update after which the state is read. Defaults to the latest update
+        #[arg(long)]
+        at: Option<u64>,
+    },
+    /// Print the declared classes
+    Classes {
+        /// The update after which the state is read. Defaults to the latest update
+        #[arg(long)]
+        at: Option<u64>,
+    },
+    /// Print the roots of the state tries, and check them against the expected ones. The
+    /// updates of the state must start from genesis
+    Root {
+        /// The update after which the state is read. Defaults to the latest update
+        #[arg(long)]
+        at: Option<u64>,
+        /// The expected global state root, such as the new root of the OS output
+        #[arg(long, value_parser = parse_felt)]
+        expected_root: Option<BigUint>,
+        /// The expected root of the contracts trie
+        #[arg(long, value_parser = parse_felt)]
+        contracts_root: Option<BigUint>,
+        /// The expected root of the classes trie
+        #[arg(long, value_parser = parse_felt)]
+        classes_root: Option<BigUint>,
+    },
+    /// Write a snapshot of the state, as the JSON lines of the state diffs of its updates
+    Export {
+        /// The snapshot file. Defaults to stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Apply the updates of a snapshot that the state does not have yet
+    Import {
+        /// The snapshot file
+        input: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+struct WatchArgs {
+    /// The URL of the JSON-RPC API of the execution node
+    #[arg(long, default_value = "http://localhost:8545")]
+    rpc_url: String,
+    #[command(flatten)]
+    beacon: BeaconArgs,
+    /// The contract the state updates are sent to, as hex. Defaults to the Starknet core
+    /// contract on mainnet
+    #[arg(long, value_parser = parse_hex::<20>)]
+    contract: Option<[u8; 20]>,
+    /// The first block to process, when there is no checkpoint. Defaults to the latest block
+    #[arg(long)]
+    from_block: Option<u64>,
+    /// The file the progress is saved to, and resumed from
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+    /// The file the records are appended to, one JSON object per line. Defaults to stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// The delay between two polls of the execution node, in seconds
+    #[arg(long, default_value_t = 12)]
+    interval: u64,
+}
+
+#[derive(clap::Args)]
+struct BeaconArgs {
+    /// The URL of the beacon node
+    #[arg(long, default_value = "http://localhost:5052")]
+    beacon_url: String,
+    /// The timeout of each request, in seconds
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+    /// How many times a failed request is retried
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+    /// The directory caching the blobs of blocks fetched by root. Defaults to the user cache
+    /// directory
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+    /// Do not cache blobs on disk
+    #[arg(long, conflicts_with = "cache_dir")]
+    no_cache: bool,
+}
+
+impl BeaconArgs {
+    fn http_config(&self) -> HttpConfig {
+        HttpConfig {
+            timeout: Duration::from_secs(self.timeout),
+            retries: self.retries,
+            ..HttpConfig::default()
+        }
+    }
+
+    fn client(&self) -> BeaconClient {
+        let client = BeaconClient::new(&self.beacon_url, self.http_config());
+        if self.no_cache {
+            return client;
+        }
+        match self.cache_dir.clone().or_else(DiskCache::default_dir) {
+            Some(dir) => client.with_cache(DiskCache::new(dir)),
+            None => client,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct BuildTxArgs {
+    /// The file containing the state diffs, as JSON in the format printed by `recover`
+    #[arg(short, long, value_name = "FILE")]
+    state_diffs_file: PathBuf,
+    /// The chain id of the network
+    #[arg(long)]
+    chain_id: u64,
+    /// The nonce of the sender
+    #[arg(long, default_value_t = 0)]
+    nonce: u64,
+    /// The address the transaction is sent to
+    #[arg(long, value_parser = parse_hex::<20>)]
+    to: [u8; 20],
+    /// The calldata of the transaction, as hex
+    #[arg(long, default_value = "")]
+    data: String,
+    /// The gas limit of the transaction
+    #[arg(long, default_value_t = 100_000)]
+    gas_limit: u64,
+    /// The maximum fee per gas, in wei
+    #[arg(long, default_value_t = 30_000_000_000)]
+    max_fee_per_gas: u128,
+    /// The maximum priority fee per gas, in wei
+    #[arg(long, default_value_t = 1_000_000_000)]
+    max_priority_fee_per_gas: u128,
+    /// The maximum fee per blob gas, in wei
+    #[arg(long, default_value_t = 1_000_000_000)]
+    max_fee_per_blob_gas: u128,
+    /// The secret key signing the transaction, as hex. The transaction is left unsigned
+    /// without it
+    #[arg(long, value_parser = parse_hex::<32>)]
+    private_key: Option<[u8; 32]>,
+}
+
+/// Parse a felt given either as a decimal string or as a `0x`-prefixed hex string.
+fn parse_felt(value: &str) -> Result<BigUint, String> {
+    state_diffs::parse_felt(value).map_err(|e| format!("invalid felt {}: {}", value, e))
+}
+
+/// Parse a fixed-size byte string given as hex, with or without `0x` prefix.
+fn parse_hex<const N: usize>(value: &str) -> Result<[u8; N], String> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| format!("invalid hex {}: {}", value, e))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("expected {} bytes, got {}", N, value))
+}
+
+/// Encode state diffs into blobs and build the blob transaction carrying them, signed if
+/// a secret key is given.
+fn build_tx(args: BuildTxArgs, state_diffs: &DataJson) -> Result<serde_json::Value, String> {
+    let data = hex::decode(args.data.trim_start_matches("0x"))
+        .map_err(|e| format!("invalid calldata: {}", e))?;
+    let encoded = serde::encode_state_diffs(state_diffs).map_err(|e| e.to_string())?;
+    let blobs = blob::encode_blobs(&encoded).map_err(|e| e.to_string())?;
+    let sidecar = BlobTransactionSidecar::from_blobs(blobs).map_err(|e| e.to_string())?;
+    let mut transaction = BlobTransaction {
+        chain_id: args.chain_id,
+        nonce: args.nonce,
+        max_priority_fee_per_gas: args.max_priority_fee_per_gas,
+        max_fee_per_gas: args.max_fee_per_gas,
+        gas_limit: args.gas_limit,
+        to: args.to,
+        value: BigUint::from(0u32),
+        data,
+        access_list: Vec::new(),
+        max_fee_per_blob_gas: args.max_fee_per_blob_gas,
+        blob_versioned_hashes: sidecar.versioned_hashes(),
+        signature: None,
+    };
+    let versioned_hashes: Vec<String> = transaction
+        .blob_versioned_hashes
+        .iter()
+        .map(|hash| format!("0x{}", hex::encode(hash)))
+        .collect();
+    let mut output = serde_json::json!({
+        "blob_count": sidecar.blobs.len(),
+        "blob_versioned_hashes": versioned_hashes,
+        "signing_hash": format!("0x{}", hex::encode(transaction.signing_hash())),
+        "unsigned_transaction": format!("0x{}", hex::encode(transaction.encode())),
+    });
+
+    if let Some(private_key) = args.private_key {
+        transaction.sign(&private_key).map_err(|e| e.to_string())?;
+        let sender = secret_key_to_address(&private_key).map_err(|e| e.to_string())?;
+        let wrapper = BlobTransactionNetworkWrapper {
+            transaction,
+            sidecar,
+        };
+        let raw = wrapper.encode().map_err(|e| e.to_string())?;
+        output["from"] = format!("0x{}", hex::encode(sender)).into();
+        output["transaction_hash"] =
+            format!("0x{}", hex::encode(wrapper.transaction.hash())).into();
+        output["raw_transaction"] = format!("0x{}", hex::encode(raw)).into();
+    }
+    Ok(output)
+}
+
+/// Decode a raw blob transaction in network form, check its blobs and recover the state
+/// diffs they carry.
+fn decode_tx(raw_hex: &str) -> Result<serde_json::Value, String> {
+    let raw = hex::decode(raw_hex.trim().trim_start_matches("0x"))
+        .map_err(|e| format!("invalid hex: {}", e))?;
+    let (transaction, sidecar) = decode_blob_transaction(&raw).map_err(|e| e.to_string())?;
+    let sidecar = sidecar.ok_or("the transaction is not in network form, it carries no blobs")?;
+    sidecar
+        .verify_versioned_hashes(&transaction.blob_versioned_hashes)
+        .map_err(|e| e.to_string())?;
+    sidecar.verify_kzg_proofs().map_err(|e| e.to_string())?;
+
+    let original_data = blob::recover_blobs(sidecar.blobs);
+    let state_diffs = serde::parse_state_diffs(&original_data);
+    Ok(serde_json::json!({
+        "transaction_hash": format!("0x{}", hex::encode(transaction.hash())),
+        "chain_id": transaction.chain_id,
+        "nonce": transaction.nonce,
+        "signed": transaction.signature.is_some(),
+        "to": format!("0x{}", hex::encode(transaction.to)),
+        "max_fee_per_blob_gas": transaction.max_fee_per_blob_gas.to_string(),
+        "blob_versioned_hashes": sidecar
+            .commitments
+            .iter()
+            .map(|commitment| format!("0x{}", hex::encode(kzg_to_versioned_hash(commitment))))
+            .collect::<Vec<_>>(),
+        "state_diffs": state_diffs,
+    }))
+}
+
+/// Reads the input of a transaction, given as hex or as an `eth_getTransactionByHash`
+/// response.
+fn parse_transaction_input(input: &str) -> Result<Vec<u8>, String> {
+    let input = match input.trim_start().starts_with('{') {
+        true => {
+            let transaction: serde_json::Value =
+                serde_json::from_str(input).map_err(|e| e.to_string())?;
+            let transaction = transaction.get("result").unwrap_or(&transaction);
+            transaction["input"]
+                .as_str()
+                .ok_or("the transaction has no input")?
+                .to_string()
+        }
+        false => input.to_string(),
+    };
+    hex::decode(input.trim().trim_start_matches("0x")).map_err(|e| format!("invalid input: {}", e))
+}
+
+/// Recovers the state diffs of an `updateState` transaction from its input and the inputs
+/// of the transactions registering its memory pages.
+fn decode_calldata(
+    input: &str,
+    pages: &[String],
+    version: OsOutputVersion,
+) -> Result<DataJson, String> {
+    let call = UpdateState::decode(&parse_transaction_input(input)?).map_err(|e| e.to_string())?;
+    let pages = pages
+        .iter()
+        .map(|page| {
+            ContinuousMemoryPage::decode(&parse_transaction_input(page)?).map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    decode_legacy_state_update(&call, &pages, version).map_err(|e| e.to_string())
+}
+
+/// Decodes an `updateStateKzgDA` transaction from its input, its logs and its blobs.
+fn decode_l1(
+    input: &str,
+    logs: Option<&str>,
+    blob_inputs: &[Vec<u8>],
+    contract: &[u8; 20],
+    version: OsOutputVersion,
+) -> Result<L1StateUpdate, String> {
+    let input = parse_transaction_input(input)?;
+    let logs = match logs {
+        Some(logs) => parse_logs_json(logs).map_err(|e| e.to_string())?,
+        None => Vec::new(),
+    };
+    let blob_inputs = blob_inputs
+        .iter()
+        .map(|blob_input| sidecar::parse_blob_input(blob_input))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    decode_state_update(&input, &logs, blob_inputs, contract, version).map_err(|e| e.to_string())
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    if let Some(Commands::Recover { blob_file }) = cli.command {
-        let blob_file = blob_file.unwrap();
-        let blob_data = serde::parse_file_to_blob_data(blob_file.to_str().unwrap());
-        let original_data = blob::recover(blob_data);
-        let state_diffs = serde::parse_state_diffs(original_data.as_slice());
-        let state_diffs_json = serde::to_json(state_diffs);
-        println!("state_diffs_json {}", state_diffs_json);
+    match cli.command {
+        Some(Commands::Recover {
+            blob_file,
+            block_root,
+            labels,
+            interpret,
+            names,
+        }) => {
+            let input = std::fs::read(blob_file.unwrap()).expect("Failed to read file");
+            let blob_input = sidecar::parse_blob_input(&input).unwrap_or_else(|e| {
+                eprintln!("invalid blob file: {}", e);
+                process::exit(2);
+            });
+            if let sidecar::BlobInput::Sidecars(sidecars) = &blob_input {
+                match sidecar::verify_sidecars(sidecars, block_root.as_ref()) {
+                    Ok(Authentication::Authenticated) => {}
+                    Ok(Authentication::Unauthenticated) => warn_unauthenticated(),
+                    Err(e) => {
+                        eprintln!("sidecars could not be authenticated: {}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+            let original_data = blob::recover_blobs(blob_input.into_blobs());
+            let state_diffs = serde::parse_state_diffs(original_data.as_slice());
+            let storage_labels = labels.map(|labels| {
+                label_storage(&labels, &state_diffs).unwrap_or_else(|e| {
+                    eprintln!("invalid labels: {}", e);
+                    process::exit(2);
+                })
+            });
+            let registry = names.registry().unwrap_or_else(|e| {
+                eprintln!("invalid label file {}", e);
+                process::exit(2);
+            });
+            let interpretations = interpret.then(|| {
+                let mut interpreter = ValueInterpreter::from_state_diffs(&state_diffs);
+                if let Some(registry) = &registry {
+                    interpreter.extend_addresses(registry.addresses().cloned());
+                }
+                interpreter.interpret_state_diffs(&state_diffs)
+            });
+            let names = registry.map(|registry| registry.names(&state_diffs));
+            let state_diffs_json = serde::to_json(state_diffs);
+            println!("state_diffs_json {}", state_diffs_json);
+            if let Some(names) = names {
+                println!("names_json {}", serde_json::to_string(&names).unwrap());
+            }
+            if let Some(interpretations) = interpretations {
+                println!(
+                    "storage_interpretations_json {}",
+                    serde_json::to_string(&interpretations).unwrap()
+                );
+            }
+            if let Some(storage_labels) = storage_labels {
+                println!(
+                    "storage_labels_json {}",
+                    serde_json::to_string(&storage_labels).unwrap()
+                );
+            }
+        }
+        Some(Commands::CheckKzg {
+            blob_files,
+            x,
+            y_low,
+            y_high,
+            commitment_low,
+            commitment_high,
+        }) => {
+            if [&y_high, &commitment_low, &commitment_high]
+                .iter()
+                .any(|felts| felts.len() != y_low.len())
+            {
+                eprintln!("invalid claim: every blob needs a y and a commitment");
+                process::exit(2);
+            }
+            let claims = (0..y_low.len())
+                .map(|i| {
+                    StarknetKzgClaim::from_output_felts(
+                        x.clone(),
+                        &commitment_low[i],
+                        &commitment_high[i],
+                        &y_low[i],
+                        &y_high[i],
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_else(|e| {
+                    eprintln!("invalid claim: {}", e);
+                    process::exit(2);
+                });
+            let mut blobs = Vec::new();
+            for path in &blob_files {
+                let input = std::fs::read(path).unwrap_or_else(|e| {
+                    eprintln!("failed to read {}: {}", path.display(), e);
+                    process::exit(2);
+                });
+                blobs.extend(sidecar::parse_blobs(&input).unwrap_or_else(|e| {
+                    eprintln!("invalid blob file {}: {}", path.display(), e);
+                    process::exit(2);
+                }));
+            }
+            let original_data = blob::recover_blobs(blobs.clone());
+            let data = &original_data[..serde::state_diffs_len(&original_data)];
+            let reports = check_point_evaluation(&blobs, &claims, Some(data)).unwrap_or_else(|e| {
+                eprintln!("failed to check the claims: {}", e);
+                process::exit(2);
+            });
+            println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+            if !reports.iter().all(|report| report.is_valid()) {
+                process::exit(1);
+            }
+        }
+        Some(Commands::BlobFee {
+            excess_blob_gas,
+            blobs,
+            fork,
+        }) => {
+            let blob_fee = fee::blob_fee(fork, excess_blob_gas, blobs).unwrap_or_else(|e| {
+                eprintln!("invalid blob count: {}", e);
+                process::exit(2);
+            });
+            println!("{}", serde_json::to_string_pretty(&blob_fee).unwrap());
+        }
+        Some(Commands::DecodeTx { raw }) => {
+            let raw = if raw == "-" {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input).unwrap();
+                input
+            } else if let Some(path) = raw.strip_prefix('@') {
+                std::fs::read_to_string(path).expect("Failed to read file")
+            } else {
+                raw
+            };
+            match decode_tx(&raw) {
+                Ok(decoded) => println!("{}", serde_json::to_string_pretty(&decoded).unwrap()),
+                Err(e) => {
+                    eprintln!("invalid blob transaction: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Commands::BuildTx(args)) => {
+            let json =
+                std::fs::read_to_string(&args.state_diffs_file).expect("Failed to read file");
+            let state_diffs: DataJson = serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("invalid state diffs: {}", e);
+                process::exit(2);
+            });
+            match build_tx(args, &state_diffs) {
+                Ok(built) => println!("{}", serde_json::to_string_pretty(&built).unwrap()),
+                Err(e) => {
+                    eprintln!("failed to build the transaction: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Commands::Fetch {
+            beacon,
+            block,
+            versioned_hashes,
+        }) => match beacon.client().state_diffs(&block, &versioned_hashes) {
+            Ok((state_diffs, authentication)) => {
+                if authentication == Authentication::Unauthenticated {
+                    warn_unauthenticated();
+                }
+                println!("{}", serde::to_json(state_diffs))
+            }
+            Err(e) => {
+                eprintln!("failed to fetch the blobs of block {}: {}", block, e);
+                process::exit(1);
+            }
+        },
+        Some(Commands::Resolve {
+            transaction_hash,
+            rpc_url,
+            beacon,
+        }) => {
+            let execution = ExecutionClient::new(&rpc_url, beacon.http_config());
+            match resolve_state_update(&execution, &beacon.client(), &transaction_hash) {
+                Ok(state_update) => {
+                    println!("{}", serde_json::to_string_pretty(&state_update).unwrap())
+                }
+                Err(e) => {
+                    eprintln!(
+                        "failed to resolve transaction 0x{}: {}",
+                        hex::encode(transaction_hash),
+                        e
+                    );
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Commands::DecodeCalldata {
+            input,
+            page_files,
+            os_version,
+        }) => {
+            let read = |path: &PathBuf| {
+                std::fs::read_to_string(path).unwrap_or_else(|e| {
+                    eprintln!("failed to read {}: {}", path.display(), e);
+                    process::exit(2);
+                })
+            };
+            let pages: Vec<String> = page_files.iter().map(read).collect();
+            match decode_calldata(&read(&input), &pages, os_version) {
+                Ok(state_diffs) => println!("state_diffs_json {}", serde::to_json(state_diffs)),
+                Err(e) => {
+                    eprintln!("failed to decode the state update: {}", e);
+                    process::exit(2);
+                }
+            }
+        }
+        Some(Commands::DecodeL1 {
+            input,
+            receipt,
+            blob_files,
+            contract,
+            os_version,
+        }) => {
+            let read = |path: &PathBuf| {
+                std::fs::read(path).unwrap_or_else(|e| {
+                    eprintln!("failed to read {}: {}", path.display(), e);
+                    process::exit(2);
+                })
+            };
+            let input = String::from_utf8_lossy(&read(&input)).into_owned();
+            let logs = receipt.map(|receipt| String::from_utf8_lossy(&read(&receipt)).into_owned());
+            let blob_inputs: Vec<Vec<u8>> = blob_files.iter().map(read).collect();
+            match decode_l1(
+                &input,
+                logs.as_deref(),
+                &blob_inputs,
+                &contract.unwrap_or(STARKNET_CORE_CONTRACT),
+                os_version,
+            ) {
+                Ok(state_update) => {
+                    println!("{}", serde_json::to_string_pretty(&state_update).unwrap());
+                    if !state_update.is_valid() {
+                        process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("failed to decode the state update: {}", e);
+                    process::exit(2);
+                }
+            }
+        }
+        Some(Commands::Classify {
+            files,
+            snapshot,
+            from_genesis,
+            names,
+        }) => match classify(&files, snapshot.as_ref(), from_genesis, &names) {
+            Ok(report) => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+            Err(e) => {
+                eprintln!("failed to classify the state updates: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Commands::ResolveKeys {
+            file,
+            variables,
+            depth,
+            names,
+        }) => match resolve_keys(&file, variables, depth, &names) {
+            Ok(report) => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+            Err(e) => {
+                eprintln!("failed to resolve the storage keys: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Commands::Balances(args)) => match balances(args) {
+            Ok(report) => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+            Err(e) => {
+                eprintln!("failed to report the balances: {}", e);
+                process::exit(2);
+            }
+        },
+        Some(Commands::StateDiffCommitment { files, expected }) => {
+            let mut state_diffs = Vec::new();
+            for file in &files {
+                let input = std::fs::read(file).unwrap_or_else(|e| {
+                    eprintln!("failed to read {}: {}", file.display(), e);
+                    process::exit(2);
+                });
+                state_diffs.push(read_state_diffs(&input).unwrap_or_else(|e| {
+                    eprintln!("invalid state diffs {}: {}", file.display(), e);
+                    process::exit(2);
+                }));
+            }
+            let commitment = state_diff_commitment(&state_diffs).unwrap_or_else(|e| {
+                eprintln!("invalid state diffs: {}", e);
+                process::exit(2);
+            });
+            println!("0x{}", commitment.to_str_radix(16));
+            if expected.is_some_and(|expected| expected != commitment) {
+                eprintln!("the state diff commitment is not the expected one");
+                process::exit(1);
+            }
+        }
+        Some(Commands::State(args)) => {
+            if let Err(e) = state(args) {
+                eprintln!("state command failed: {}", e);
+                process::exit(1);
+            }
+        }
+        Some(Commands::Watch(args)) => {
+            if let Err(e) = watch(args) {
+                eprintln!("failed to watch the chain: {}", e);
+                process::exit(1);
+            }
+        }
+        None => {}
+    }
+}
+
+/// Labels the storage keys of state diffs with the variables listed in a label file.
+fn label_storage(labels: &Path, state_diffs: &DataJson) -> Result<StorageLabels, String> {
+    let json = std::fs::read_to_string(labels).map_err(|e| e.to_string())?;
+    let specs = LabelSpec::parse_json(&json).map_err(|e| e.to_string())?;
+    Ok(StorageLabeller::new(&specs).label_state_diffs(state_diffs, &HashMap::new()))
+}
+
+/// Inverts the storage keys of map entries keyed by addresses seen in state diffs, and
+/// labels them.
+fn resolve_keys(
+    file: &Path,
+    variables: Vec<String>,
+    depth: usize,
+    names: &NamesArgs,
+) -> Result<serde_json::Value, String> {
+    let registry = names.registry()?;
+    let input = std::fs::read(file).map_err(|e| e.to_string())?;
+    let state_diffs = read_state_diffs(&input)?;
+    let resolver = match variables.is_empty() {
+        true => PreimageResolver::new(DEFAULT_VARIABLES.map(str::to_string), depth),
+        false => PreimageResolver::new(variables, depth),
+    };
+    let resolved = resolver.resolve(&state_diffs);
+    let mut report = with_names(&resolved, registry.as_ref(), |resolved| {
+        std::iter::once(&resolved.contract)
+            .chain(resolved.keys.iter().map(|key| &key.value))
+            .collect()
+    });
+    for (json, resolved) in report.iter_mut().zip(&resolved) {
+        json["label"] = serde_json::json!(resolved.label());
+    }
+    Ok(serde_json::Value::Array(report))
+}
+
+/// Reports the new ERC20 balances written by state diffs, named if requested.
+fn balances(args: BalancesArgs) -> Result<serde_json::Value, String> {
+    let registry = args.names.registry()?;
+    let changes = balance_changes_of(args)?;
+    let report = with_names(&changes, registry.as_ref(), |change| {
+        vec![&change.token_address, &change.account]
+    });
+    Ok(serde_json::Value::Array(report))
+}
+
+/// Computes the new ERC20 balances written by state diffs, with their deltas when the
+/// previous state is given.
+fn balance_changes_of(args: BalancesArgs) -> Result<Vec<BalanceChange>, String> {
+    let input = std::fs::read(&args.file).map_err(|e| e.to_string())?;
+    let state_diffs = read_state_diffs(&input)?;
+    let mut tokens = match args.no_default_tokens {
+        true => Vec::new(),
+        false => Token::defaults(),
+    };
+    tokens.extend(args.tokens);
+
+    if let Some(db) = &args.db {
+        let store = StateStore::open(db).map_err(|e| e.to_string())?;
+        let at = match args.at {
+            Some(at) => at,
+            None => store
+                .update_count()
+                .map_err(|e| e.to_string())?
+                .saturating_sub(1),
+        };
+        // The first error of the store, the lookup itself cannot fail
+        let error = std::cell::RefCell::new(None);
+        let previous = |address: &BigUint, key: &BigUint| {
+            store
+                .storage_at(address, key, at)
+                .unwrap_or_else(|e| {
+                    error.borrow_mut().get_or_insert(e.to_string());
+                    None
+                })
+                .unwrap_or_default()
+        };
+        let changes = balance_changes(&state_diffs, &tokens, &args.accounts, Some(&previous));
+        return match error.into_inner() {
+            Some(e) => Err(e),
+            None => Ok(changes),
+        };
+    }
+    if let Some(snapshot) = &args.snapshot {
+        let file = std::fs::File::open(snapshot).map_err(|e| e.to_string())?;
+        let mut previous_state = StateDiff::default();
+        for entry in read_snapshot(std::io::BufReader::new(file)) {
+            let (_, state_diffs) = entry.map_err(|e| e.to_string())?;
+            previous_state.merge(&StateDiff::from_data_json(&state_diffs));
+        }
+        let previous = |address: &BigUint, key: &BigUint| {
+            previous_state
+                .storage_diffs
+                .get(address)
+                .and_then(|storage| storage.get(key))
+                .cloned()
+                .unwrap_or_default()
+        };
+        return Ok(balance_changes(
+            &state_diffs,
+            &tokens,
+            &args.accounts,
+            Some(&previous),
+        ));
+    }
+    Ok(balance_changes(&state_diffs, &tokens, &args.accounts, None))
+}
+
+/// Parse a token given as `NAME=ADDRESS`.
+fn parse_token(value: &str) -> Result<Token, String> {
+    let (name, address) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=ADDRESS, got {}", value))?;
+    Ok(Token::new(name, parse_felt(address)?))
+}
+
+/// Warns that sidecars could not be tied to a trusted block root.
+fn warn_unauthenticated() {
+    eprintln!("warning: the sidecars are not authenticated, no trusted block root was given");
+}
+
+/// Reads the state diffs of an update, given as JSON or as blobs.
+fn read_state_diffs(input: &[u8]) -> Result<DataJson, String> {
+    if let Ok(state_diffs) = serde_json::from_slice::<DataJson>(input) {
+        return Ok(state_diffs);
+    }
+    let blob_input = sidecar::parse_blob_input(input).map_err(|e| e.to_string())?;
+    if let sidecar::BlobInput::Sidecars(sidecars) = &blob_input {
+        sidecar::verify_sidecars(sidecars, None).map_err(|e| e.to_string())?;
+        warn_unauthenticated();
+    }
+    let original_data = blob::recover_blobs(blob_input.into_blobs());
+    Ok(serde::parse_state_diffs(&original_data))
+}
+
+/// Serializes the entries of a report, adding the names of the felts of each entry under
+/// `names` when a registry is given.
+fn with_names<T: ::serde::Serialize>(
+    entries: &[T],
+    registry: Option<&LabelRegistry>,
+    felts: impl Fn(&T) -> Vec<&BigUint>,
+) -> Vec<serde_json::Value> {
+    entries
+        .iter()
+        .map(|entry| {
+            let mut json = serde_json::to_value(entry).unwrap();
+            if let Some(registry) = registry {
+                json["names"] = serde_json::json!(registry.names_of(felts(entry)));
+            }
+            json
+        })
+        .collect()
+}
+
+/// Classifies the class changes of successive state updates, given as JSON or as blobs.
+fn classify(
+    files: &[PathBuf],
+    snapshot: Option<&PathBuf>,
+    from_genesis: bool,
+    names: &NamesArgs,
+) -> Result<serde_json::Value, String> {
+    let registry = names.registry()?;
+    let mut classifier = match snapshot {
+        Some(path) => {
+            let file = std::fs::File::open(path)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+            ContractClassifier::from_snapshot(std::io::BufReader::new(file), from_genesis)
+                .map_err(|e| e.to_string())?
+        }
+        None if from_genesis => ContractClassifier::from_genesis(),
+        None => ContractClassifier::partial(),
+    };
+    let mut updates = Vec::new();
+    for file in files {
+        let input =
+            std::fs::read(file).map_err(|e| format!("failed to read {}: {}", file.display(), e))?;
+        let state_diffs = read_state_diffs(&input)
+            .map_err(|e| format!("invalid state update {}: {}", file.display(), e))?;
+        let contracts = classifier.classify(&state_diffs);
+        let unknown = contracts
+            .iter()
+            .filter(|contract| contract.class_change == ClassChange::Unknown)
+            .count();
+        let mut update = serde_json::json!({
+            "file": file.display().to_string(),
+            "contracts": contracts,
+            "unknown": unknown,
+        });
+        if let Some(registry) = &registry {
+            update["names"] = serde_json::json!(registry.names(&state_diffs));
+        }
+        updates.push(update);
+    }
+    Ok(serde_json::Value::Array(updates))
+}
+
+/// Runs a state subcommand against the store at `args.db`, printing results as JSON.
+fn state(args: StateArgs) -> Result<(), String> {
+    let store = StateStore::open(&args.db).map_err(|e| e.to_string())?;
+    let latest = || -> Result<u64, String> {
+        let count = store.update_count().map_err(|e| e.to_string())?;
+        Ok(count.saturating_sub(1))
+    };
+    let felt = |value: Option<BigUint>| serde_json::json!(value.map(|v| v.to_str_radix(10)));
+    let result = match args.command {
+        StateCommand::Apply { files } => {
+            let mut updates = Vec::new();
+            for file in files {
+                let input = std::fs::read(&file)
+                    .map_err(|e| format!("failed to read {}: {}", file.display(), e))?;
+                let state_diffs = read_state_diffs(&input)
+                    .map_err(|e| format!("invalid state update {}: {}", file.display(), e))?;
+                updates.push(store.apply(&state_diffs).map_err(|e| e.to_string())?);
+            }
+            serde_json::json!({ "applied": updates })
+        }
+        StateCommand::Storage { address, key, at } => {
+            let at = at.map_or_else(latest, Ok)?;
+            felt(
+                store
+                    .storage_at(&address, &key, at)
+                    .map_err(|e| e.to_string())?,
+            )
+        }
+        StateCommand::Nonce { address, at } => {
+            let at = at.map_or_else(latest, Ok)?;
+            serde_json::json!(store.nonce_at(&address, at).map_err(|e| e.to_string())?)
+        }
+        StateCommand::ClassHash { address, at } => {
+            let at = at.map_or_else(latest, Ok)?;
+            felt(
+                store
+                    .class_hash_at(&address, at)
+                    .map_err(|e| e.to_string())?,
+            )
+        }
+        StateCommand::Classes { at } => {
+            let at = at.map_or_else(latest, Ok)?;
+            let classes = store.declared_classes(at).map_err(|e| e.to_string())?;
+            serde_json::to_value(classes).map_err(|e| e.to_string())?
+        }
+        StateCommand::Root {
+            at,
+            expected_root,
+            contracts_root,
+            classes_root,
+        } => {
+            let at = at.map_or_else(latest, Ok)?;
+            let roots = StarknetState::from_store(&store, at)
+                .and_then(|state| state.roots())
+                .map_err(|e| e.to_string())?;
+            let mismatches = roots.compare(&ExpectedRoots {
+                global_root: expected_root,
+                contracts_root,
+                classes_root,
+                ..ExpectedRoots::default()
+            });
+            let report = serde_json::json!({ "roots": roots, "mismatches": mismatches });
+            if !mismatches.is_empty() {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                return Err("the state roots are not the expected ones".to_string());
+            }
+            report
+        }
+        StateCommand::Export { output } => {
+            let result = match output {
+                Some(path) => std::fs::File::create(&path)
+                    .map_err(|e| format!("failed to create {}: {}", path.display(), e))
+                    .and_then(|file| {
+                        store
+                            .export_snapshot(std::io::BufWriter::new(file))
+                            .map_err(|e| e.to_string())
+                    }),
+                None => store
+                    .export_snapshot(std::io::stdout().lock())
+                    .map_err(|e| e.to_string()),
+            };
+            return result;
+        }
+        StateCommand::Import { input } => {
+            let file = std::fs::File::open(&input)
+                .map_err(|e| format!("failed to read {}: {}", input.display(), e))?;
+            let applied = store
+                .import_snapshot(std::io::BufReader::new(file))
+                .map_err(|e| e.to_string())?;
+            serde_json::json!({ "applied": applied })
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    Ok(())
+}
+
+/// Runs a [`Watcher`] until the process is killed, writing each event as a line of JSON.
+/// Errors while polling are reported and the poll is retried.
+fn watch(args: WatchArgs) -> Result<(), String> {
+    let config = args.beacon.http_config();
+    let execution = ExecutionClient::new(&args.rpc_url, config);
+    let start_block = match args.from_block {
+        Some(block) => block,
+        None => execution.block_number().map_err(|e| e.to_string())?,
+    };
+    // Live blocks can still be reorged, so their blobs are never read from the cache
+    let beacon = BeaconClient::new(&args.beacon.beacon_url, args.beacon.http_config());
+    let mut watcher = Watcher::new(
+        execution,
+        beacon,
+        args.contract.unwrap_or(STARKNET_CORE_CONTRACT),
+        start_block,
+    );
+    if let Some(checkpoint) = &args.checkpoint {
+        watcher = watcher
+            .with_checkpoint(checkpoint)
+            .map_err(|e| e.to_string())?;
+    }
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("failed to open {}: {}", path.display(), e))?,
+        ),
+        None => Box::new(std::io::stdout()),
+    };
+
+    eprintln!("watching from block {}", watcher.next_block());
+    loop {
+        let mut write_error = None;
+        let result = watcher.poll(&mut |event| {
+            let line = serde_json::to_string(&event).unwrap();
+            if let Err(e) = writeln!(output, "{}", line).and_then(|_| output.flush()) {
+                write_error.get_or_insert(e);
+            }
+        });
+        if let Some(e) = write_error {
+            return Err(format!("failed to write a record: {}", e));
+        }
+        if let Err(e) = result {
+            eprintln!("failed to process block {}: {}", watcher.next_block(), e);
+        }
+        std::thread::sleep(Duration::from_secs(args.interval));
     }
 }
 
@@ -44,3 +1284,204 @@ fn test_cli_sn_goerli() {
     println!("{}", state_diffs_json);
     // TODO assert result of old version of sn_goerli
 }
+
+#[test]
+fn test_cli_decode_tx() {
+    use majin_blob_eip_4844::kzg::{blob_to_commitment, compute_blob_proof};
+    use majin_blob_eip_4844::transaction::{
+        BlobTransaction, BlobTransactionNetworkWrapper, BlobTransactionSidecar, Signature,
+        WRAPPER_VERSION_BLOB_PROOFS,
+    };
+
+    let blob_data = serde::parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+    let commitment = blob_to_commitment(&blob_data).unwrap();
+    let proof = compute_blob_proof(&blob_data, &commitment).unwrap();
+    let wrapper = BlobTransactionNetworkWrapper {
+        transaction: BlobTransaction {
+            chain_id: 1,
+            nonce: 0,
+            max_priority_fee_per_gas: 1,
+            max_fee_per_gas: 1,
+            gas_limit: 21_000,
+            to: [0x11; 20],
+            value: BigUint::from(0u32),
+            data: Vec::new(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: 1,
+            blob_versioned_hashes: vec![kzg_to_versioned_hash(&commitment)],
+            signature: Some(Signature {
+                y_parity: false,
+                r: BigUint::from(1u32),
+                s: BigUint::from(1u32),
+            }),
+        },
+        sidecar: BlobTransactionSidecar {
+            version: WRAPPER_VERSION_BLOB_PROOFS,
+            blobs: vec![blob_data.clone()],
+            commitments: vec![commitment],
+            proofs: vec![proof],
+        },
+    };
+    let raw_hex = hex::encode(wrapper.encode().unwrap());
+
+    let decoded = decode_tx(&raw_hex).unwrap();
+    let state_diffs = serde::parse_state_diffs(&blob::recover(blob_data));
+    assert_eq!(
+        decoded["state_diffs"],
+        serde_json::to_value(&state_diffs).unwrap()
+    );
+
+    let mut tampered = wrapper;
+    tampered.transaction.blob_versioned_hashes[0][31] ^= 1;
+    assert!(decode_tx(&hex::encode(tampered.encode().unwrap())).is_err());
+}
+
+#[test]
+fn test_cli_build_tx() {
+    let blob_data = serde::parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+    let state_diffs = serde::parse_state_diffs(&blob::recover(blob_data));
+    let args = BuildTxArgs {
+        state_diffs_file: PathBuf::new(),
+        chain_id: 1337,
+        nonce: 3,
+        to: [0x11; 20],
+        data: String::new(),
+        gas_limit: 100_000,
+        max_fee_per_gas: 30_000_000_000,
+        max_priority_fee_per_gas: 1_000_000_000,
+        max_fee_per_blob_gas: 1_000_000_000,
+        private_key: Some([0x42; 32]),
+    };
+
+    let built = build_tx(args, &state_diffs).unwrap();
+    let decoded = decode_tx(built["raw_transaction"].as_str().unwrap()).unwrap();
+    assert_eq!(decoded["transaction_hash"], built["transaction_hash"]);
+    assert_eq!(decoded["nonce"], 3);
+    assert_eq!(
+        decoded["state_diffs"],
+        serde_json::to_value(&state_diffs).unwrap()
+    );
+}
+
+#[test]
+fn test_cli_decode_l1() {
+    use majin_blob_core::kzg::{compute_evaluation_point, split_commitment};
+    use majin_blob_eip_4844::kzg::{blob_to_commitment, compute_proof};
+    use majin_blob_types::abi::{event_topic, UpdateStateKzgDa, LOG_STATE_UPDATE};
+    use num_traits::One;
+
+    let blob_file = std::fs::read("../../examples/blob/mainnet.txt").unwrap();
+    let blob_data = serde::parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+    let original_data = blob::recover(blob_data.clone());
+    let data = &original_data[..serde::state_diffs_len(&original_data)];
+    let commitment = blob_to_commitment(&blob_data).unwrap();
+    let x = compute_evaluation_point(data, &[commitment]).unwrap();
+    let (proof, y) = compute_proof(&blob_data, &x).unwrap();
+    let (commitment_low, commitment_high) = split_commitment(&commitment);
+    let mask = (BigUint::one() << 128u32) - 1u32;
+
+    // Header of the OS output, with `use_kzg_da` set, then its KZG segment and messages
+    let mut program_output = vec![BigUint::from(0u32); 10];
+    program_output[8] = BigUint::one();
+    program_output.extend([
+        x,
+        BigUint::one(),
+        commitment_low,
+        commitment_high,
+        &y & mask,
+        y >> 128u32,
+    ]);
+    // One message to L1, none to L2
+    program_output.extend([3u32, 8, 9, 0, 0].map(BigUint::from));
+    let input = UpdateStateKzgDa {
+        program_output,
+        kzg_proofs: vec![proof.to_vec()],
+    }
+    .encode();
+    let logs = format!(
+        r#"{{"logs":[{{"address":"0x{}","topics":["0x{}"],"data":"0x{}{:064x}{}"}}]}}"#,
+        hex::encode(STARKNET_CORE_CONTRACT),
+        hex::encode(event_topic(LOG_STATE_UPDATE)),
+        "11".repeat(32),
+        42,
+        "22".repeat(32),
+    );
+
+    let state_update = decode_l1(
+        &hex::encode(input),
+        Some(&logs),
+        &[blob_file],
+        &STARKNET_CORE_CONTRACT,
+        OsOutputVersion::V0_13_2,
+    )
+    .unwrap();
+    assert!(state_update.is_valid());
+    assert_eq!(state_update.blobs[0].proof_valid, Some(true));
+    assert_eq!(
+        state_update.transition.state_diffs,
+        serde::parse_state_diffs(data)
+    );
+    let json = serde_json::to_value(&state_update).unwrap();
+    assert_eq!(json["events"][0]["log_state_update"]["block_number"], 42);
+    assert_eq!(
+        json["transition"]["messages"]["to_l1"][0]["to_address"],
+        "9"
+    );
+}
+
+#[test]
+fn test_cli_decode_calldata() {
+    let blob_data = serde::parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+    let original_data = blob::recover(blob_data);
+    let data = &original_data[..serde::state_diffs_len(&original_data)];
+    let expected = serde::parse_state_diffs(data);
+
+    // Header of 0.13.0 and no messages, then the state diffs
+    let mut program_output: Vec<BigUint> = [1u32, 2, 100, 4, 6, 0, 0].map(BigUint::from).to_vec();
+    program_output.extend(data.iter().cloned());
+    let call = UpdateState {
+        program_output,
+        onchain_data_hash: BigUint::from(0u32),
+        onchain_data_size: BigUint::from(0u32),
+    };
+    let input = format!("0x{}", hex::encode(call.encode()));
+    let state_diffs = decode_calldata(&input, &[], OsOutputVersion::V0_13_0).unwrap();
+    assert_eq!(state_diffs, expected);
+
+    // The same data in memory pages, given out of order
+    let pages: Vec<String> = data
+        .chunks(data.len() / 2 + 1)
+        .enumerate()
+        .rev()
+        .map(|(i, values)| {
+            let page = ContinuousMemoryPage {
+                start_address: BigUint::from(1000 * (i + 1)),
+                values: values.to_vec(),
+                z: BigUint::from(1u32),
+                alpha: BigUint::from(2u32),
+                prime: BigUint::from(3u32),
+            };
+            format!(
+                r#"{{"result":{{"input":"0x{}"}}}}"#,
+                hex::encode(page.encode())
+            )
+        })
+        .collect();
+    let call = UpdateState {
+        program_output: [1u32, 2, 100, 4, 6, 0, 0].map(BigUint::from).to_vec(),
+        onchain_data_hash: BigUint::from(0u32),
+        onchain_data_size: BigUint::from(data.len()),
+    };
+    let input = hex::encode(call.encode());
+    let state_diffs = decode_calldata(&input, &pages, OsOutputVersion::V0_13_0).unwrap();
+    assert_eq!(state_diffs, expected);
+}
+
+#[test]
+fn test_cli_read_state_diffs() {
+    let blob_file = std::fs::read("../../examples/blob/mainnet.txt").unwrap();
+    let state_diffs = read_state_diffs(&blob_file).unwrap();
+    let json = serde_json::to_vec(&state_diffs).unwrap();
+    assert_eq!(read_state_diffs(&json), Ok(state_diffs));
+    assert!(read_state_diffs(b"not a state update").is_err());
+}
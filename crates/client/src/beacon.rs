@@ -0,0 +1,336 @@
+use std::fmt;
+use std::str::FromStr;
+
+use majin_blob_core::blob;
+use majin_blob_eip_4844::blob::kzg_to_versioned_hash;
+use majin_blob_types::serde::parse_state_diffs;
+use majin_blob_types::sidecar::{
+    parse_sidecars_json, sidecars_to_blobs, verify_sidecars, Authentication, BlobSidecar,
+    SidecarError,
+};
+use majin_blob_types::state_diffs::DataJson;
+use thiserror::Error;
+
+use crate::cache::DiskCache;
+use crate::http::{HttpClient, HttpConfig, HttpError};
+
+/// Cache namespace of `blob_sidecars` responses.
+const SIDECARS_NAMESPACE: &str = "blob_sidecars";
+
+#[derive(Debug, Error)]
+pub enum BeaconError {
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    #[error(transparent)]
+    Sidecar(#[from] SidecarError),
+    #[error("no blob of block {block_id} has versioned hash 0x{}", hex::encode(.versioned_hash))]
+    MissingBlob {
+        block_id: BlockId,
+        versioned_hash: [u8; 32],
+    },
+    #[error("block {0} has no blobs")]
+    NoBlobs(BlockId),
+    #[error("invalid beacon node response: {0}")]
+    InvalidResponse(String),
+}
+
+/// A block identifier, as accepted by the beacon API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockId {
+    Head,
+    Finalized,
+    Slot(u64),
+    Root([u8; 32]),
+}
+
+impl BlockId {
+    /// Whether the block designated never changes. Responses about it can be cached.
+    ///
+    /// Only a block root pins a block: the block at a slot changes when the chain reorgs.
+    pub fn is_immutable(&self) -> bool {
+        matches!(self, BlockId::Root(_))
+    }
+}
+
+impl FromStr for BlockId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "head" => Ok(BlockId::Head),
+            "finalized" => Ok(BlockId::Finalized),
+            _ => match s.strip_prefix("0x") {
+                Some(root) => hex::decode(root)
+                    .ok()
+                    .and_then(|root| root.try_into().ok())
+                    .map(BlockId::Root)
+                    .ok_or_else(|| format!("invalid block root {}", s)),
+                None => s
+                    .parse()
+                    .map(BlockId::Slot)
+                    .map_err(|_| format!("invalid block id {}", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockId::Head => write!(f, "head"),
+            BlockId::Finalized => write!(f, "finalized"),
+            BlockId::Slot(slot) => write!(f, "{}", slot),
+            BlockId::Root(root) => write!(f, "0x{}", hex::encode(root)),
+        }
+    }
+}
+
+/// A client of the beacon API, fetching and authenticating blob sidecars.
+#[derive(Debug, Clone)]
+pub struct BeaconClient {
+    url: String,
+    http: HttpClient,
+    cache: Option<DiskCache>,
+}
+
+impl BeaconClient {
+    /// Builds a client of the beacon node at `url`, without cache.
+    pub fn new(url: &str, config: HttpConfig) -> Self {
+        BeaconClient {
+            url: url.trim_end_matches('/').to_string(),
+            http: HttpClient::new(config),
+            cache: None,
+        }
+    }
+
+    /// Caches the responses about immutable blocks in `cache`, see [`BlockId::is_immutable`].
+    pub fn with_cache(mut self, cache: DiskCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Fetches the blob sidecars of a block and checks them, see [`verify_sidecars`].
+    ///
+    /// # Returns
+    ///
+    /// The sidecars, authenticated if they were fetched by block root and belong to that
+    /// block. Sidecars fetched by other block ids are only as trustworthy as the node.
+    pub fn blob_sidecars(
+        &self,
+        block_id: &BlockId,
+    ) -> Result<(Vec<BlobSidecar>, Authentication), BeaconError> {
+        let key = block_id.to_string();
+        let cache = self.cache.as_ref().filter(|_| block_id.is_immutable());
+        let cached = cache.and_then(|cache| cache.get(SIDECARS_NAMESPACE, &key));
+
+        let response = match &cached {
+            Some(response) => response.clone(),
+            None => self.http.get(
+                &format!("{}/eth/v1/beacon/blob_sidecars/{}", self.url, block_id),
+                "application/json",
+            )?,
+        };
+        let sidecars = parse_sidecars_json(&String::from_utf8_lossy(&response))?;
+        let block_root = match block_id {
+            BlockId::Root(root) => Some(root),
+            _ => None,
+        };
+        let authentication = verify_sidecars(&sidecars, block_root)?;
+
+        if let (Some(cache), None) = (cache, cached) {
+            // The cache only saves requests, failing to fill it is not an error
+            let _ = cache.put(SIDECARS_NAMESPACE, &key, &response);
+        }
+        Ok((sidecars, authentication))
+    }
+
+    /// Fetches the time of the genesis of the chain, in seconds since the Unix epoch.
+    pub fn genesis_time(&self) -> Result<u64, BeaconError> {
+        let response = self.http.get(
+            &format!("{}/eth/v1/beacon/genesis", self.url),
+            "application/json",
+        )?;
+        let genesis: serde_json::Value = serde_json::from_slice(&response)
+            .map_err(|e| BeaconError::InvalidResponse(e.to_string()))?;
+        genesis["data"]["genesis_time"]
+            .as_str()
+            .and_then(|time| time.parse().ok())
+            .ok_or_else(|| BeaconError::InvalidResponse("missing genesis_time".to_string()))
+    }
+
+    /// Fetches the blob sidecars of a block whose blobs have the given versioned hashes.
+    ///
+    /// # Returns
+    ///
+    /// The sidecars, in the order of the versioned hashes, and their authentication, see
+    /// [`BeaconClient::blob_sidecars`].
+    pub fn blob_sidecars_by_versioned_hashes(
+        &self,
+        block_id: &BlockId,
+        versioned_hashes: &[[u8; 32]],
+    ) -> Result<(Vec<BlobSidecar>, Authentication), BeaconError> {
+        let (sidecars, authentication) = self.blob_sidecars(block_id)?;
+        let selected = versioned_hashes
+            .iter()
+            .map(|versioned_hash| {
+                sidecars
+                    .iter()
+                    .find(|sidecar| {
+                        kzg_to_versioned_hash(&sidecar.kzg_commitment) == *versioned_hash
+                    })
+                    .cloned()
+                    .ok_or(BeaconError::MissingBlob {
+                        block_id: *block_id,
+                        versioned_hash: *versioned_hash,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((selected, authentication))
+    }
+
+    /// Fetches the blobs of a block, optionally restricted to some versioned hashes, and
+    /// recovers the state diffs they carry, with the authentication of their sidecars.
+    pub fn state_diffs(
+        &self,
+        block_id: &BlockId,
+        versioned_hashes: &[[u8; 32]],
+    ) -> Result<(DataJson, Authentication), BeaconError> {
+        let (sidecars, authentication) = if versioned_hashes.is_empty() {
+            self.blob_sidecars(block_id)?
+        } else {
+            self.blob_sidecars_by_versioned_hashes(block_id, versioned_hashes)?
+        };
+        if sidecars.is_empty() {
+            return Err(BeaconError::NoBlobs(*block_id));
+        }
+        let original_data = blob::recover_blobs(sidecars_to_blobs(sidecars));
+        Ok((parse_state_diffs(&original_data), authentication))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::{beacon_node, block_root_8626176, MockServer};
+    use majin_blob_types::serde::parse_file_to_blob_data;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Serves the recorded sidecars at slot 8626176, answering the first `failures`
+    /// requests with `failure_status`.
+    fn mock_beacon_node(failures: usize, failure_status: u16) -> MockServer {
+        let seen = AtomicUsize::new(0);
+        MockServer::start(move |request| {
+            if seen.fetch_add(1, Ordering::SeqCst) < failures {
+                return (failure_status, "unavailable".to_string());
+            }
+            beacon_node(request).unwrap_or((404, "not found".to_string()))
+        })
+    }
+
+    fn config(retries: u32) -> HttpConfig {
+        HttpConfig {
+            timeout: Duration::from_secs(5),
+            retries,
+            retry_backoff: Duration::from_millis(10),
+        }
+    }
+
+    #[test]
+    fn test_state_diffs_with_retries() {
+        let server = mock_beacon_node(2, 503);
+        let client = BeaconClient::new(&server.url, config(2));
+        let (state_diffs, authentication) =
+            client.state_diffs(&BlockId::Slot(8626176), &[]).unwrap();
+        assert_eq!(server.requests(), 3);
+        assert_eq!(authentication, Authentication::Unauthenticated);
+
+        let blob_data = parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+        assert_eq!(state_diffs, parse_state_diffs(&blob::recover(blob_data)));
+    }
+
+    #[test]
+    fn test_errors_are_not_retried() {
+        let server = mock_beacon_node(0, 503);
+        let client = BeaconClient::new(&server.url, config(3));
+        let result = client.blob_sidecars(&BlockId::Slot(1));
+        assert!(matches!(
+            result,
+            Err(BeaconError::Http(HttpError::Status { status: 404, .. }))
+        ));
+        assert_eq!(server.requests(), 1);
+
+        let server = mock_beacon_node(5, 500);
+        let client = BeaconClient::new(&server.url, config(1));
+        assert!(client.blob_sidecars(&BlockId::Slot(8626176)).is_err());
+        assert_eq!(server.requests(), 2);
+    }
+
+    #[test]
+    fn test_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = mock_beacon_node(0, 503);
+        let client =
+            BeaconClient::new(&server.url, config(0)).with_cache(DiskCache::new(dir.path()));
+        let block_id = BlockId::Root(block_root_8626176());
+        let sidecars = client.blob_sidecars(&block_id).unwrap();
+        assert_eq!(sidecars.1, Authentication::Authenticated);
+        assert_eq!(client.blob_sidecars(&block_id).unwrap(), sidecars);
+        assert_eq!(server.requests(), 1);
+
+        // A node that went down still serves cached blocks
+        let offline = BeaconClient::new("http://127.0.0.1:1", config(0))
+            .with_cache(DiskCache::new(dir.path()));
+        assert_eq!(offline.blob_sidecars(&block_id).unwrap(), sidecars);
+        assert!(offline.blob_sidecars(&BlockId::Head).is_err());
+    }
+
+    #[test]
+    fn test_slots_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = mock_beacon_node(0, 503);
+        let client =
+            BeaconClient::new(&server.url, config(0)).with_cache(DiskCache::new(dir.path()));
+        let sidecars = client.blob_sidecars(&BlockId::Slot(8626176)).unwrap();
+        assert_eq!(
+            client.blob_sidecars(&BlockId::Slot(8626176)).unwrap(),
+            sidecars
+        );
+        assert_eq!(server.requests(), 2);
+    }
+
+    #[test]
+    fn test_blob_sidecars_by_versioned_hashes() {
+        let server = mock_beacon_node(0, 503);
+        let client = BeaconClient::new(&server.url, config(0));
+        let (sidecars, _) = client.blob_sidecars(&BlockId::Slot(8626176)).unwrap();
+        let versioned_hash = kzg_to_versioned_hash(&sidecars[0].kzg_commitment);
+
+        let selected = client
+            .blob_sidecars_by_versioned_hashes(&BlockId::Slot(8626176), &[versioned_hash])
+            .unwrap()
+            .0;
+        assert_eq!(selected, sidecars);
+        assert!(matches!(
+            client.blob_sidecars_by_versioned_hashes(&BlockId::Slot(8626176), &[[1u8; 32]]),
+            Err(BeaconError::MissingBlob { .. })
+        ));
+    }
+
+    #[test]
+    fn test_genesis_time() {
+        let server = mock_beacon_node(0, 503);
+        let client = BeaconClient::new(&server.url, config(0));
+        assert_eq!(client.genesis_time().unwrap(), 1606824023);
+    }
+
+    #[test]
+    fn test_block_id() {
+        assert_eq!("head".parse(), Ok(BlockId::Head));
+        assert_eq!("8626176".parse(), Ok(BlockId::Slot(8626176)));
+        let root = format!("0x{}", "ab".repeat(32));
+        assert_eq!(root.parse(), Ok(BlockId::Root([0xab; 32])));
+        assert_eq!(BlockId::Root([0xab; 32]).to_string(), root);
+        assert!("0x1234".parse::<BlockId>().is_err());
+    }
+}
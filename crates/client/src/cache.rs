@@ -0,0 +1,69 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A cache of immutable responses on disk, one file per entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DiskCache { dir: dir.into() }
+    }
+
+    /// Returns `$XDG_CACHE_HOME/majin-blob`, or `$HOME/.cache/majin-blob`.
+    pub fn default_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .map(|dir| dir.join("majin-blob"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the entry stored under `key` in `namespace`, if any.
+    pub fn get(&self, namespace: &str, key: &str) -> Option<Vec<u8>> {
+        fs::read(self.path(namespace, key)).ok()
+    }
+
+    /// Stores an entry. The file is written under a temporary name then renamed, so that
+    /// readers never see a partial entry.
+    pub fn put(&self, namespace: &str, key: &str, value: &[u8]) -> io::Result<()> {
+        let path = self.path(namespace, key);
+        fs::create_dir_all(path.parent().unwrap())?;
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp, value)?;
+        fs::rename(&tmp, &path)
+    }
+
+    fn path(&self, namespace: &str, key: &str) -> PathBuf {
+        let key: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        self.dir.join(namespace).join(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path());
+        assert_eq!(cache.get("sidecars", "1"), None);
+        cache.put("sidecars", "1", b"first").unwrap();
+        cache.put("sidecars", "1", b"second").unwrap();
+        assert_eq!(cache.get("sidecars", "1"), Some(b"second".to_vec()));
+        assert_eq!(cache.get("other", "1"), None);
+        // Keys cannot escape the cache directory
+        cache.put("sidecars", "../../escape", b"value").unwrap();
+        assert!(dir.path().join("sidecars").join("______escape").exists());
+    }
+}
@@ -0,0 +1,294 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::http::{HttpClient, HttpConfig, HttpError};
+
+#[derive(Debug, Error)]
+pub enum ExecutionError {
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    #[error("JSON-RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("invalid JSON-RPC response: {0}")]
+    InvalidResponse(String),
+    #[error("transaction 0x{} not found", hex::encode(.0))]
+    TransactionNotFound([u8; 32]),
+}
+
+/// The fields of an L1 transaction needed to locate its blobs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1Transaction {
+    pub hash: [u8; 32],
+    pub transaction_type: u64,
+    pub from: [u8; 20],
+    pub to: Option<[u8; 20]>,
+    pub input: Vec<u8>,
+    pub blob_versioned_hashes: Vec<[u8; 32]>,
+}
+
+/// The fields of an L1 transaction receipt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1Receipt {
+    pub block_hash: [u8; 32],
+    pub block_number: u64,
+    /// Whether the transaction succeeded.
+    pub status: bool,
+    pub blob_gas_used: Option<u64>,
+    pub blob_gas_price: Option<u128>,
+}
+
+/// The fields of an L1 block header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1Block {
+    pub hash: [u8; 32],
+    pub parent_hash: [u8; 32],
+    pub number: u64,
+    pub timestamp: u64,
+    /// The root of the beacon block the execution block builds upon (EIP-4788).
+    pub parent_beacon_block_root: Option<[u8; 32]>,
+}
+
+/// A client of the JSON-RPC API of an execution node.
+#[derive(Debug)]
+pub struct ExecutionClient {
+    url: String,
+    http: HttpClient,
+    next_id: AtomicU64,
+}
+
+impl ExecutionClient {
+    pub fn new(url: &str, config: HttpConfig) -> Self {
+        ExecutionClient {
+            url: url.to_string(),
+            http: HttpClient::new(config),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Calls a JSON-RPC method and returns its result.
+    pub fn request(&self, method: &str, params: Value) -> Result<Value, ExecutionError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response = self.http.post_json(&self.url, &request)?;
+        let mut response: Value = serde_json::from_slice(&response)
+            .map_err(|e| ExecutionError::InvalidResponse(e.to_string()))?;
+        if let Some(error) = response.get("error") {
+            return Err(ExecutionError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        Ok(response["result"].take())
+    }
+
+    /// Fetches a transaction by hash, with `eth_getTransactionByHash`.
+    pub fn transaction(&self, hash: &[u8; 32]) -> Result<L1Transaction, ExecutionError> {
+        let result = self.request("eth_getTransactionByHash", json!([to_hex(hash)]))?;
+        if result.is_null() {
+            return Err(ExecutionError::TransactionNotFound(*hash));
+        }
+        parse_transaction(&result)
+    }
+
+    /// Fetches the receipt of a transaction, with `eth_getTransactionReceipt`.
+    pub fn receipt(&self, hash: &[u8; 32]) -> Result<L1Receipt, ExecutionError> {
+        let result = self.request("eth_getTransactionReceipt", json!([to_hex(hash)]))?;
+        if result.is_null() {
+            return Err(ExecutionError::TransactionNotFound(*hash));
+        }
+        Ok(L1Receipt {
+            block_hash: bytes_field(&result, "blockHash")?,
+            block_number: quantity_field(&result, "blockNumber")? as u64,
+            status: quantity_field(&result, "status")? == 1,
+            blob_gas_used: optional(&result, "blobGasUsed", quantity_field)?.map(|gas| gas as u64),
+            blob_gas_price: optional(&result, "blobGasPrice", quantity_field)?,
+        })
+    }
+
+    /// Fetches the header of a block by hash, with `eth_getBlockByHash`.
+    pub fn block(&self, hash: &[u8; 32]) -> Result<L1Block, ExecutionError> {
+        let result = self.request("eth_getBlockByHash", json!([to_hex(hash), false]))?;
+        if result.is_null() {
+            return Err(ExecutionError::InvalidResponse(format!(
+                "block {} not found",
+                to_hex(hash)
+            )));
+        }
+        parse_block(&result)
+    }
+
+    /// Fetches the header of a block by number, with `eth_getBlockByNumber`, or `None` if
+    /// the block does not exist.
+    pub fn block_by_number(&self, number: u64) -> Result<Option<L1Block>, ExecutionError> {
+        let result = self.request(
+            "eth_getBlockByNumber",
+            json!([format!("0x{:x}", number), false]),
+        )?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        parse_block(&result).map(Some)
+    }
+
+    /// Fetches the number of the latest block, with `eth_blockNumber`.
+    pub fn block_number(&self) -> Result<u64, ExecutionError> {
+        let result = self.request("eth_blockNumber", json!([]))?;
+        Ok(parse_quantity(&result, "result")? as u64)
+    }
+
+    /// Fetches a block and its transactions by number, with `eth_getBlockByNumber`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the block does not exist yet.
+    pub fn block_with_transactions(
+        &self,
+        number: u64,
+    ) -> Result<Option<(L1Block, Vec<L1Transaction>)>, ExecutionError> {
+        let result = self.request(
+            "eth_getBlockByNumber",
+            json!([format!("0x{:x}", number), true]),
+        )?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        let transactions = match result.get("transactions") {
+            Some(Value::Array(transactions)) => transactions
+                .iter()
+                .map(parse_transaction)
+                .collect::<Result<_, _>>()?,
+            _ => return Err(invalid("transactions")),
+        };
+        Ok(Some((parse_block(&result)?, transactions)))
+    }
+}
+
+fn parse_transaction(object: &Value) -> Result<L1Transaction, ExecutionError> {
+    Ok(L1Transaction {
+        hash: bytes_field(object, "hash")?,
+        transaction_type: quantity_field(object, "type")? as u64,
+        from: bytes_field(object, "from")?,
+        to: optional(object, "to", bytes_field)?,
+        input: data_field(object, "input")?,
+        blob_versioned_hashes: match object.get("blobVersionedHashes") {
+            Some(Value::Array(hashes)) => hashes
+                .iter()
+                .map(|hash| parse_bytes(hash, "blobVersionedHashes"))
+                .collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        },
+    })
+}
+
+fn parse_block(object: &Value) -> Result<L1Block, ExecutionError> {
+    Ok(L1Block {
+        hash: bytes_field(object, "hash")?,
+        parent_hash: bytes_field(object, "parentHash")?,
+        number: quantity_field(object, "number")? as u64,
+        timestamp: quantity_field(object, "timestamp")? as u64,
+        parent_beacon_block_root: optional(object, "parentBeaconBlockRoot", bytes_field)?,
+    })
+}
+
+/// Encodes bytes as `0x`-prefixed hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn invalid(field: &str) -> ExecutionError {
+    ExecutionError::InvalidResponse(format!("invalid or missing field {}", field))
+}
+
+fn optional<T>(
+    object: &Value,
+    field: &str,
+    parse: fn(&Value, &str) -> Result<T, ExecutionError>,
+) -> Result<Option<T>, ExecutionError> {
+    match object.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(_) => parse(object, field).map(Some),
+    }
+}
+
+fn data_field(object: &Value, field: &str) -> Result<Vec<u8>, ExecutionError> {
+    let value = object[field].as_str().ok_or_else(|| invalid(field))?;
+    hex::decode(value.trim_start_matches("0x")).map_err(|_| invalid(field))
+}
+
+fn parse_bytes<const N: usize>(value: &Value, field: &str) -> Result<[u8; N], ExecutionError> {
+    let value = value.as_str().ok_or_else(|| invalid(field))?;
+    hex::decode(value.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid(field))
+}
+
+fn bytes_field<const N: usize>(object: &Value, field: &str) -> Result<[u8; N], ExecutionError> {
+    parse_bytes(&object[field], field)
+}
+
+fn quantity_field(object: &Value, field: &str) -> Result<u128, ExecutionError> {
+    parse_quantity(&object[field], field)
+}
+
+fn parse_quantity(value: &Value, field: &str) -> Result<u128, ExecutionError> {
+    let value = value.as_str().ok_or_else(|| invalid(field))?;
+    let digits = value.strip_prefix("0x").ok_or_else(|| invalid(field))?;
+    u128::from_str_radix(digits, 16).map_err(|_| invalid(field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::MockServer;
+    use std::time::Duration;
+
+    fn config() -> HttpConfig {
+        HttpConfig {
+            timeout: Duration::from_secs(5),
+            retries: 0,
+            retry_backoff: Duration::from_millis(10),
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        let server = MockServer::start(|request| {
+            let request: Value = serde_json::from_str(&request.body).unwrap();
+            let response = match request["method"].as_str().unwrap() {
+                "eth_blockNumber" => {
+                    json!({"jsonrpc": "2.0", "id": request["id"], "result": "0x10"})
+                }
+                _ => json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32601, "message": "method not found"}
+                }),
+            };
+            (200, response.to_string())
+        });
+        let client = ExecutionClient::new(&server.url, config());
+        assert_eq!(client.block_number().unwrap(), 0x10);
+        assert!(matches!(
+            client.request("eth_foo", json!([])),
+            Err(ExecutionError::Rpc { code: -32601, .. })
+        ));
+    }
+
+    #[test]
+    fn test_transaction_not_found() {
+        let server =
+            MockServer::start(|_| (200, r#"{"jsonrpc":"2.0","id":1,"result":null}"#.to_string()));
+        let client = ExecutionClient::new(&server.url, config());
+        assert!(matches!(
+            client.transaction(&[1u8; 32]),
+            Err(ExecutionError::TransactionNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::Read;
+use std::thread;
+use std::time::Duration;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("{url} answered with status {status}: {body}")]
+    Status {
+        url: String,
+        status: u16,
+        body: String,
+    },
+    #[error("request to {url} failed: {message}")]
+    Transport { url: String, message: String },
+}
+
+impl HttpError {
+    /// Whether the request may succeed if sent again: transport failures, rate limiting and
+    /// server errors.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            HttpError::Status { status, .. } => *status == 429 || *status >= 500,
+            HttpError::Transport { .. } => true,
+        }
+    }
+}
+
+/// Timeouts and retry policy of an [`HttpClient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpConfig {
+    /// Timeout of a whole request, response body included.
+    pub timeout: Duration,
+    /// How many times a request failing with a transient error is sent again.
+    pub retries: u32,
+    /// Delay before the first retry, doubled before each following one.
+    pub retry_backoff: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            timeout: Duration::from_secs(30),
+            retries: 3,
+            retry_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A blocking HTTP client retrying transient failures.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    agent: ureq::Agent,
+    config: HttpConfig,
+}
+
+impl HttpClient {
+    pub fn new(config: HttpConfig) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout(config.timeout).build();
+        HttpClient { agent, config }
+    }
+
+    /// Sends a GET request and returns the response body.
+    /// # Arguments
+    /// * `url` - The URL to query.
+    /// * `accept` - The media type to ask for.
+    pub fn get(&self, url: &str, accept: &str) -> Result<Vec<u8>, HttpError> {
+        self.with_retries(|| into_body(url, self.agent.get(url).set("Accept", accept).call()))
+    }
+
+    /// Sends a POST request with a JSON body and returns the response body.
+    pub fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<Vec<u8>, HttpError> {
+        let body = body.to_string();
+        self.with_retries(|| {
+            let response = self
+                .agent
+                .post(url)
+                .set("Content-Type", "application/json")
+                .send_string(&body);
+            into_body(url, response)
+        })
+    }
+
+    fn with_retries<F>(&self, send: F) -> Result<Vec<u8>, HttpError>
+    where
+        F: Fn() -> Result<Vec<u8>, HttpError>,
+    {
+        let mut backoff = self.config.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            match send() {
+                Err(e) if e.is_transient() && attempt < self.config.retries => {
+                    attempt += 1;
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Reads the body of a response, or converts the error of the request.
+fn into_body(
+    url: &str,
+    response: Result<ureq::Response, ureq::Error>,
+) -> Result<Vec<u8>, HttpError> {
+    match response {
+        Ok(response) => read_body(url, response),
+        Err(error) => Err(to_http_error(url, error)),
+    }
+}
+
+fn to_http_error(url: &str, error: ureq::Error) -> HttpError {
+    match error {
+        ureq::Error::Status(status, response) => HttpError::Status {
+            url: url.to_string(),
+            status,
+            body: response.into_string().unwrap_or_default(),
+        },
+        ureq::Error::Transport(transport) => HttpError::Transport {
+            url: url.to_string(),
+            message: transport.to_string(),
+        },
+    }
+}
+
+fn read_body(url: &str, response: ureq::Response) -> Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| HttpError::Transport {
+            url: url.to_string(),
+            message: e.to_string(),
+        })?;
+    Ok(body)
+}
@@ -0,0 +1,8 @@
+pub mod beacon;
+pub mod cache;
+pub mod execution;
+pub mod http;
+pub mod state_update;
+pub mod watch;
+#[cfg(test)]
+mod testutils;
@@ -0,0 +1,262 @@
+use majin_blob_core::blob;
+use majin_blob_types::serde::parse_state_diffs;
+use majin_blob_types::sidecar::sidecars_to_blobs;
+use majin_blob_types::state_diffs::DataJson;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::beacon::{BeaconClient, BeaconError, BlockId};
+use crate::execution::{
+    to_hex, ExecutionClient, ExecutionError, L1Block, L1Receipt, L1Transaction,
+};
+
+/// Duration of a beacon chain slot, in seconds.
+pub const SECONDS_PER_SLOT: u64 = 12;
+
+#[derive(Debug, Error)]
+pub enum StateUpdateError {
+    #[error(transparent)]
+    Execution(#[from] ExecutionError),
+    #[error(transparent)]
+    Beacon(#[from] BeaconError),
+    #[error("transaction {0} is not a blob transaction")]
+    NoBlobs(String),
+    #[error("transaction {0} is pending")]
+    Pending(String),
+    #[error("transaction {0} reverted")]
+    Reverted(String),
+    #[error("block {block_hash} is older than the beacon chain genesis")]
+    BeforeGenesis { block_hash: String },
+    #[error("the sidecars of slot {slot} do not build upon beacon block {expected}")]
+    ParentRootMismatch { slot: u64, expected: String },
+}
+
+/// Where a state update was published on L1.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct L1Metadata {
+    pub transaction_hash: String,
+    pub block_number: u64,
+    pub block_hash: String,
+    pub block_timestamp: u64,
+    pub slot: u64,
+    pub from: String,
+    pub to: Option<String>,
+    pub blob_versioned_hashes: Vec<String>,
+    pub blob_gas_used: Option<u64>,
+    pub blob_gas_price: Option<u128>,
+}
+
+/// The state diffs published by an L1 transaction, with where they were published.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct StateUpdate {
+    pub l1: L1Metadata,
+    pub state_diffs: DataJson,
+}
+
+/// Returns the slot of the beacon block whose execution payload has the given timestamp.
+pub fn timestamp_to_slot(genesis_time: u64, timestamp: u64) -> Option<u64> {
+    timestamp
+        .checked_sub(genesis_time)
+        .map(|elapsed| elapsed / SECONDS_PER_SLOT)
+}
+
+/// Fetches an L1 transaction publishing a state update, such as a call to
+/// `updateStateKzgDA`, fetches its blobs and recovers the state diffs they carry.
+pub fn resolve_state_update(
+    execution: &ExecutionClient,
+    beacon: &BeaconClient,
+    transaction_hash: &[u8; 32],
+) -> Result<StateUpdate, StateUpdateError> {
+    let hash = to_hex(transaction_hash);
+    let transaction = execution.transaction(transaction_hash)?;
+    if transaction.blob_versioned_hashes.is_empty() {
+        return Err(StateUpdateError::NoBlobs(hash));
+    }
+    let receipt = match execution.receipt(transaction_hash) {
+        Err(ExecutionError::TransactionNotFound(_)) => return Err(StateUpdateError::Pending(hash)),
+        receipt => receipt?,
+    };
+    let block = execution.block(&receipt.block_hash)?;
+    decode_state_update(
+        beacon,
+        beacon.genesis_time()?,
+        &transaction,
+        &receipt,
+        &block,
+    )
+}
+
+/// Fetches the blobs of a transaction included in `block` and recovers the state diffs
+/// they carry.
+///
+/// The blobs are looked up at the slot of the block, and the beacon block they belong to
+/// must be the child of the `parentBeaconBlockRoot` of the block, so that a beacon node
+/// serving another chain is caught.
+pub fn decode_state_update(
+    beacon: &BeaconClient,
+    genesis_time: u64,
+    transaction: &L1Transaction,
+    receipt: &L1Receipt,
+    block: &L1Block,
+) -> Result<StateUpdate, StateUpdateError> {
+    let hash = to_hex(&transaction.hash);
+    if transaction.blob_versioned_hashes.is_empty() {
+        return Err(StateUpdateError::NoBlobs(hash));
+    }
+    if !receipt.status {
+        return Err(StateUpdateError::Reverted(hash));
+    }
+    let slot = timestamp_to_slot(genesis_time, block.timestamp).ok_or_else(|| {
+        StateUpdateError::BeforeGenesis {
+            block_hash: to_hex(&block.hash),
+        }
+    })?;
+
+    // The versioned hashes of the transaction select the sidecars, and the parent root of
+    // the block pins the beacon block they come from
+    let (sidecars, _) = beacon.blob_sidecars_by_versioned_hashes(
+        &BlockId::Slot(slot),
+        &transaction.blob_versioned_hashes,
+    )?;
+    if let Some(parent_root) = block.parent_beacon_block_root {
+        // All sidecars share one header, see `verify_sidecars`
+        if sidecars[0].signed_block_header.message.parent_root != parent_root {
+            return Err(StateUpdateError::ParentRootMismatch {
+                slot,
+                expected: to_hex(&parent_root),
+            });
+        }
+    }
+    let original_data = blob::recover_blobs(sidecars_to_blobs(sidecars));
+
+    Ok(StateUpdate {
+        l1: L1Metadata {
+            transaction_hash: hash,
+            block_number: block.number,
+            block_hash: to_hex(&block.hash),
+            block_timestamp: block.timestamp,
+            slot,
+            from: to_hex(&transaction.from),
+            to: transaction.to.map(|to| to_hex(&to)),
+            blob_versioned_hashes: transaction
+                .blob_versioned_hashes
+                .iter()
+                .map(|versioned_hash| to_hex(versioned_hash))
+                .collect(),
+            blob_gas_used: receipt.blob_gas_used,
+            blob_gas_price: receipt.blob_gas_price,
+        },
+        state_diffs: parse_state_diffs(&original_data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpConfig;
+    use crate::testutils::{beacon_node, MockRequest, MockServer, SIDECARS_8626176};
+    use majin_blob_eip_4844::blob::kzg_to_versioned_hash;
+    use majin_blob_types::serde::parse_file_to_blob_data;
+    use majin_blob_types::sidecar::parse_sidecars_json;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    const TX_HASH: [u8; 32] = [0x77; 32];
+    const BLOCK_HASH: [u8; 32] = [0x88; 32];
+
+    /// Answers JSON-RPC requests about a transaction whose blobs are the recorded sidecars
+    /// of slot 8626176, and beacon API requests.
+    fn node(request: &MockRequest, status: &str, parent_root: [u8; 32]) -> (u16, String) {
+        if request.method != "POST" {
+            return beacon_node(request).unwrap_or((404, "not found".to_string()));
+        }
+        let sidecars = parse_sidecars_json(SIDECARS_8626176).unwrap();
+        let request: Value = serde_json::from_str(&request.body).unwrap();
+        let result = match request["method"].as_str().unwrap() {
+            "eth_getTransactionByHash" => json!({
+                "hash": to_hex(&TX_HASH),
+                "type": "0x3",
+                "from": to_hex(&[0x11; 20]),
+                "to": to_hex(&[0x22; 20]),
+                "input": "0x",
+                "blobVersionedHashes": [to_hex(&kzg_to_versioned_hash(&sidecars[0].kzg_commitment))],
+            }),
+            "eth_getTransactionReceipt" => json!({
+                "blockHash": to_hex(&BLOCK_HASH),
+                "blockNumber": "0x12a05f2",
+                "status": status,
+                "blobGasUsed": "0x20000",
+                "blobGasPrice": "0x1",
+            }),
+            "eth_getBlockByHash" => json!({
+                "hash": to_hex(&BLOCK_HASH),
+                "parentHash": to_hex(&[0x87; 32]),
+                "number": "0x12a05f2",
+                "timestamp": format!("0x{:x}", 1606824023 + 8626176 * SECONDS_PER_SLOT + 5),
+                "parentBeaconBlockRoot": to_hex(&parent_root),
+            }),
+            _ => Value::Null,
+        };
+        let response = json!({"jsonrpc": "2.0", "id": request["id"], "result": result});
+        (200, response.to_string())
+    }
+
+    fn recorded_parent_root() -> [u8; 32] {
+        let sidecars = parse_sidecars_json(SIDECARS_8626176).unwrap();
+        sidecars[0].signed_block_header.message.parent_root
+    }
+
+    fn clients(server: &MockServer) -> (ExecutionClient, BeaconClient) {
+        let config = HttpConfig {
+            timeout: Duration::from_secs(5),
+            retries: 0,
+            retry_backoff: Duration::from_millis(10),
+        };
+        (
+            ExecutionClient::new(&server.url, config.clone()),
+            BeaconClient::new(&server.url, config),
+        )
+    }
+
+    #[test]
+    fn test_resolve_state_update() {
+        let parent_root = recorded_parent_root();
+        let server = MockServer::start(move |request| node(request, "0x1", parent_root));
+        let (execution, beacon) = clients(&server);
+        let state_update = resolve_state_update(&execution, &beacon, &TX_HASH).unwrap();
+
+        assert_eq!(state_update.l1.slot, 8626176);
+        assert_eq!(state_update.l1.block_number, 0x12a05f2);
+        assert_eq!(state_update.l1.blob_gas_used, Some(0x20000));
+        let blob_data = parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+        assert_eq!(
+            state_update.state_diffs,
+            parse_state_diffs(&blob::recover(blob_data))
+        );
+    }
+
+    #[test]
+    fn test_resolve_state_update_errors() {
+        let parent_root = recorded_parent_root();
+        let server = MockServer::start(move |request| node(request, "0x0", parent_root));
+        let (execution, beacon) = clients(&server);
+        assert!(matches!(
+            resolve_state_update(&execution, &beacon, &TX_HASH),
+            Err(StateUpdateError::Reverted(_))
+        ));
+
+        let server = MockServer::start(|request| node(request, "0x1", [0xff; 32]));
+        let (execution, beacon) = clients(&server);
+        assert!(matches!(
+            resolve_state_update(&execution, &beacon, &TX_HASH),
+            Err(StateUpdateError::ParentRootMismatch { slot: 8626176, .. })
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_to_slot() {
+        assert_eq!(timestamp_to_slot(1606824023, 1606824023), Some(0));
+        assert_eq!(timestamp_to_slot(1606824023, 1606824023 + 25), Some(2));
+        assert_eq!(timestamp_to_slot(1606824023, 0), None);
+    }
+}
@@ -0,0 +1,82 @@
+use majin_blob_types::sidecar::parse_sidecars_json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::beacon::BlockId;
+
+/// Sidecars recorded at slot 8626176, holding the blob of `examples/blob/mainnet.txt`.
+pub const SIDECARS_8626176: &str = include_str!("blob_sidecars_8626176.json");
+
+/// The root of the block at slot 8626176.
+pub fn block_root_8626176() -> [u8; 32] {
+    parse_sidecars_json(SIDECARS_8626176).unwrap()[0]
+        .signed_block_header
+        .message
+        .hash_tree_root()
+}
+
+/// A request received by a [`MockServer`].
+pub struct MockRequest {
+    pub method: String,
+    pub url: String,
+    pub body: String,
+}
+
+/// A local HTTP server answering requests with a handler, standing in for Ethereum nodes.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockServer {
+    /// Starts a server answering each request with the status and body the handler returns.
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&MockRequest) -> (u16, String) + Send + 'static,
+    {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                let (status, response) = handler(&MockRequest {
+                    method: request.method().to_string(),
+                    url: request.url().to_string(),
+                    body,
+                });
+                let _ = request
+                    .respond(tiny_http::Response::from_string(response).with_status_code(status));
+            }
+        });
+        MockServer { url, requests }
+    }
+
+    /// Returns how many requests the server received.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+/// Answers the beacon API requests about slot 8626176 on mainnet, by slot or block root.
+pub fn beacon_node(request: &MockRequest) -> Option<(u16, String)> {
+    let by_root = format!(
+        "/eth/v1/beacon/blob_sidecars/{}",
+        BlockId::Root(block_root_8626176())
+    );
+    if request.url == by_root {
+        return Some((200, SIDECARS_8626176.to_string()));
+    }
+    match request.url.as_str() {
+        "/eth/v1/beacon/blob_sidecars/8626176" => Some((200, SIDECARS_8626176.to_string())),
+        "/eth/v1/beacon/genesis" => Some((
+            200,
+            r#"{"data":{"genesis_time":"1606824023","genesis_validators_root":"0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95","genesis_fork_version":"0x00000000"}}"#
+                .to_string(),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,533 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::beacon::{BeaconClient, BeaconError};
+use crate::execution::{to_hex, ExecutionClient, ExecutionError, L1Transaction};
+use crate::state_update::{decode_state_update, StateUpdate, StateUpdateError};
+
+/// How many of the latest blocks the checkpoint remembers. Reorgs deeper than this are
+/// not detected.
+pub const REORG_DEPTH: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error(transparent)]
+    Execution(#[from] ExecutionError),
+    #[error(transparent)]
+    Beacon(#[from] BeaconError),
+    #[error(transparent)]
+    StateUpdate(#[from] StateUpdateError),
+    #[error("failed to access the checkpoint: {0}")]
+    Checkpoint(#[from] io::Error),
+}
+
+/// A block processed by a [`Watcher`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointBlock {
+    pub number: u64,
+    pub hash: String,
+    /// The hashes of the transactions whose state updates were emitted.
+    pub state_updates: Vec<String>,
+}
+
+/// The latest blocks processed by a [`Watcher`], oldest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub blocks: Vec<CheckpointBlock>,
+}
+
+impl Checkpoint {
+    /// Reads a checkpoint, or returns `None` if there is none at `path`.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        match fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the checkpoint under a temporary name then renames it, so that a watcher
+    /// killed while saving resumes from the previous checkpoint.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self).unwrap())?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn head(&self) -> Option<&CheckpointBlock> {
+        self.blocks.last()
+    }
+}
+
+/// What a [`Watcher`] reports about the chain.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchEvent {
+    /// A state update was published in a new block.
+    StateUpdate(Box<StateUpdate>),
+    /// A state update emitted before belongs to a block that was reorged out.
+    Retracted {
+        block_number: u64,
+        block_hash: String,
+        transaction_hash: String,
+    },
+    /// A state update of a new block could not be decoded, and is skipped.
+    Failed {
+        block_number: u64,
+        block_hash: String,
+        transaction_hash: String,
+        error: String,
+    },
+}
+
+/// Follows an execution node and decodes the state updates published to a contract in
+/// blobs, as blocks arrive.
+#[derive(Debug)]
+pub struct Watcher {
+    execution: ExecutionClient,
+    beacon: BeaconClient,
+    contract: [u8; 20],
+    next_block: u64,
+    checkpoint: Checkpoint,
+    checkpoint_path: Option<PathBuf>,
+    genesis_time: Option<u64>,
+}
+
+impl Watcher {
+    /// Builds a watcher of the state updates sent to `contract`, starting at block
+    /// `start_block`. Blobs are fetched by slot, so they are never served from the cache of
+    /// `beacon`, and a slot reorged out is fetched again.
+    pub fn new(
+        execution: ExecutionClient,
+        beacon: BeaconClient,
+        contract: [u8; 20],
+        start_block: u64,
+    ) -> Self {
+        Watcher {
+            execution,
+            beacon,
+            contract,
+            next_block: start_block,
+            checkpoint: Checkpoint::default(),
+            checkpoint_path: None,
+            genesis_time: None,
+        }
+    }
+
+    /// Saves the progress of the watcher at `path` after each block. If a checkpoint is
+    /// already there, the watcher resumes after its latest block.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Result<Self, WatchError> {
+        let path = path.into();
+        if let Some(checkpoint) = Checkpoint::load(&path)? {
+            if let Some(head) = checkpoint.head() {
+                self.next_block = head.number + 1;
+            }
+            self.checkpoint = checkpoint;
+        }
+        self.checkpoint_path = Some(path);
+        Ok(self)
+    }
+
+    /// Returns the number of the next block to process.
+    pub fn next_block(&self) -> u64 {
+        self.next_block
+    }
+
+    /// Processes the blocks produced since the last call, up to the latest one.
+    ///
+    /// When the latest block processed is no longer on the chain, or a block does not
+    /// build upon it, the chain was reorganized: the blocks are unwound until the common
+    /// ancestor, and the state updates they carried are retracted.
+    ///
+    /// A state update that cannot be decoded is reported with [`WatchEvent::Failed`] and
+    /// skipped. Only the failures of the nodes, which may succeed later, are returned, and
+    /// the block is processed again on the next call.
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks processed.
+    pub fn poll<F>(&mut self, emit: &mut F) -> Result<usize, WatchError>
+    where
+        F: FnMut(WatchEvent),
+    {
+        // The head may be reorged out with no block built upon the new chain yet
+        while let Some(head) = self.checkpoint.head() {
+            let canonical = self.execution.block_by_number(head.number)?;
+            if canonical.is_some_and(|block| to_hex(&block.hash) == head.hash) {
+                break;
+            }
+            self.retract_head(emit)?;
+        }
+
+        let mut processed = 0;
+        while let Some((block, transactions)) =
+            self.execution.block_with_transactions(self.next_block)?
+        {
+            if let Some(head) = self.checkpoint.head() {
+                if head.number + 1 == block.number && head.hash != to_hex(&block.parent_hash) {
+                    self.retract_head(emit)?;
+                    continue;
+                }
+            }
+
+            // Decode every state update of the block before emitting any, so that a
+            // failure of the nodes leaves nothing to retract when the block is processed
+            // again
+            let mut events = Vec::new();
+            let mut state_updates = Vec::new();
+            for transaction in transactions
+                .iter()
+                .filter(|tx| is_state_update(tx, &self.contract))
+            {
+                let receipt = self.execution.receipt(&transaction.hash)?;
+                if !receipt.status {
+                    continue;
+                }
+                let genesis_time = match self.genesis_time {
+                    Some(genesis_time) => genesis_time,
+                    None => *self.genesis_time.insert(self.beacon.genesis_time()?),
+                };
+                match decode_state_update(&self.beacon, genesis_time, transaction, &receipt, &block)
+                {
+                    Ok(state_update) => {
+                        state_updates.push(state_update.l1.transaction_hash.clone());
+                        events.push(WatchEvent::StateUpdate(Box::new(state_update)));
+                    }
+                    Err(error) if is_transient(&error) => return Err(error.into()),
+                    Err(error) => events.push(WatchEvent::Failed {
+                        block_number: block.number,
+                        block_hash: to_hex(&block.hash),
+                        transaction_hash: to_hex(&transaction.hash),
+                        error: error.to_string(),
+                    }),
+                }
+            }
+
+            self.checkpoint.blocks.push(CheckpointBlock {
+                number: block.number,
+                hash: to_hex(&block.hash),
+                state_updates,
+            });
+            if self.checkpoint.blocks.len() > REORG_DEPTH {
+                self.checkpoint.blocks.remove(0);
+            }
+            events.into_iter().for_each(&mut *emit);
+            self.next_block = block.number + 1;
+            self.save()?;
+            processed += 1;
+        }
+        Ok(processed)
+    }
+
+    /// Unwinds the latest block processed, retracting its state updates.
+    fn retract_head<F>(&mut self, emit: &mut F) -> io::Result<()>
+    where
+        F: FnMut(WatchEvent),
+    {
+        let Some(orphaned) = self.checkpoint.blocks.pop() else {
+            return Ok(());
+        };
+        for transaction_hash in orphaned.state_updates.into_iter().rev() {
+            emit(WatchEvent::Retracted {
+                block_number: orphaned.number,
+                block_hash: orphaned.hash.clone(),
+                transaction_hash,
+            });
+        }
+        self.next_block = orphaned.number;
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        match &self.checkpoint_path {
+            Some(path) => self.checkpoint.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether decoding a state update failed because of a node, and may succeed later.
+fn is_transient(error: &StateUpdateError) -> bool {
+    match error {
+        StateUpdateError::Execution(_) => true,
+        StateUpdateError::Beacon(BeaconError::Http(error)) => error.is_transient(),
+        _ => false,
+    }
+}
+
+/// Whether a transaction publishes a state update to `contract` in blobs.
+fn is_state_update(transaction: &L1Transaction, contract: &[u8; 20]) -> bool {
+    transaction.to.as_ref() == Some(contract) && !transaction.blob_versioned_hashes.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::DiskCache;
+    use crate::http::HttpConfig;
+    use crate::state_update::SECONDS_PER_SLOT;
+    use crate::testutils::{beacon_node, MockServer, SIDECARS_8626176};
+    use majin_blob_eip_4844::blob::kzg_to_versioned_hash;
+    use majin_blob_types::sidecar::parse_sidecars_json;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    const CONTRACT: [u8; 20] = [0x22; 20];
+    const TX_HASH: [u8; 32] = [0x77; 32];
+
+    /// Builds a block carrying the blob of the recorded sidecars of slot 8626176 if
+    /// `with_state_update`.
+    fn block(number: u64, hash: u8, parent_hash: u8, with_state_update: bool) -> Value {
+        let sidecars = parse_sidecars_json(SIDECARS_8626176).unwrap();
+        let transactions = match with_state_update {
+            true => vec![json!({
+                "hash": to_hex(&TX_HASH),
+                "type": "0x3",
+                "from": to_hex(&[0x11; 20]),
+                "to": to_hex(&CONTRACT),
+                "input": "0x",
+                "blobVersionedHashes": [to_hex(&kzg_to_versioned_hash(&sidecars[0].kzg_commitment))],
+            })],
+            false => Vec::new(),
+        };
+        json!({
+            "hash": to_hex(&[hash; 32]),
+            "parentHash": to_hex(&[parent_hash; 32]),
+            "number": format!("0x{:x}", number),
+            "timestamp": format!("0x{:x}", 1606824023 + 8626176 * SECONDS_PER_SLOT),
+            "parentBeaconBlockRoot": to_hex(&sidecars[0].signed_block_header.message.parent_root),
+            "transactions": transactions,
+        })
+    }
+
+    /// Serves the beacon API and the JSON-RPC API of a chain that can be replaced, with
+    /// the sidecars of slot 8626176 in `sidecars`.
+    fn mock_node(chain: Arc<Mutex<Vec<Value>>>, sidecars: Arc<Mutex<String>>) -> MockServer {
+        MockServer::start(move |request| {
+            if request.url == "/eth/v1/beacon/blob_sidecars/8626176" {
+                return (200, sidecars.lock().unwrap().clone());
+            }
+            if request.method != "POST" {
+                return beacon_node(request).unwrap_or((404, "not found".to_string()));
+            }
+            let request: Value = serde_json::from_str(&request.body).unwrap();
+            let result = match request["method"].as_str().unwrap() {
+                "eth_getBlockByNumber" => {
+                    let number = request["params"][0].as_str().unwrap();
+                    let chain = chain.lock().unwrap();
+                    chain
+                        .iter()
+                        .find(|block| block["number"] == number)
+                        .cloned()
+                        .unwrap_or(Value::Null)
+                }
+                "eth_getTransactionReceipt" => json!({
+                    "blockHash": to_hex(&[0xa1; 32]),
+                    "blockNumber": "0x65",
+                    "status": "0x1",
+                }),
+                _ => Value::Null,
+            };
+            let response = json!({"jsonrpc": "2.0", "id": request["id"], "result": result});
+            (200, response.to_string())
+        })
+    }
+
+    fn recorded_sidecars() -> Arc<Mutex<String>> {
+        Arc::new(Mutex::new(SIDECARS_8626176.to_string()))
+    }
+
+    fn watcher(server: &MockServer, start_block: u64) -> Watcher {
+        let config = HttpConfig {
+            timeout: Duration::from_secs(5),
+            retries: 0,
+            retry_backoff: Duration::from_millis(10),
+        };
+        Watcher::new(
+            ExecutionClient::new(&server.url, config.clone()),
+            BeaconClient::new(&server.url, config),
+            CONTRACT,
+            start_block,
+        )
+    }
+
+    #[test]
+    fn test_poll_with_reorg() {
+        let chain = Arc::new(Mutex::new(vec![
+            block(100, 0xa0, 0x9f, false),
+            block(101, 0xa1, 0xa0, true),
+            block(102, 0xa2, 0xa1, false),
+        ]));
+        let server = mock_node(chain.clone(), recorded_sidecars());
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("checkpoint.json");
+        let mut watcher = watcher(&server, 100).with_checkpoint(&checkpoint).unwrap();
+
+        let mut events = Vec::new();
+        assert_eq!(watcher.poll(&mut |event| events.push(event)).unwrap(), 3);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            WatchEvent::StateUpdate(state_update) => {
+                assert_eq!(state_update.l1.block_number, 101);
+                assert_eq!(state_update.l1.slot, 8626176);
+                assert!(!state_update.state_diffs.state_update.is_empty());
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        // Blocks 101 and 102 are replaced by blocks without state update
+        *chain.lock().unwrap() = vec![
+            block(100, 0xa0, 0x9f, false),
+            block(101, 0xb1, 0xa0, false),
+            block(102, 0xb2, 0xb1, false),
+            block(103, 0xb3, 0xb2, false),
+        ];
+        let mut events = Vec::new();
+        assert_eq!(watcher.poll(&mut |event| events.push(event)).unwrap(), 3);
+        assert_eq!(
+            events,
+            vec![WatchEvent::Retracted {
+                block_number: 101,
+                block_hash: to_hex(&[0xa1; 32]),
+                transaction_hash: to_hex(&TX_HASH),
+            }]
+        );
+        assert_eq!(watcher.next_block(), 104);
+
+        // A new watcher resumes from the checkpoint
+        let mut resumed = self::watcher(&server, 0)
+            .with_checkpoint(&checkpoint)
+            .unwrap();
+        assert_eq!(resumed.next_block(), 104);
+        assert_eq!(resumed.poll(&mut |_| panic!("no new block")).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_poll_with_reorg_at_the_same_slot() {
+        let chain = Arc::new(Mutex::new(vec![
+            block(100, 0xa0, 0x9f, false),
+            block(101, 0xa1, 0xa0, true),
+        ]));
+        let sidecars = recorded_sidecars();
+        let server = mock_node(chain.clone(), sidecars.clone());
+        let dir = tempfile::tempdir().unwrap();
+        let mut watcher = watcher(&server, 100);
+        watcher.beacon = watcher.beacon.with_cache(DiskCache::new(dir.path()));
+        let mut events = Vec::new();
+        assert_eq!(watcher.poll(&mut |event| events.push(event)).unwrap(), 2);
+        assert_eq!(events.len(), 1);
+
+        // Block 101 is replaced by a block at the same slot, built upon another beacon
+        // block: the sidecars of the slot change with it
+        let recorded = parse_sidecars_json(SIDECARS_8626176).unwrap();
+        let parent_root = to_hex(&recorded[0].signed_block_header.message.parent_root);
+        let other_root = to_hex(&[0x42; 32]);
+        let reorged = SIDECARS_8626176.replace(&parent_root, &other_root);
+        assert_ne!(reorged, SIDECARS_8626176);
+        *sidecars.lock().unwrap() = reorged;
+        let mut new_block = block(101, 0xb1, 0xa0, true);
+        new_block["parentBeaconBlockRoot"] = Value::String(other_root);
+        *chain.lock().unwrap() = vec![
+            block(100, 0xa0, 0x9f, false),
+            new_block,
+            block(102, 0xb2, 0xb1, false),
+        ];
+
+        let mut events = Vec::new();
+        assert_eq!(watcher.poll(&mut |event| events.push(event)).unwrap(), 2);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            WatchEvent::Retracted {
+                block_number: 101,
+                ..
+            }
+        ));
+        match &events[1] {
+            WatchEvent::StateUpdate(state_update) => {
+                assert_eq!(state_update.l1.block_hash, to_hex(&[0xb1; 32]));
+                assert_eq!(state_update.l1.slot, 8626176);
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert_eq!(watcher.next_block(), 103);
+    }
+
+    #[test]
+    fn test_poll_skips_undecodable_state_updates() {
+        // The blob of the state update of block 101 is not in the sidecars of its slot
+        let mut unknown_blob = block(101, 0xa1, 0xa0, true);
+        unknown_blob["transactions"][0]["blobVersionedHashes"] = json!([to_hex(&[0x01; 32])]);
+        let chain = Arc::new(Mutex::new(vec![
+            block(100, 0xa0, 0x9f, false),
+            unknown_blob,
+            block(102, 0xa2, 0xa1, false),
+        ]));
+        let server = mock_node(chain, recorded_sidecars());
+        let mut watcher = watcher(&server, 100);
+        let mut events = Vec::new();
+        assert_eq!(watcher.poll(&mut |event| events.push(event)).unwrap(), 3);
+        match &events[..] {
+            [WatchEvent::Failed {
+                block_number: 101,
+                transaction_hash,
+                ..
+            }] => assert_eq!(*transaction_hash, to_hex(&TX_HASH)),
+            events => panic!("unexpected events {:?}", events),
+        }
+        assert_eq!(watcher.next_block(), 103);
+        assert!(watcher.checkpoint.blocks[1].state_updates.is_empty());
+    }
+
+    #[test]
+    fn test_poll_detects_reorg_of_the_head() {
+        let chain = Arc::new(Mutex::new(vec![
+            block(100, 0xa0, 0x9f, false),
+            block(101, 0xa1, 0xa0, true),
+        ]));
+        let server = mock_node(chain.clone(), recorded_sidecars());
+        let mut watcher = watcher(&server, 100);
+        assert_eq!(watcher.poll(&mut |_| {}).unwrap(), 2);
+
+        // Block 101 is replaced, and no block builds upon the new one yet
+        *chain.lock().unwrap() = vec![block(100, 0xa0, 0x9f, false), block(101, 0xb1, 0xa0, false)];
+        let mut events = Vec::new();
+        assert_eq!(watcher.poll(&mut |event| events.push(event)).unwrap(), 1);
+        assert_eq!(
+            events,
+            vec![WatchEvent::Retracted {
+                block_number: 101,
+                block_hash: to_hex(&[0xa1; 32]),
+                transaction_hash: to_hex(&TX_HASH),
+            }]
+        );
+        assert_eq!(watcher.checkpoint.head().unwrap().hash, to_hex(&[0xb1; 32]));
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+        let checkpoint = Checkpoint {
+            blocks: vec![CheckpointBlock {
+                number: 1,
+                hash: to_hex(&[1; 32]),
+                state_updates: vec![to_hex(&[2; 32])],
+            }],
+        };
+        checkpoint.save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), Some(checkpoint));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(Checkpoint::load(&path).is_err());
+    }
+}
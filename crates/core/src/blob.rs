@@ -1,9 +1,19 @@
 use num_bigint::BigUint;
 use num_traits::Num;
+use thiserror::Error;
 
 use crate::math::ifft;
+use crate::polynomial::Polynomial;
 use majin_blob_eip_4844::{BLOB_LEN, BLS_MODULUS, GENERATOR};
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BlobError {
+    #[error("a blob holds at most {BLOB_LEN} field elements, got {0}")]
+    TooManyElements(usize),
+    #[error("field element {0} is not lower than the BLS modulus")]
+    NonCanonicalFieldElement(usize),
+}
+
 /// Recovers the original data from a given blob.
 ///
 /// This function takes a vector of `BigUint` representing the data of a blob and
@@ -27,3 +37,94 @@ pub fn recover(data: Vec<BigUint>) -> Vec<BigUint> {
 
     ifft(data, xs, &BLS_MODULUS)
 }
+
+/// Recovers the original data spread over several blobs, such as the blobs of a single
+/// blob transaction.
+///
+/// # Arguments
+///
+/// * `blobs` - The data of each blob, in order.
+///
+/// # Returns
+///
+/// The data recovered from each blob, concatenated.
+pub fn recover_blobs(blobs: Vec<Vec<BigUint>>) -> Vec<BigUint> {
+    blobs.into_iter().flat_map(recover).collect()
+}
+
+/// Encodes data into a blob, the inverse of [`recover`].
+///
+/// The data is read as the coefficients of a polynomial, and the blob holds its
+/// evaluations in the bit-reversed order defined by EIP-4844.
+///
+/// # Arguments
+///
+/// * `data` - At most `BLOB_LEN` field elements, each lower than the BLS modulus.
+///
+/// # Returns
+///
+/// The blob field elements, or an error if the data does not fit in a blob.
+pub fn encode(data: Vec<BigUint>) -> Result<Vec<BigUint>, BlobError> {
+    if data.len() > BLOB_LEN {
+        return Err(BlobError::TooManyElements(data.len()));
+    }
+    if let Some(index) = data.iter().position(|element| *element >= *BLS_MODULUS) {
+        return Err(BlobError::NonCanonicalFieldElement(index));
+    }
+    Ok(Polynomial::from_coefficients(data).to_blob())
+}
+
+/// Encodes data into as many blobs as needed, the inverse of [`recover_blobs`].
+///
+/// # Arguments
+///
+/// * `data` - The field elements to encode, each lower than the BLS modulus.
+///
+/// # Returns
+///
+/// The field elements of each blob, in order.
+pub fn encode_blobs(data: &[BigUint]) -> Result<Vec<Vec<BigUint>>, BlobError> {
+    data.chunks(BLOB_LEN)
+        .enumerate()
+        .map(|(blob, chunk)| {
+            encode(chunk.to_vec()).map_err(|error| match error {
+                BlobError::NonCanonicalFieldElement(index) => {
+                    BlobError::NonCanonicalFieldElement(blob * BLOB_LEN + index)
+                }
+                error => error,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::Zero;
+
+    #[test]
+    fn test_encode_recover_round_trip() {
+        let data: Vec<BigUint> = (1..=10u32).map(BigUint::from).collect();
+        let blobs = encode_blobs(&data).unwrap();
+        assert_eq!(blobs.len(), 1);
+
+        let recovered = recover_blobs(blobs);
+        assert_eq!(recovered.len(), BLOB_LEN);
+        assert_eq!(recovered[..data.len()], data[..]);
+        assert!(recovered[data.len()..].iter().all(Zero::is_zero));
+    }
+
+    #[test]
+    fn test_encode_rejects_invalid_data() {
+        assert_eq!(
+            encode(vec![BigUint::zero(); BLOB_LEN + 1]),
+            Err(BlobError::TooManyElements(BLOB_LEN + 1))
+        );
+        let mut data = vec![BigUint::zero(); BLOB_LEN + 3];
+        data[BLOB_LEN + 2] = BLS_MODULUS.clone();
+        assert_eq!(
+            encode_blobs(&data),
+            Err(BlobError::NonCanonicalFieldElement(BLOB_LEN + 2))
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use thiserror::Error;
+
+use majin_blob_types::abi::{ContinuousMemoryPage, UpdateState};
+use majin_blob_types::os_output::{OsOutput, OsOutputError, OsOutputVersion};
+use majin_blob_types::serde::parse_state_diffs;
+use majin_blob_types::state_diffs::DataJson;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CalldataError {
+    #[error(transparent)]
+    OsOutput(#[from] OsOutputError),
+    #[error("the program output publishes its data in blobs")]
+    KzgDa,
+    #[error("the state update publishes {expected} felts of data, the memory pages hold {actual}")]
+    DataSize { expected: BigUint, actual: usize },
+}
+
+/// Recovers the state diffs published in the output of the Starknet OS, following its
+/// message segments, as before blobs.
+///
+/// # Arguments
+///
+/// * `output` - The output of the OS, as felts.
+/// * `version` - The layout of the output.
+///
+/// # Returns
+///
+/// The state diffs, as [`parse_state_diffs`] returns them for blobs.
+pub fn state_diffs_from_output(
+    output: &[BigUint],
+    version: OsOutputVersion,
+) -> Result<DataJson, CalldataError> {
+    let os_output = OsOutput::parse(output, version)?;
+    if os_output.header.use_kzg_da {
+        return Err(CalldataError::KzgDa);
+    }
+    Ok(parse_state_diffs(&output[os_output.len..]))
+}
+
+/// Recovers the state diffs published in continuous memory pages.
+///
+/// The values of the pages are concatenated in the order of their start address, so the
+/// pages can be given in any order.
+///
+/// # Arguments
+///
+/// * `pages` - The pages holding the data, without the page of the program output.
+///
+/// # Returns
+///
+/// The state diffs, as [`parse_state_diffs`] returns them for blobs.
+pub fn state_diffs_from_memory_pages(pages: &[ContinuousMemoryPage]) -> DataJson {
+    parse_state_diffs(&memory_pages_data(pages))
+}
+
+/// Recovers the state diffs of an `updateState` call, from the memory pages it refers to,
+/// or from its program output if the data was not published in pages.
+///
+/// # Arguments
+///
+/// * `call` - The decoded calldata.
+/// * `pages` - The pages holding the data, without the page of the program output.
+/// * `version` - The layout of the program output.
+pub fn decode_legacy_state_update(
+    call: &UpdateState,
+    pages: &[ContinuousMemoryPage],
+    version: OsOutputVersion,
+) -> Result<DataJson, CalldataError> {
+    if pages.is_empty() {
+        return state_diffs_from_output(&call.program_output, version);
+    }
+    let data = memory_pages_data(pages);
+    if call.onchain_data_size.to_usize() != Some(data.len()) {
+        return Err(CalldataError::DataSize {
+            expected: call.onchain_data_size.clone(),
+            actual: data.len(),
+        });
+    }
+    Ok(parse_state_diffs(&data))
+}
+
+fn memory_pages_data(pages: &[ContinuousMemoryPage]) -> Vec<BigUint> {
+    let mut pages: Vec<&ContinuousMemoryPage> = pages.iter().collect();
+    pages.sort_by(|a, b| a.start_address.cmp(&b.start_address));
+    pages
+        .into_iter()
+        .flat_map(|page| page.values.iter().cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::recover;
+    use majin_blob_types::serde::{parse_file_to_blob_data, state_diffs_len};
+
+    /// The state diffs of the mainnet blob fixture, as felts.
+    fn state_diffs_data() -> Vec<BigUint> {
+        let data = recover(parse_file_to_blob_data("../../examples/blob/mainnet.txt"));
+        data[..state_diffs_len(&data)].to_vec()
+    }
+
+    fn page(start_address: u32, values: &[BigUint]) -> ContinuousMemoryPage {
+        ContinuousMemoryPage {
+            start_address: BigUint::from(start_address),
+            values: values.to_vec(),
+            z: BigUint::from(1u32),
+            alpha: BigUint::from(2u32),
+            prime: BigUint::from(3u32),
+        }
+    }
+
+    #[test]
+    fn test_state_diffs_from_output() {
+        let data = state_diffs_data();
+        // Header of 0.13.0, then no messages
+        let mut output: Vec<BigUint> = [1u32, 2, 100, 4, 6, 0, 0].map(BigUint::from).to_vec();
+        output.extend(data.iter().cloned());
+        assert_eq!(
+            state_diffs_from_output(&output, OsOutputVersion::V0_13_0),
+            Ok(parse_state_diffs(&data))
+        );
+
+        // With 0.13.1, the sixth felt is `use_kzg_da`
+        let output = [1u32, 2, 100, 4, 6, 1, 7, 0, 0, 0].map(BigUint::from);
+        assert_eq!(
+            state_diffs_from_output(&output, OsOutputVersion::V0_13_1),
+            Err(CalldataError::KzgDa)
+        );
+    }
+
+    #[test]
+    fn test_decode_legacy_state_update() {
+        let data = state_diffs_data();
+        let (first, second) = data.split_at(data.len() / 2);
+        let pages = [page(2000, second), page(1000, first)];
+        let mut call = UpdateState {
+            program_output: Vec::new(),
+            onchain_data_hash: BigUint::from(0u32),
+            onchain_data_size: BigUint::from(data.len()),
+        };
+        assert_eq!(
+            decode_legacy_state_update(&call, &pages, OsOutputVersion::V0_13_0),
+            Ok(parse_state_diffs(&data))
+        );
+        assert_eq!(
+            state_diffs_from_memory_pages(&pages),
+            parse_state_diffs(&data)
+        );
+
+        call.onchain_data_size += 1u32;
+        assert!(matches!(
+            decode_legacy_state_update(&call, &pages, OsOutputVersion::V0_13_0),
+            Err(CalldataError::DataSize { .. })
+        ));
+    }
+}
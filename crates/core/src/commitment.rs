@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use num_bigint::BigUint;
+
+use crate::hash::{poseidon_hash_many, HashError};
+use majin_blob_types::state_diffs::DataJson;
+
+/// Prefix of the hash chain of a state diff commitment, the short string
+/// `STARKNET_STATE_DIFF0`.
+const STARKNET_STATE_DIFF0: &[u8] = b"STARKNET_STATE_DIFF0";
+
+/// A state diff in the canonical form Starknet block headers commit to.
+///
+/// Blob state diffs do not tell deployed contracts from replaced classes, which the
+/// commitment does not either, and do not carry the classes declared without a compiled
+/// class hash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// The class hashes of the deployed contracts and of the contracts whose class was
+    /// replaced, by address.
+    pub deployed_or_replaced: BTreeMap<BigUint, BigUint>,
+    /// The compiled class hashes of the declared classes, by class hash.
+    pub declared_classes: BTreeMap<BigUint, BigUint>,
+    /// The class hashes of the declared Cairo 0 classes.
+    pub deprecated_declared_classes: BTreeSet<BigUint>,
+    /// The storage writes, by address and key.
+    pub storage_diffs: BTreeMap<BigUint, BTreeMap<BigUint, BigUint>>,
+    /// The new nonces, by address.
+    pub nonces: BTreeMap<BigUint, BigUint>,
+}
+
+impl StateDiff {
+    /// Converts state diffs recovered from blobs.
+    ///
+    /// Blobs carry the nonce of every contract they touch, not only the updated ones.
+    /// Zero nonces cannot have been updated and are left out, other nonces that were not
+    /// updated must be removed from [`StateDiff::nonces`] for the commitment to match.
+    pub fn from_data_json(data: &DataJson) -> Self {
+        let mut diff = StateDiff::default();
+        for contract in &data.state_update {
+            if let Some(class_hash) = &contract.new_class_hash {
+                diff.deployed_or_replaced
+                    .insert(contract.address.clone(), class_hash.clone());
+            }
+            if contract.nonce != 0 {
+                diff.nonces
+                    .insert(contract.address.clone(), BigUint::from(contract.nonce));
+            }
+            if !contract.storage_updates.is_empty() {
+                diff.storage_diffs.insert(
+                    contract.address.clone(),
+                    contract
+                        .storage_updates
+                        .iter()
+                        .map(|update| (update.key.clone(), update.value.clone()))
+                        .collect(),
+                );
+            }
+        }
+        diff.declared_classes = data
+            .class_declaration
+            .iter()
+            .map(|class| (class.class_hash.clone(), class.compiled_class_hash.clone()))
+            .collect();
+        diff
+    }
+
+    /// Merges the diff of the following blocks into this one, so that it covers the
+    /// whole range. Values written by `next` take precedence.
+    pub fn merge(&mut self, next: &StateDiff) {
+        self.deployed_or_replaced
+            .extend(next.deployed_or_replaced.clone());
+        self.declared_classes.extend(next.declared_classes.clone());
+        self.deprecated_declared_classes
+            .extend(next.deprecated_declared_classes.clone());
+        for (address, storage) in &next.storage_diffs {
+            self.storage_diffs
+                .entry(address.clone())
+                .or_default()
+                .extend(storage.clone());
+        }
+        self.nonces.extend(next.nonces.clone());
+    }
+
+    /// Computes the commitment of the state diff, the Poseidon hash of its canonical
+    /// serialization, sorted by address, class hash and storage key.
+    ///
+    /// Fails if a value of the diff is not a felt, which a diff read from a malformed blob
+    /// may hold.
+    pub fn commitment(&self) -> Result<BigUint, HashError> {
+        let mut chain = vec![BigUint::from_bytes_be(STARKNET_STATE_DIFF0)];
+
+        chain.push(BigUint::from(self.deployed_or_replaced.len()));
+        for (address, class_hash) in &self.deployed_or_replaced {
+            chain.extend([address.clone(), class_hash.clone()]);
+        }
+        chain.push(BigUint::from(self.declared_classes.len()));
+        for (class_hash, compiled_class_hash) in &self.declared_classes {
+            chain.extend([class_hash.clone(), compiled_class_hash.clone()]);
+        }
+        chain.push(BigUint::from(self.deprecated_declared_classes.len()));
+        chain.extend(self.deprecated_declared_classes.iter().cloned());
+
+        // A single data availability mode, L1
+        chain.extend([BigUint::from(1u32), BigUint::from(0u32)]);
+
+        let storage_diffs: Vec<_> = self
+            .storage_diffs
+            .iter()
+            .filter(|(_, storage)| !storage.is_empty())
+            .collect();
+        chain.push(BigUint::from(storage_diffs.len()));
+        for (address, storage) in storage_diffs {
+            chain.extend([address.clone(), BigUint::from(storage.len())]);
+            for (key, value) in storage {
+                chain.extend([key.clone(), value.clone()]);
+            }
+        }
+        chain.push(BigUint::from(self.nonces.len()));
+        for (address, nonce) in &self.nonces {
+            chain.extend([address.clone(), nonce.clone()]);
+        }
+        poseidon_hash_many(&chain)
+    }
+}
+
+/// Computes the commitment of the state diffs of a range of blocks, see
+/// [`StateDiff::commitment`].
+///
+/// # Arguments
+///
+/// * `state_diffs` - The state diffs of successive blocks or block ranges, in order.
+pub fn state_diff_commitment<'a>(
+    state_diffs: impl IntoIterator<Item = &'a DataJson>,
+) -> Result<BigUint, HashError> {
+    let mut merged = StateDiff::default();
+    for data in state_diffs {
+        merged.merge(&StateDiff::from_data_json(data));
+    }
+    merged.commitment()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin_blob_types::state_diffs::{ClassDeclaration, ContractUpdate, StorageUpdate};
+
+    fn big(value: u64) -> BigUint {
+        BigUint::from(value)
+    }
+
+    fn contract(
+        address: u64,
+        nonce: u64,
+        class_hash: Option<u64>,
+        storage: &[(u64, u64)],
+    ) -> ContractUpdate {
+        ContractUpdate {
+            address: big(address),
+            nonce,
+            number_of_storage_updates: storage.len() as u64,
+            new_class_hash: class_hash.map(big),
+            storage_updates: storage
+                .iter()
+                .map(|(key, value)| StorageUpdate {
+                    key: big(*key),
+                    value: big(*value),
+                })
+                .collect(),
+        }
+    }
+
+    fn data(state_update: Vec<ContractUpdate>, classes: &[(u64, u64)]) -> DataJson {
+        DataJson {
+            state_update_size: state_update.len() as u64,
+            state_update,
+            class_declaration_size: classes.len() as u64,
+            class_declaration: classes
+                .iter()
+                .map(|(class_hash, compiled_class_hash)| ClassDeclaration {
+                    class_hash: big(*class_hash),
+                    compiled_class_hash: big(*compiled_class_hash),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_commitment() {
+        // Contracts and storage keys out of order
+        let diff = data(
+            vec![
+                contract(20, 0, None, &[(7, 70), (3, 30)]),
+                contract(10, 2, Some(0xc1), &[]),
+            ],
+            &[(0xc1, 0xcc)],
+        );
+        let prefix = BigUint::from_bytes_be(b"STARKNET_STATE_DIFF0");
+        let chain: Vec<BigUint> = [
+            // Deployed or replaced contracts, then declared classes
+            vec![
+                prefix,
+                big(1),
+                big(10),
+                big(0xc1),
+                big(1),
+                big(0xc1),
+                big(0xcc),
+            ],
+            // No Cairo 0 classes, then the data availability mode
+            vec![big(0), big(1), big(0)],
+            // Storage diffs of a single contract, sorted by key
+            vec![big(1), big(20), big(2), big(3), big(30), big(7), big(70)],
+            // The nonce of contract 20 is zero, not an update
+            vec![big(1), big(10), big(2)],
+        ]
+        .concat();
+        assert_eq!(state_diff_commitment([&diff]), poseidon_hash_many(&chain));
+
+        let mut invalid = diff.clone();
+        invalid.state_update[0].storage_updates[0].value = BigUint::from(1u32) << 252u32;
+        assert!(state_diff_commitment([&invalid]).is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let first = data(vec![contract(10, 1, Some(0xc1), &[(3, 30), (4, 40)])], &[]);
+        let second = data(vec![contract(10, 2, None, &[(3, 31)])], &[(0xc1, 0xcc)]);
+        let merged = data(
+            vec![contract(10, 2, Some(0xc1), &[(3, 31), (4, 40)])],
+            &[(0xc1, 0xcc)],
+        );
+        assert_eq!(
+            state_diff_commitment([&first, &second]),
+            state_diff_commitment([&merged])
+        );
+        assert_ne!(
+            state_diff_commitment([&second, &first]),
+            state_diff_commitment([&merged])
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use num_bigint::BigUint;
+use starknet_crypto::FieldElement;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HashError {
+    #[error("{0} is not a valid felt")]
+    InvalidFelt(BigUint),
+}
+
+/// Converts a `BigUint` into a Stark field element.
+///
+/// # Arguments
+///
+/// * `value` - The value to convert. Must be lower than the Stark field prime.
+///
+/// # Returns
+///
+/// The field element, or `None` if the value does not fit in the field.
+pub fn to_field_element(value: &BigUint) -> Option<FieldElement> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > 32 {
+        return None;
+    }
+    let mut buffer = [0u8; 32];
+    buffer[32 - bytes.len()..].copy_from_slice(&bytes);
+    FieldElement::from_bytes_be(&buffer).ok()
+}
+
+/// Converts a Stark field element into a `BigUint`.
+pub fn from_field_element(value: &FieldElement) -> BigUint {
+    BigUint::from_bytes_be(&value.to_bytes_be())
+}
+
+/// Converts a `BigUint` into a Stark field element, failing on values out of the field.
+fn felt(value: &BigUint) -> Result<FieldElement, HashError> {
+    to_field_element(value).ok_or_else(|| HashError::InvalidFelt(value.clone()))
+}
+
+/// Computes the Poseidon hash of a sequence of felts, as `poseidon_hash_many` in Cairo.
+///
+/// # Arguments
+///
+/// * `values` - The felts to hash.
+///
+/// # Returns
+///
+/// The hash as a `BigUint`, or an error if a value is not lower than the Stark field
+/// prime.
+pub fn poseidon_hash_many(values: &[BigUint]) -> Result<BigUint, HashError> {
+    let elements = values.iter().map(felt).collect::<Result<Vec<_>, _>>()?;
+    Ok(from_field_element(&starknet_crypto::poseidon_hash_many(
+        &elements,
+    )))
+}
+
+/// Computes the Poseidon hash of two felts, as `poseidon_hash` in Cairo.
+pub fn poseidon_hash(x: &BigUint, y: &BigUint) -> Result<BigUint, HashError> {
+    Ok(from_field_element(&starknet_crypto::poseidon_hash(
+        felt(x)?,
+        felt(y)?,
+    )))
+}
+
+/// Computes the Pedersen hash of two felts, as `pedersen` in Cairo.
+pub fn pedersen_hash(x: &BigUint, y: &BigUint) -> Result<BigUint, HashError> {
+    Ok(from_field_element(&starknet_crypto::pedersen_hash(
+        &felt(x)?,
+        &felt(y)?,
+    )))
+}
+
+/// Computes the Starknet Keccak hash of a string: the Keccak-256 hash of its bytes,
+/// truncated to 250 bits. It gives the selectors of functions and the base addresses of
+/// storage variables.
+pub fn sn_keccak(value: &str) -> BigUint {
+    let hash = BigUint::from_bytes_be(&majin_blob_eip_4844::transaction::keccak256(
+        value.as_bytes(),
+    ));
+    hash & ((BigUint::from(1u32) << 250u32) - 1u32)
+}
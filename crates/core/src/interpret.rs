@@ -0,0 +1,358 @@
+//! Heuristic interpretations of storage values.
+//!
+//! Storage values are bare felts. Their shape hints at what they hold: a short ASCII
+//! string, a timestamp, the address of a contract of the same diff, one half of a `u256`
+//! stored next to the other. None of these is certain, each interpretation comes with a
+//! confidence.
+
+use std::collections::{BTreeMap, HashSet};
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::{Serialize, Serializer};
+
+use majin_blob_types::state_diffs::DataJson;
+
+/// Timestamps before the launch of Starknet mainnet, in November 2021, are not taken for
+/// timestamps.
+const MIN_TIMESTAMP: u64 = 1_636_000_000;
+
+/// Timestamps after 2040 are not taken for timestamps.
+const MAX_TIMESTAMP: u64 = 2_208_988_800;
+
+/// Short strings have at most 31 characters to fit in a felt.
+const MAX_SHORT_STRING_LEN: usize = 31;
+
+/// A kind of value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueKind {
+    /// The address of a contract known from the state diffs.
+    Address,
+    /// A Cairo short string, ASCII characters packed in a felt.
+    ShortString,
+    /// A Unix timestamp, in seconds.
+    Timestamp,
+    /// The low or high 128-bit half of a `u256`.
+    U256Half,
+    /// A boolean, 0 or 1.
+    Bool,
+    /// A small integer, such as a counter, an amount or an enum.
+    SmallInt,
+}
+
+/// How likely an interpretation is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// A likely meaning of a storage value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Interpretation {
+    pub kind: ValueKind,
+    pub confidence: Confidence,
+    /// The value as the kind reads it, such as the string or the date.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl Interpretation {
+    fn new(kind: ValueKind, confidence: Confidence, text: Option<String>) -> Self {
+        Interpretation {
+            kind,
+            confidence,
+            text,
+        }
+    }
+}
+
+/// Interprets the storage values of state diffs.
+#[derive(Debug, Clone, Default)]
+pub struct ValueInterpreter {
+    known_addresses: HashSet<BigUint>,
+}
+
+impl ValueInterpreter {
+    /// Creates an interpreter knowing the contracts of `data` as addresses.
+    pub fn from_state_diffs(data: &DataJson) -> Self {
+        ValueInterpreter {
+            known_addresses: data
+                .state_update
+                .iter()
+                .map(|contract| contract.address.clone())
+                .collect(),
+        }
+    }
+
+    /// Adds addresses known from elsewhere.
+    pub fn extend_addresses(&mut self, addresses: impl IntoIterator<Item = BigUint>) {
+        self.known_addresses.extend(addresses);
+    }
+
+    /// Interprets a value written to a storage slot.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value.
+    /// * `neighbours` - Whether the contract also writes the previous and the next slot,
+    ///   which makes the value likely to be half of a `u256`.
+    ///
+    /// # Returns
+    ///
+    /// The likely meanings of the value, most confident first. Zero has none.
+    pub fn interpret(&self, value: &BigUint, neighbours: (bool, bool)) -> Vec<Interpretation> {
+        let mut interpretations = Vec::new();
+        if value.bits() == 0 {
+            return interpretations;
+        }
+        if self.known_addresses.contains(value) {
+            interpretations.push(Interpretation::new(
+                ValueKind::Address,
+                Confidence::High,
+                Some(format!("0x{}", value.to_str_radix(16))),
+            ));
+        }
+        if let Some(string) = short_string(value) {
+            let confidence = match string.len() {
+                4.. => Confidence::High,
+                _ => Confidence::Low,
+            };
+            interpretations.push(Interpretation::new(
+                ValueKind::ShortString,
+                confidence,
+                Some(string),
+            ));
+        }
+        if let Some(seconds) = value.to_u64() {
+            if (MIN_TIMESTAMP..MAX_TIMESTAMP).contains(&seconds) {
+                interpretations.push(Interpretation::new(
+                    ValueKind::Timestamp,
+                    Confidence::Medium,
+                    Some(format_timestamp(seconds)),
+                ));
+            }
+        }
+        if value.bits() <= 128 {
+            let half = match neighbours {
+                (_, true) => Some("low"),
+                (true, false) => Some("high"),
+                (false, false) => None,
+            };
+            if let Some(half) = half {
+                interpretations.push(Interpretation::new(
+                    ValueKind::U256Half,
+                    Confidence::Medium,
+                    Some(half.to_string()),
+                ));
+            }
+        }
+        if *value == BigUint::from(1u32) {
+            interpretations.push(Interpretation::new(
+                ValueKind::Bool,
+                Confidence::Medium,
+                Some("true".to_string()),
+            ));
+        }
+        if value.bits() <= 64 {
+            let confidence = match value.bits() {
+                0..=16 => Confidence::Medium,
+                _ => Confidence::Low,
+            };
+            interpretations.push(Interpretation::new(
+                ValueKind::SmallInt,
+                confidence,
+                Some(value.to_str_radix(10)),
+            ));
+        }
+        // Stable, so that equally confident kinds keep their order
+        interpretations.sort_by_key(|interpretation| std::cmp::Reverse(interpretation.confidence));
+        interpretations
+    }
+
+    /// Interprets the storage values written by state diffs.
+    pub fn interpret_state_diffs(&self, data: &DataJson) -> StorageInterpretations {
+        let mut interpretations = StorageInterpretations::default();
+        for contract in &data.state_update {
+            let written: HashSet<&BigUint> = contract
+                .storage_updates
+                .iter()
+                .map(|update| &update.key)
+                .collect();
+            for update in &contract.storage_updates {
+                let previous = update.key.bits() > 0 && written.contains(&(&update.key - 1u32));
+                let next = written.contains(&(&update.key + 1u32));
+                let found = self.interpret(&update.value, (previous, next));
+                if !found.is_empty() {
+                    interpretations
+                        .0
+                        .entry(contract.address.clone())
+                        .or_default()
+                        .insert(update.key.clone(), found);
+                }
+            }
+        }
+        interpretations
+    }
+}
+
+/// Interpretations of storage values, by contract address, then storage key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageInterpretations(pub BTreeMap<BigUint, BTreeMap<BigUint, Vec<Interpretation>>>);
+
+impl Serialize for StorageInterpretations {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(contract, values)| {
+            let values: BTreeMap<String, &Vec<Interpretation>> = values
+                .iter()
+                .map(|(key, interpretations)| (key.to_str_radix(10), interpretations))
+                .collect();
+            (contract.to_str_radix(10), values)
+        }))
+    }
+}
+
+/// Decodes a Cairo short string: at least two printable ASCII characters, big-endian.
+fn short_string(value: &BigUint) -> Option<String> {
+    let bytes = value.to_bytes_be();
+    let printable = bytes.iter().all(|byte| (0x20..0x7f).contains(byte));
+    match printable && (2..=MAX_SHORT_STRING_LEN).contains(&bytes.len()) {
+        true => String::from_utf8(bytes).ok(),
+        false => None,
+    }
+}
+
+/// Formats a Unix timestamp as an RFC 3339 UTC date.
+fn format_timestamp(seconds: u64) -> String {
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    // Civil date from days since 1970-01-01, in eras of 400 years starting in March
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = match month_index {
+        0..=9 => month_index + 3,
+        _ => month_index - 9,
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin_blob_types::state_diffs::{ContractUpdate, StorageUpdate};
+
+    fn kinds(interpretations: &[Interpretation]) -> Vec<(ValueKind, Confidence)> {
+        interpretations
+            .iter()
+            .map(|interpretation| (interpretation.kind, interpretation.confidence))
+            .collect()
+    }
+
+    #[test]
+    fn test_interpret() {
+        let interpreter = ValueInterpreter::default();
+        // A 5-byte string is also a 39-bit integer, a less likely one
+        let name = interpreter.interpret(&BigUint::from_bytes_be(b"Ether"), (false, false));
+        assert_eq!(name[0].text.as_deref(), Some("Ether"));
+        assert_eq!(
+            kinds(&name),
+            [
+                (ValueKind::ShortString, Confidence::High),
+                (ValueKind::SmallInt, Confidence::Low)
+            ]
+        );
+        assert_eq!(
+            kinds(&interpreter.interpret(&BigUint::from(1u32), (false, false))),
+            [
+                (ValueKind::Bool, Confidence::Medium),
+                (ValueKind::SmallInt, Confidence::Medium)
+            ]
+        );
+        assert!(interpreter
+            .interpret(&BigUint::from(0u32), (false, false))
+            .is_empty());
+
+        let timestamp = interpreter.interpret(&BigUint::from(1_710_288_000u64), (false, false));
+        assert_eq!(timestamp[0].kind, ValueKind::Timestamp);
+        assert_eq!(timestamp[0].text.as_deref(), Some("2024-03-13T00:00:00Z"));
+        assert_eq!(format_timestamp(951_827_696), "2000-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn test_interpret_state_diffs() {
+        let token = BigUint::from(0x7u32) << 240u32;
+        let owner = BigUint::from(0x9u32) << 240u32;
+        let update = |key: u32, value: BigUint| StorageUpdate {
+            key: BigUint::from(key),
+            value,
+        };
+        let data = DataJson {
+            state_update_size: 2,
+            state_update: vec![
+                ContractUpdate {
+                    address: token.clone(),
+                    nonce: 0,
+                    number_of_storage_updates: 3,
+                    new_class_hash: None,
+                    storage_updates: vec![
+                        update(10, owner.clone()),
+                        // A u256 balance over two slots
+                        update(20, BigUint::from(u128::MAX)),
+                        update(21, BigUint::from(3u32)),
+                    ],
+                },
+                ContractUpdate {
+                    address: owner.clone(),
+                    nonce: 1,
+                    number_of_storage_updates: 0,
+                    new_class_hash: None,
+                    storage_updates: Vec::new(),
+                },
+            ],
+            class_declaration_size: 0,
+            class_declaration: Vec::new(),
+        };
+        let interpretations =
+            ValueInterpreter::from_state_diffs(&data).interpret_state_diffs(&data);
+        let values = &interpretations.0[&token];
+        assert_eq!(
+            kinds(&values[&BigUint::from(10u32)]),
+            [(ValueKind::Address, Confidence::High)]
+        );
+        assert_eq!(
+            values[&BigUint::from(20u32)][0].text.as_deref(),
+            Some("low")
+        );
+        assert_eq!(
+            kinds(&values[&BigUint::from(21u32)]),
+            [
+                (ValueKind::U256Half, Confidence::Medium),
+                (ValueKind::SmallInt, Confidence::Medium)
+            ]
+        );
+        assert_eq!(
+            values[&BigUint::from(21u32)][0].text.as_deref(),
+            Some("high")
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use num_bigint::BigUint;
+use num_traits::One;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::hash::{poseidon_hash, poseidon_hash_many, HashError};
+use crate::polynomial::{Polynomial, PolynomialError};
+use majin_blob_eip_4844::{BLOB_LEN, BYTES_PER_COMMITMENT};
+use majin_blob_types::os_output::KzgSegment;
+use majin_blob_types::state_diffs::{serialize_biguint, serialize_option_biguint};
+
+/// A compressed BLS12-381 G1 point committing to a blob.
+pub type KzgCommitment = [u8; BYTES_PER_COMMITMENT];
+
+/// Number of bits in each of the two felts holding a KZG commitment in the OS output.
+const COMMITMENT_LIMB_BITS: u64 = 192;
+/// Number of bits in each of the two felts holding an evaluation in the OS output.
+const EVALUATION_LIMB_BITS: u64 = 128;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KzgError {
+    #[error("KZG commitment limb does not fit in {COMMITMENT_LIMB_BITS} bits")]
+    InvalidCommitmentLimb,
+    #[error("evaluation limb does not fit in {EVALUATION_LIMB_BITS} bits")]
+    InvalidEvaluationLimb,
+    #[error("expected a blob of {BLOB_LEN} field elements, got {0}")]
+    InvalidBlobLength(usize),
+    #[error("got {blobs} blobs for {claims} claims")]
+    ClaimCount { blobs: usize, claims: usize },
+    #[error(transparent)]
+    Hash(#[from] HashError),
+}
+
+/// A point evaluation claim for one blob, as posted by Starknet to its core contract.
+///
+/// The Starknet OS output of an `updateStateKzgDA` call holds, for every blob, the
+/// evaluation point `x` (shared by all blobs), the KZG commitment split into two 192-bit
+/// felts and the evaluation `y` split into two 128-bit felts, low half first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarknetKzgClaim {
+    pub x: BigUint,
+    pub y: BigUint,
+    pub commitment: KzgCommitment,
+}
+
+impl StarknetKzgClaim {
+    /// Builds a claim from the felts of the KZG segment of the OS output.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The evaluation point.
+    /// * `commitment_low` - The low 192 bits of the commitment.
+    /// * `commitment_high` - The high 192 bits of the commitment.
+    /// * `y_low` - The low 128 bits of the evaluation.
+    /// * `y_high` - The high 128 bits of the evaluation.
+    pub fn from_output_felts(
+        x: BigUint,
+        commitment_low: &BigUint,
+        commitment_high: &BigUint,
+        y_low: &BigUint,
+        y_high: &BigUint,
+    ) -> Result<Self, KzgError> {
+        Ok(StarknetKzgClaim {
+            x,
+            y: join_evaluation(y_low, y_high)?,
+            commitment: join_commitment(commitment_low, commitment_high)?,
+        })
+    }
+}
+
+/// Reads the claims of the KZG segment of an OS output, one per blob.
+pub fn claims_from_segment(segment: &KzgSegment) -> Result<Vec<StarknetKzgClaim>, KzgError> {
+    segment
+        .commitments
+        .iter()
+        .zip(&segment.evaluations)
+        .map(|([commitment_low, commitment_high], [y_low, y_high])| {
+            StarknetKzgClaim::from_output_felts(
+                segment.z.clone(),
+                commitment_low,
+                commitment_high,
+                y_low,
+                y_high,
+            )
+        })
+        .collect()
+}
+
+/// Outcome of checking a [`StarknetKzgClaim`] against a blob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PointEvaluationReport {
+    #[serde(serialize_with = "serialize_biguint")]
+    pub x: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub claimed_y: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub computed_y: BigUint,
+    pub y_matches: bool,
+    /// The evaluation point derived from the state diff, when the state diff was provided.
+    #[serde(serialize_with = "serialize_option_biguint")]
+    pub derived_x: Option<BigUint>,
+    pub x_matches: Option<bool>,
+}
+
+impl PointEvaluationReport {
+    /// Returns `true` if the blob matches the claim.
+    pub fn is_valid(&self) -> bool {
+        self.y_matches && self.x_matches != Some(false)
+    }
+}
+
+/// Splits a KZG commitment into the `(low, high)` 192-bit felts used in the OS output.
+pub fn split_commitment(commitment: &KzgCommitment) -> (BigUint, BigUint) {
+    let value = BigUint::from_bytes_be(commitment);
+    let mask = (BigUint::one() << COMMITMENT_LIMB_BITS) - 1u32;
+    (&value & mask, value >> COMMITMENT_LIMB_BITS)
+}
+
+/// Joins the `(low, high)` 192-bit felts of the OS output into a KZG commitment.
+pub fn join_commitment(low: &BigUint, high: &BigUint) -> Result<KzgCommitment, KzgError> {
+    if low.bits() > COMMITMENT_LIMB_BITS || high.bits() > COMMITMENT_LIMB_BITS {
+        return Err(KzgError::InvalidCommitmentLimb);
+    }
+    let bytes = ((high << COMMITMENT_LIMB_BITS) + low).to_bytes_be();
+    let mut commitment = [0u8; BYTES_PER_COMMITMENT];
+    commitment[BYTES_PER_COMMITMENT - bytes.len()..].copy_from_slice(&bytes);
+    Ok(commitment)
+}
+
+/// Joins the `(low, high)` 128-bit felts of the OS output into an evaluation.
+pub fn join_evaluation(low: &BigUint, high: &BigUint) -> Result<BigUint, KzgError> {
+    if low.bits() > EVALUATION_LIMB_BITS || high.bits() > EVALUATION_LIMB_BITS {
+        return Err(KzgError::InvalidEvaluationLimb);
+    }
+    Ok((high << EVALUATION_LIMB_BITS) + low)
+}
+
+/// Derives the evaluation point the Starknet OS uses for its blobs.
+///
+/// `x = poseidon(poseidon_many(data), poseidon_many(commitment felts))`, where the
+/// commitment felts are the `(low, high)` halves of every commitment, in blob order.
+///
+/// # Arguments
+///
+/// * `data` - The full data availability segment, i.e. the state diff felts before they
+///   were split into blobs.
+/// * `commitments` - The KZG commitments of the blobs, in order.
+///
+/// # Returns
+///
+/// The evaluation point as a felt, or an error if a value of `data` is not a felt.
+pub fn compute_evaluation_point(
+    data: &[BigUint],
+    commitments: &[KzgCommitment],
+) -> Result<BigUint, HashError> {
+    let commitment_felts: Vec<BigUint> = commitments
+        .iter()
+        .flat_map(|commitment| {
+            let (low, high) = split_commitment(commitment);
+            [low, high]
+        })
+        .collect();
+    poseidon_hash(
+        &poseidon_hash_many(data)?,
+        &poseidon_hash_many(&commitment_felts)?,
+    )
+}
+
+/// Evaluates the polynomial of a blob at `x`, directly from its evaluation form.
+///
+/// # Arguments
+///
+/// * `blob` - The blob field elements, as returned by `parse_str_to_blob_data`.
+/// * `x` - The evaluation point.
+pub fn evaluate_blob(blob: &[BigUint], x: &BigUint) -> Result<BigUint, KzgError> {
+    let polynomial = Polynomial::from_blob(blob).map_err(|e| match e {
+        PolynomialError::InvalidBlobLength(len) | PolynomialError::InvalidDomainSize(len) => {
+            KzgError::InvalidBlobLength(len)
+        }
+    })?;
+    Ok(polynomial.evaluate(x))
+}
+
+/// Checks the point evaluation claims of a state update against its blobs.
+///
+/// # Arguments
+///
+/// * `blobs` - The field elements of every blob of the state update, in order, as returned
+///   by `parse_str_to_blob_data`.
+/// * `claims` - The claims posted on L1, one per blob.
+/// * `data` - The data availability segment the claims cover, over all the blobs. When
+///   provided, `x` is derived from it and from every claimed commitment and compared with
+///   the claimed `x`.
+///
+/// # Returns
+///
+/// A report of which parts of the claim hold, one per blob.
+pub fn check_point_evaluation(
+    blobs: &[Vec<BigUint>],
+    claims: &[StarknetKzgClaim],
+    data: Option<&[BigUint]>,
+) -> Result<Vec<PointEvaluationReport>, KzgError> {
+    if blobs.len() != claims.len() {
+        return Err(KzgError::ClaimCount {
+            blobs: blobs.len(),
+            claims: claims.len(),
+        });
+    }
+    let commitments: Vec<KzgCommitment> = claims.iter().map(|claim| claim.commitment).collect();
+    let derived_x = data
+        .map(|data| compute_evaluation_point(data, &commitments))
+        .transpose()?;
+    blobs
+        .iter()
+        .zip(claims)
+        .map(|(blob, claim)| {
+            let computed_y = evaluate_blob(blob, &claim.x)?;
+            Ok(PointEvaluationReport {
+                x: claim.x.clone(),
+                claimed_y: claim.y.clone(),
+                y_matches: computed_y == claim.y,
+                computed_y,
+                x_matches: derived_x.as_ref().map(|derived_x| *derived_x == claim.x),
+                derived_x: derived_x.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::{encode, recover};
+    use majin_blob_types::serde::{parse_file_to_blob_data, state_diffs_len};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_commitment_split_round_trip() {
+        let mut commitment = [0u8; BYTES_PER_COMMITMENT];
+        commitment
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, b)| *b = i as u8 + 0x80);
+        let (low, high) = split_commitment(&commitment);
+        assert!(low.bits() <= 192 && high.bits() <= 192);
+        assert_eq!(join_commitment(&low, &high), Ok(commitment));
+        assert_eq!(
+            join_commitment(&(BigUint::one() << 192u32), &high),
+            Err(KzgError::InvalidCommitmentLimb)
+        );
+    }
+
+    #[test]
+    fn test_join_evaluation() {
+        let y = join_evaluation(&BigUint::from(2u32), &BigUint::one()).unwrap();
+        assert_eq!(y, (BigUint::one() << 128u32) + 2u32);
+        assert_eq!(
+            join_evaluation(&(BigUint::one() << 128u32), &BigUint::one()),
+            Err(KzgError::InvalidEvaluationLimb)
+        );
+    }
+
+    #[test]
+    fn test_check_point_evaluation() {
+        let blob = parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+        let coefficients = recover(blob.clone());
+        let data = &coefficients[..state_diffs_len(&coefficients)];
+        let commitment = [0xa5u8; BYTES_PER_COMMITMENT];
+        let x = compute_evaluation_point(data, &[commitment]).unwrap();
+        let y = Polynomial::from_coefficients(coefficients.clone()).evaluate(&x);
+
+        let claim = StarknetKzgClaim { x, y, commitment };
+        let blobs = [blob];
+        let report =
+            check_point_evaluation(&blobs, std::slice::from_ref(&claim), Some(data)).unwrap();
+        assert!(report[0].y_matches);
+        assert_eq!(report[0].x_matches, Some(true));
+        assert!(report[0].is_valid());
+
+        let wrong_claim = StarknetKzgClaim {
+            y: BigUint::from_str("42").unwrap(),
+            ..claim
+        };
+        let report = check_point_evaluation(&blobs, &[wrong_claim], None).unwrap();
+        assert!(!report[0].y_matches);
+        assert_eq!(report[0].x_matches, None);
+        assert!(!report[0].is_valid());
+    }
+
+    #[test]
+    fn test_check_point_evaluation_two_blobs() {
+        let first = parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+        let first_coefficients = recover(first.clone());
+        let second_coefficients: Vec<BigUint> = (0..BLOB_LEN as u32).map(BigUint::from).collect();
+        let second = encode(second_coefficients.clone()).unwrap();
+        let data = [first_coefficients.clone(), second_coefficients.clone()].concat();
+        let commitments = [
+            [0xa5u8; BYTES_PER_COMMITMENT],
+            [0x5au8; BYTES_PER_COMMITMENT],
+        ];
+
+        // The point covers both blobs and both commitments, not a blob alone
+        let x = compute_evaluation_point(&data, &commitments).unwrap();
+        assert_ne!(
+            Ok(x.clone()),
+            compute_evaluation_point(&first_coefficients, &commitments[..1])
+        );
+        let claims: Vec<StarknetKzgClaim> = [first_coefficients, second_coefficients]
+            .into_iter()
+            .zip(commitments)
+            .map(|(coefficients, commitment)| StarknetKzgClaim {
+                y: Polynomial::from_coefficients(coefficients).evaluate(&x),
+                x: x.clone(),
+                commitment,
+            })
+            .collect();
+        let blobs = [first, second];
+        let reports = check_point_evaluation(&blobs, &claims, Some(&data)).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(PointEvaluationReport::is_valid));
+
+        assert_eq!(
+            check_point_evaluation(&blobs[..1], &claims, Some(&data)),
+            Err(KzgError::ClaimCount {
+                blobs: 1,
+                claims: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_compute_evaluation_point_rejects_non_felts() {
+        // A valid BLS field element, above the Stark prime
+        let value = BigUint::one() << 252u32;
+        assert_eq!(
+            compute_evaluation_point(std::slice::from_ref(&value), &[]),
+            Err(HashError::InvalidFelt(value))
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use num_bigint::BigUint;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+use crate::blob::recover_blobs;
+use crate::kzg::{
+    claims_from_segment, compute_evaluation_point, evaluate_blob, KzgCommitment, KzgError,
+    StarknetKzgClaim,
+};
+use majin_blob_eip_4844::blob::kzg_to_versioned_hash;
+use majin_blob_eip_4844::BYTES_PER_PROOF;
+use majin_blob_types::abi::{
+    decode_core_contract_events, AbiError, CoreContractEvent, Log, UpdateStateKzgDa,
+};
+use majin_blob_types::os_output::{OsOutput, OsOutputError, OsOutputVersion, StateTransition};
+use majin_blob_types::serde::{parse_state_diffs, state_diffs_len};
+use majin_blob_types::sidecar::{self, BlobInput, SidecarError};
+use majin_blob_types::state_diffs::serialize_biguint;
+
+#[derive(Debug, Error)]
+pub enum LinkError {
+    #[error(transparent)]
+    Abi(#[from] AbiError),
+    #[error(transparent)]
+    Sidecar(#[from] SidecarError),
+    #[error("the program output does not publish its data in blobs")]
+    NoKzgDa,
+    #[error(transparent)]
+    OsOutput(#[from] OsOutputError),
+    #[error("the program output claims {claimed} blobs, got {actual}")]
+    BlobCount { claimed: usize, actual: usize },
+    #[error("the program output claims {claimed} blobs, got {actual} KZG proofs")]
+    ProofCount { claimed: usize, actual: usize },
+    #[error("KZG proof {0} is not {BYTES_PER_PROOF} bytes long")]
+    InvalidProofLength(usize),
+    #[error(transparent)]
+    Claim(#[from] KzgError),
+    #[cfg(feature = "kzg")]
+    #[error(transparent)]
+    Kzg(#[from] majin_blob_eip_4844::kzg::KzgError),
+}
+
+/// How a blob matches the claim the OS output makes about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkedBlob {
+    pub index: usize,
+    #[serde(serialize_with = "serialize_hex")]
+    pub commitment: KzgCommitment,
+    #[serde(serialize_with = "serialize_hex")]
+    pub versioned_hash: [u8; 32],
+    #[serde(serialize_with = "serialize_biguint")]
+    pub claimed_y: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub computed_y: BigUint,
+    pub y_matches: bool,
+    /// Whether the blob has the claimed commitment, when KZG support is enabled.
+    pub commitment_matches: Option<bool>,
+    /// Whether the KZG proof of the call proves `y`, when KZG support is enabled.
+    pub proof_valid: Option<bool>,
+}
+
+/// A state update published by an `updateStateKzgDA` call, cross-checked against the
+/// blobs of its transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct L1StateUpdate {
+    /// The evaluation point claimed by the OS output.
+    #[serde(serialize_with = "serialize_biguint")]
+    pub x: BigUint,
+    /// The evaluation point derived from the blobs and the claimed commitments.
+    #[serde(serialize_with = "serialize_biguint")]
+    pub derived_x: BigUint,
+    pub x_matches: bool,
+    pub blobs: Vec<LinkedBlob>,
+    pub events: Vec<CoreContractEvent>,
+    pub os_output: OsOutput,
+    /// The state diffs, with the blocks and state roots of the OS output.
+    pub transition: StateTransition,
+}
+
+impl L1StateUpdate {
+    /// Returns `true` if every check that could be run holds.
+    pub fn is_valid(&self) -> bool {
+        self.x_matches
+            && self.blobs.iter().all(|blob| {
+                blob.y_matches
+                    && blob.commitment_matches != Some(false)
+                    && blob.proof_valid != Some(false)
+            })
+    }
+}
+
+/// Links an `updateStateKzgDA` call and the events of its transaction to the blobs the
+/// transaction carried, and recovers the state diffs.
+///
+/// Every blob is checked against the claim of the OS output: its evaluation at `x` must
+/// be the claimed `y`, and with KZG support, its commitment must be the claimed one and
+/// the KZG proof of the call must prove `y`. The claimed `x` must be the one derived from
+/// the data of the blobs.
+///
+/// # Arguments
+///
+/// * `call` - The decoded calldata.
+/// * `version` - The layout of the OS output of the call.
+/// * `events` - The events of the core contract emitted by the transaction.
+/// * `blobs` - The blobs of the transaction, in order.
+pub fn link_state_update(
+    call: &UpdateStateKzgDa,
+    version: OsOutputVersion,
+    events: Vec<CoreContractEvent>,
+    blobs: Vec<Vec<BigUint>>,
+) -> Result<L1StateUpdate, LinkError> {
+    let os_output = OsOutput::parse(&call.program_output, version)?;
+    let claims = claims_from_segment(os_output.kzg.as_ref().ok_or(LinkError::NoKzgDa)?)?;
+    if claims.len() != blobs.len() {
+        return Err(LinkError::BlobCount {
+            claimed: claims.len(),
+            actual: blobs.len(),
+        });
+    }
+    if claims.len() != call.kzg_proofs.len() {
+        return Err(LinkError::ProofCount {
+            claimed: claims.len(),
+            actual: call.kzg_proofs.len(),
+        });
+    }
+
+    let mut linked = Vec::with_capacity(blobs.len());
+    for (index, ((blob, claim), proof)) in
+        blobs.iter().zip(&claims).zip(&call.kzg_proofs).enumerate()
+    {
+        let proof: [u8; BYTES_PER_PROOF] = proof
+            .as_slice()
+            .try_into()
+            .map_err(|_| LinkError::InvalidProofLength(index))?;
+        let computed_y = evaluate_blob(blob, &claim.x)?;
+        let (commitment_matches, proof_valid) = check_kzg(blob, claim, &proof)?;
+        linked.push(LinkedBlob {
+            index,
+            commitment: claim.commitment,
+            versioned_hash: kzg_to_versioned_hash(&claim.commitment),
+            claimed_y: claim.y.clone(),
+            y_matches: computed_y == claim.y,
+            computed_y,
+            commitment_matches,
+            proof_valid,
+        });
+    }
+
+    let original_data = recover_blobs(blobs);
+    let data = &original_data[..state_diffs_len(&original_data)];
+    let commitments: Vec<KzgCommitment> = claims.iter().map(|claim| claim.commitment).collect();
+    let x = claims
+        .first()
+        .map(|claim| claim.x.clone())
+        .unwrap_or_default();
+    let derived_x = compute_evaluation_point(data, &commitments).map_err(KzgError::from)?;
+    Ok(L1StateUpdate {
+        x_matches: derived_x == x,
+        x,
+        derived_x,
+        blobs: linked,
+        events,
+        transition: os_output.join(parse_state_diffs(data)),
+        os_output,
+    })
+}
+
+/// Decodes an `updateStateKzgDA` transaction and links it to its blobs, see
+/// [`link_state_update`].
+///
+/// Sidecars are authenticated, and only those carrying a claimed commitment are kept, as
+/// a block may carry the blobs of other transactions.
+///
+/// # Arguments
+///
+/// * `input` - The calldata of the transaction.
+/// * `logs` - The logs of the transaction, the events of `contract` are decoded.
+/// * `blob_inputs` - The blobs of the transaction, bare or as sidecars.
+/// * `contract` - The address of the core contract.
+/// * `version` - The layout of the OS output of the call.
+pub fn decode_state_update(
+    input: &[u8],
+    logs: &[Log],
+    blob_inputs: Vec<BlobInput>,
+    contract: &[u8; 20],
+    version: OsOutputVersion,
+) -> Result<L1StateUpdate, LinkError> {
+    let call = UpdateStateKzgDa::decode(input)?;
+    let events = decode_core_contract_events(logs, contract)?;
+    let os_output = OsOutput::parse(&call.program_output, version)?;
+    let claims = claims_from_segment(os_output.kzg.as_ref().ok_or(LinkError::NoKzgDa)?)?;
+    let mut blobs = Vec::new();
+    for blob_input in blob_inputs {
+        match blob_input {
+            BlobInput::Blob(blob) => blobs.push(blob),
+            BlobInput::Sidecars(sidecars) => {
+                // The claims, not a block root, authenticate the blobs: only the
+                // sidecars of the claimed commitments are kept, and checked against them
+                sidecar::verify_sidecars(&sidecars, None)?;
+                let claimed = sidecars.into_iter().filter(|sidecar| {
+                    claims
+                        .iter()
+                        .any(|claim| claim.commitment == sidecar.kzg_commitment)
+                });
+                blobs.extend(sidecar::sidecars_to_blobs(claimed.collect()));
+            }
+        }
+    }
+    link_state_update(&call, version, events, blobs)
+}
+
+/// Checks the commitment and the KZG proof of a claim.
+///
+/// # Returns
+///
+/// Whether the blob has the claimed commitment, and whether the proof holds.
+#[cfg(feature = "kzg")]
+fn check_kzg(
+    blob: &[BigUint],
+    claim: &StarknetKzgClaim,
+    proof: &[u8; BYTES_PER_PROOF],
+) -> Result<(Option<bool>, Option<bool>), LinkError> {
+    use majin_blob_eip_4844::blob::field_element_to_bytes;
+    use majin_blob_eip_4844::kzg::{blob_to_commitment, verify_proof};
+
+    let commitment = blob_to_commitment(blob)?;
+    // A claimed commitment or proof that is not a valid point, or an evaluation out of the
+    // field, fails the check
+    let proof_valid = match (
+        field_element_to_bytes(&claim.x),
+        field_element_to_bytes(&claim.y),
+    ) {
+        (Ok(x), Ok(y)) => verify_proof(&claim.commitment, &x, &y, proof).unwrap_or(false),
+        _ => false,
+    };
+    Ok((Some(commitment == claim.commitment), Some(proof_valid)))
+}
+
+#[cfg(not(feature = "kzg"))]
+fn check_kzg(
+    _blob: &[BigUint],
+    _claim: &StarknetKzgClaim,
+    _proof: &[u8; BYTES_PER_PROOF],
+) -> Result<(Option<bool>, Option<bool>), LinkError> {
+    Ok((None, None))
+}
+
+fn serialize_hex<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::recover;
+    use crate::kzg::split_commitment;
+    use crate::polynomial::Polynomial;
+    use majin_blob_types::serde::parse_file_to_blob_data;
+    use num_traits::{One, Zero};
+
+    /// Size of the header of the OS output, from Starknet 0.13.2.
+    const HEADER_SIZE: usize = 10;
+
+    /// Builds the output of the OS and the calldata publishing `blob`, with a KZG segment
+    /// claiming `commitment`.
+    fn update_state_call(blob: &[BigUint], commitment: KzgCommitment) -> UpdateStateKzgDa {
+        let coefficients = recover(blob.to_vec());
+        let data = &coefficients[..state_diffs_len(&coefficients)];
+        let x = compute_evaluation_point(data, &[commitment]).unwrap();
+        let y = Polynomial::from_coefficients(coefficients.clone()).evaluate(&x);
+        let mask = (BigUint::one() << 128u32) - 1u32;
+        let (commitment_low, commitment_high) = split_commitment(&commitment);
+
+        // Blocks 100 to 102, with `use_kzg_da` set
+        let mut program_output = vec![BigUint::zero(); HEADER_SIZE];
+        program_output[2] = BigUint::from(99u32);
+        program_output[3] = BigUint::from(102u32);
+        program_output[8] = BigUint::one();
+        program_output.extend([
+            x,
+            BigUint::one(),
+            commitment_low,
+            commitment_high,
+            &y & mask,
+            y >> 128u32,
+        ]);
+        // No messages
+        program_output.extend([BigUint::zero(), BigUint::zero()]);
+        UpdateStateKzgDa {
+            program_output,
+            kzg_proofs: vec![vec![0xc0; BYTES_PER_PROOF]],
+        }
+    }
+
+    #[test]
+    fn test_link_state_update() {
+        let blob = parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+        let call = update_state_call(&blob, [0xa5; 48]);
+        let linked = link_state_update(
+            &call,
+            OsOutputVersion::V0_13_2,
+            Vec::new(),
+            vec![blob.clone()],
+        )
+        .unwrap();
+        assert!(linked.x_matches);
+        assert!(linked.blobs[0].y_matches);
+        assert_eq!(linked.transition.first_block_number, 100);
+        assert_eq!(linked.transition.last_block_number, 102);
+        assert_eq!(
+            linked.transition.state_diffs,
+            parse_state_diffs(&recover(blob.clone()))
+        );
+
+        // The header of 0.13.1 is shorter, the KZG segment is not where it expects
+        assert!(link_state_update(
+            &call,
+            OsOutputVersion::V0_13_1,
+            Vec::new(),
+            vec![blob.clone()]
+        )
+        .is_err());
+        assert!(matches!(
+            link_state_update(
+                &call,
+                OsOutputVersion::V0_13_2,
+                Vec::new(),
+                vec![blob.clone(), blob]
+            ),
+            Err(LinkError::BlobCount {
+                claimed: 1,
+                actual: 2
+            })
+        ));
+    }
+
+    #[cfg(feature = "kzg")]
+    #[test]
+    fn test_link_state_update_kzg() {
+        use majin_blob_eip_4844::kzg::{blob_to_commitment, compute_proof};
+
+        let blob = parse_file_to_blob_data("../../examples/blob/mainnet.txt");
+        let commitment = blob_to_commitment(&blob).unwrap();
+        let mut call = update_state_call(&blob, commitment);
+        let (proof, y) = compute_proof(&blob, &call.program_output[HEADER_SIZE]).unwrap();
+        call.kzg_proofs = vec![proof.to_vec()];
+
+        let linked = link_state_update(
+            &call,
+            OsOutputVersion::V0_13_2,
+            Vec::new(),
+            vec![blob.clone()],
+        )
+        .unwrap();
+        assert_eq!(linked.blobs[0].computed_y, y);
+        assert_eq!(linked.blobs[0].commitment_matches, Some(true));
+        assert_eq!(linked.blobs[0].proof_valid, Some(true));
+        assert!(linked.is_valid());
+
+        call.kzg_proofs = vec![blob_to_commitment(&blob).unwrap().to_vec()];
+        let linked =
+            link_state_update(&call, OsOutputVersion::V0_13_2, Vec::new(), vec![blob]).unwrap();
+        assert_eq!(linked.blobs[0].proof_valid, Some(false));
+        assert!(!linked.is_valid());
+    }
+}
@@ -1,2 +1,14 @@
 pub mod blob;
-pub mod math;
+pub mod calldata;
+pub mod commitment;
+pub mod hash;
+pub mod interpret;
+pub mod kzg;
+pub mod l1;
+pub mod polynomial;
+pub mod preimage;
+pub mod registry;
+pub mod storage_labels;
+pub mod tokens;
+
+pub use majin_blob_eip_4844::math;
@@ -0,0 +1,357 @@
+use num_bigint::BigUint;
+use num_traits::Zero;
+use thiserror::Error;
+
+use crate::math::{
+    batch_inverse, bit_reversal_permutation, fft, inv_mod, inverse_fft, powers, root_of_unity,
+};
+use majin_blob_eip_4844::{BLOB_LEN, BLS_MODULUS, PRIMITIVE_ROOT_OF_UNITY};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PolynomialError {
+    #[error("expected a blob of {BLOB_LEN} field elements, got {0}")]
+    InvalidBlobLength(usize),
+    #[error("expected a power of two number of evaluations, got {0}")]
+    InvalidDomainSize(usize),
+}
+
+/// A polynomial over the BLS12-381 scalar field.
+///
+/// The coefficient form stores `[c_0, c_1, ...]` such that `f(x) = sum(c_i * x^i)`.
+/// The evaluation form stores `[f(w^0), f(w^1), ...]` in natural order, where `w` is the
+/// primitive root of unity of the same order as the number of evaluations.
+/// Blobs store their evaluations in bit-reversed order, see [`Polynomial::from_blob`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial(Form);
+
+/// The representation of a polynomial. The evaluation form always holds a power of two
+/// number of evaluations, so that it spans a domain of roots of unity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Form {
+    Coefficients(Vec<BigUint>),
+    Evaluations(Vec<BigUint>),
+}
+
+impl Polynomial {
+    /// Builds a polynomial from its coefficients `[c_0, c_1, ...]`.
+    pub fn from_coefficients(coefficients: Vec<BigUint>) -> Self {
+        Polynomial(Form::Coefficients(coefficients))
+    }
+
+    /// Builds a polynomial from its evaluations over a domain of roots of unity.
+    ///
+    /// # Arguments
+    ///
+    /// * `evaluations` - The evaluations `[f(w^0), f(w^1), ...]`, in natural order.
+    ///
+    /// # Returns
+    ///
+    /// The polynomial in evaluation form, or an error if the number of evaluations is not
+    /// the size of a domain of roots of unity.
+    pub fn from_evaluations(evaluations: Vec<BigUint>) -> Result<Self, PolynomialError> {
+        if !is_domain_size(evaluations.len()) {
+            return Err(PolynomialError::InvalidDomainSize(evaluations.len()));
+        }
+        Ok(Polynomial(Form::Evaluations(evaluations)))
+    }
+
+    /// Builds the polynomial represented by a blob.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The blob field elements, in the bit-reversed order defined by EIP-4844.
+    ///
+    /// # Returns
+    ///
+    /// The polynomial in evaluation form, or an error if `data` is not `BLOB_LEN` long.
+    pub fn from_blob(data: &[BigUint]) -> Result<Self, PolynomialError> {
+        if data.len() != BLOB_LEN {
+            return Err(PolynomialError::InvalidBlobLength(data.len()));
+        }
+        Ok(Polynomial(Form::Evaluations(bit_reversal_permutation(
+            data,
+        ))))
+    }
+
+    /// Returns the blob field elements of the polynomial, in bit-reversed order.
+    ///
+    /// The polynomial must have a degree lower than `BLOB_LEN`.
+    pub fn to_blob(&self) -> Vec<BigUint> {
+        bit_reversal_permutation(&self.evaluations(BLOB_LEN))
+    }
+
+    /// Returns the coefficients of the polynomial.
+    pub fn coefficients(&self) -> Vec<BigUint> {
+        match &self.0 {
+            Form::Coefficients(coefficients) => coefficients.clone(),
+            Form::Evaluations(evaluations) => {
+                let root = domain_root(evaluations.len());
+                inverse_fft(evaluations, &root, &BLS_MODULUS)
+            }
+        }
+    }
+
+    /// Returns the evaluations of the polynomial over the domain of `size` roots of unity.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The size of the domain. Must be a power of two strictly greater than the
+    ///   degree of the polynomial.
+    ///
+    /// # Panics
+    ///
+    /// If `size` is not a power of two or is not greater than the degree.
+    pub fn evaluations(&self, size: usize) -> Vec<BigUint> {
+        assert!(is_domain_size(size), "invalid domain size {}", size);
+        if let Form::Evaluations(evaluations) = &self.0 {
+            if evaluations.len() == size {
+                return evaluations.clone();
+            }
+        }
+        let mut coefficients = trim(self.coefficients());
+        assert!(
+            coefficients.len() <= size,
+            "polynomial degree does not fit in a domain of size {}",
+            size
+        );
+        coefficients.resize(size, BigUint::zero());
+        fft(&coefficients, &domain_root(size), &BLS_MODULUS)
+    }
+
+    /// Converts the polynomial to coefficient form.
+    pub fn to_coefficient_form(&self) -> Self {
+        Polynomial::from_coefficients(self.coefficients())
+    }
+
+    /// Converts the polynomial to evaluation form over a domain of `size` roots of unity.
+    pub fn to_evaluation_form(&self, size: usize) -> Self {
+        Polynomial(Form::Evaluations(self.evaluations(size)))
+    }
+
+    /// Returns the degree of the polynomial, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        trim(self.coefficients()).len().checked_sub(1)
+    }
+
+    /// Evaluates the polynomial at an arbitrary field element.
+    ///
+    /// The coefficient form is evaluated with Horner's rule. The evaluation form is
+    /// evaluated with the barycentric formula, without interpolating the coefficients:
+    ///
+    /// `f(z) = (z^n - 1) / n * sum(f(w^i) * w^i / (z - w^i))`
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The evaluation point.
+    ///
+    /// # Returns
+    ///
+    /// `f(z)` reduced modulo the BLS modulus.
+    pub fn evaluate(&self, z: &BigUint) -> BigUint {
+        let p = &*BLS_MODULUS;
+        let z = z % p;
+        match &self.0 {
+            Form::Coefficients(coefficients) => coefficients
+                .iter()
+                .rev()
+                .fold(BigUint::zero(), |acc, c| (acc * &z + c) % p),
+            Form::Evaluations(evaluations) => {
+                let n = evaluations.len();
+                let domain = powers(&domain_root(n), n, p);
+                if let Some(i) = domain.iter().position(|x| *x == z) {
+                    return evaluations[i].clone();
+                }
+
+                let denominators: Vec<BigUint> = domain.iter().map(|x| (&z + p - x) % p).collect();
+                let inverses = batch_inverse(&denominators, p);
+                let sum = evaluations
+                    .iter()
+                    .zip(domain.iter())
+                    .zip(inverses.iter())
+                    .fold(BigUint::zero(), |acc, ((value, x), inverse)| {
+                        (acc + value * x % p * inverse) % p
+                    });
+
+                let numerator = (z.modpow(&BigUint::from(n), p) + p - 1u32) % p;
+                numerator * inv_mod(&BigUint::from(n), p) % p * sum % p
+            }
+        }
+    }
+
+    /// Adds two polynomials.
+    pub fn add(&self, other: &Polynomial) -> Polynomial {
+        let p = &*BLS_MODULUS;
+        if let (Form::Evaluations(a), Form::Evaluations(b)) = (&self.0, &other.0) {
+            if a.len() == b.len() {
+                return Polynomial(Form::Evaluations(
+                    a.iter().zip(b.iter()).map(|(x, y)| (x + y) % p).collect(),
+                ));
+            }
+        }
+
+        let mut a = self.coefficients();
+        let mut b = other.coefficients();
+        let len = a.len().max(b.len());
+        a.resize(len, BigUint::zero());
+        b.resize(len, BigUint::zero());
+        Polynomial::from_coefficients(a.iter().zip(b.iter()).map(|(x, y)| (x + y) % p).collect())
+    }
+
+    /// Multiplies two polynomials.
+    ///
+    /// The product is computed pointwise over a domain large enough to hold its degree,
+    /// and returned in coefficient form.
+    pub fn mul(&self, other: &Polynomial) -> Polynomial {
+        let p = &*BLS_MODULUS;
+        let (a, b) = match (self.degree(), other.degree()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Polynomial::from_coefficients(vec![]),
+        };
+        let size = (a + b + 1).next_power_of_two();
+        let product: Vec<BigUint> = self
+            .evaluations(size)
+            .iter()
+            .zip(other.evaluations(size).iter())
+            .map(|(x, y)| x * y % p)
+            .collect();
+        let mut coefficients = inverse_fft(&product, &domain_root(size), p);
+        coefficients.truncate(a + b + 1);
+        Polynomial::from_coefficients(coefficients)
+    }
+
+    /// Divides the polynomial by `(x - a)` using synthetic division.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The root of the linear divisor.
+    ///
+    /// # Returns
+    ///
+    /// The quotient in coefficient form and the remainder, which equals `f(a)`.
+    pub fn divide_by_linear(&self, a: &BigUint) -> (Polynomial, BigUint) {
+        let p = &*BLS_MODULUS;
+        let a = a % p;
+        let coefficients = trim(self.coefficients());
+        if coefficients.is_empty() {
+            return (Polynomial::from_coefficients(vec![]), BigUint::zero());
+        }
+
+        let mut quotient = vec![BigUint::zero(); coefficients.len() - 1];
+        let mut carry = BigUint::zero();
+        for i in (0..coefficients.len()).rev() {
+            carry = (&coefficients[i] + carry * &a) % p;
+            if i > 0 {
+                quotient[i - 1] = carry.clone();
+            }
+        }
+        (Polynomial::from_coefficients(quotient), carry)
+    }
+}
+
+/// Returns the generator of the evaluation domain of the given size.
+fn domain_root(size: usize) -> BigUint {
+    root_of_unity(size, &PRIMITIVE_ROOT_OF_UNITY, &BLS_MODULUS)
+}
+
+/// Returns whether `size` is the size of a domain of roots of unity of the BLS12-381
+/// scalar field, that is a power of two dividing `BLS_MODULUS - 1`.
+fn is_domain_size(size: usize) -> bool {
+    size.is_power_of_two() && size.trailing_zeros() <= 32
+}
+
+/// Removes the trailing zero coefficients.
+fn trim(mut coefficients: Vec<BigUint>) -> Vec<BigUint> {
+    while coefficients.last().is_some_and(|c| c.is_zero()) {
+        coefficients.pop();
+    }
+    coefficients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::recover;
+    use rstest::rstest;
+
+    fn poly(coefficients: &[u32]) -> Polynomial {
+        Polynomial::from_coefficients(coefficients.iter().map(|c| BigUint::from(*c)).collect())
+    }
+
+    #[rstest]
+    #[case(&[1, 2, 3], 0, 1)]
+    #[case(&[1, 2, 3], 2, 17)]
+    #[case(&[5], 1000, 5)]
+    #[case(&[], 7, 0)]
+    fn test_evaluate_forms_agree(
+        #[case] coefficients: &[u32],
+        #[case] z: u32,
+        #[case] expected: u32,
+    ) {
+        let polynomial = poly(coefficients);
+        let z = BigUint::from(z);
+        assert_eq!(polynomial.evaluate(&z), BigUint::from(expected));
+        assert_eq!(
+            polynomial.to_evaluation_form(8).evaluate(&z),
+            BigUint::from(expected)
+        );
+    }
+
+    #[test]
+    fn test_add_and_mul() {
+        // (1 + x) * (2 + x) = 2 + 3x + x^2
+        assert_eq!(poly(&[1, 1]).mul(&poly(&[2, 1])), poly(&[2, 3, 1]));
+        assert_eq!(poly(&[1, 1]).add(&poly(&[2, 0, 4])), poly(&[3, 1, 4]));
+        let sum = poly(&[1, 1])
+            .to_evaluation_form(4)
+            .add(&poly(&[2, 1]).to_evaluation_form(4));
+        assert_eq!(sum.coefficients(), poly(&[3, 2, 0, 0]).coefficients());
+    }
+
+    #[test]
+    fn test_divide_by_linear() {
+        // x^2 + 3x + 2 = (x + 2)(x + 1), so dividing by (x - 3) leaves f(3) = 20.
+        let (quotient, remainder) = poly(&[2, 3, 1]).divide_by_linear(&BigUint::from(3u32));
+        assert_eq!(quotient, poly(&[6, 1]));
+        assert_eq!(remainder, BigUint::from(20u32));
+    }
+
+    #[test]
+    fn test_from_evaluations_checks_domain_size() {
+        for len in [0, 3, 6] {
+            assert_eq!(
+                Polynomial::from_evaluations(vec![BigUint::zero(); len]),
+                Err(PolynomialError::InvalidDomainSize(len))
+            );
+        }
+        let polynomial = Polynomial::from_evaluations(poly(&[1, 2]).evaluations(4)).unwrap();
+        assert_eq!(
+            polynomial.evaluate(&BigUint::from(3u32)),
+            BigUint::from(7u32)
+        );
+        assert_eq!(
+            trim(polynomial.coefficients()),
+            poly(&[1, 2]).coefficients()
+        );
+    }
+
+    #[test]
+    fn test_blob_evaluation_matches_recovered_coefficients() {
+        let blob = majin_blob_types::serde::parse_file_to_blob_data(
+            "../../examples/blob/sn_blob_goerli.txt",
+        );
+        let polynomial = Polynomial::from_blob(&blob).unwrap();
+        let coefficients = recover(blob.clone());
+        assert_eq!(polynomial.coefficients(), coefficients);
+        assert_eq!(polynomial.to_blob(), blob);
+
+        let z = BigUint::from(0x1234_5678u32);
+        assert_eq!(
+            polynomial.evaluate(&z),
+            Polynomial::from_coefficients(coefficients).evaluate(&z)
+        );
+        assert_eq!(
+            Polynomial::from_blob(&blob[1..]),
+            Err(PolynomialError::InvalidBlobLength(BLOB_LEN - 1))
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Recovery of the map keys of storage writes from the addresses seen in the same diff.
+//!
+//! The storage key of a map entry is `pedersen(sn_keccak(name), key)`, chained with
+//! Pedersen for every further key. Maps are mostly keyed by addresses, such as balances
+//! by owner or allowances by owner and spender, and those addresses usually appear in the
+//! same state diff, as the contracts it touches or as the values it writes.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use num_bigint::BigUint;
+use serde::Serialize;
+
+use crate::hash::{pedersen_hash, sn_keccak};
+use crate::storage_labels::{ADDR_BOUND, MAX_OFFSET};
+use majin_blob_types::state_diffs::{serialize_biguint, DataJson};
+
+/// Storage variables tried when none are given, the balances and allowances of ERC20
+/// tokens.
+pub const DEFAULT_VARIABLES: [&str; 4] = [
+    "ERC20_balances",
+    "ERC20_allowances",
+    "balances",
+    "allowances",
+];
+
+/// Felts of at most this many bits are amounts, flags or counters rather than addresses.
+const MIN_ADDRESS_BITS: u64 = 128;
+
+/// Where a candidate key was seen in the state diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// The address of a contract of the diff.
+    Contract,
+    /// A value written to storage.
+    StorageValue,
+}
+
+/// A key of a map entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MapKey {
+    #[serde(serialize_with = "serialize_biguint")]
+    pub value: BigUint,
+    pub source: KeySource,
+}
+
+/// A storage key inverted into a map entry of a storage variable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedKey {
+    /// The contract written to.
+    #[serde(serialize_with = "serialize_biguint")]
+    pub contract: BigUint,
+    /// The storage key written to.
+    #[serde(serialize_with = "serialize_biguint")]
+    pub key: BigUint,
+    /// The name of the storage variable.
+    pub variable: String,
+    /// The keys of the entry, one per level of the map.
+    pub keys: Vec<MapKey>,
+    /// The slot of the entry written to, 1 for the high half of a `u256`.
+    pub offset: u32,
+}
+
+impl ResolvedKey {
+    /// Returns the label of the storage key, such as `ERC20_balances[0x123]+1`.
+    pub fn label(&self) -> String {
+        let keys: String = self
+            .keys
+            .iter()
+            .map(|key| format!("[0x{}]", key.value.to_str_radix(16)))
+            .collect();
+        match self.offset {
+            0 => format!("{}{}", self.variable, keys),
+            offset => format!("{}{}+{}", self.variable, keys, offset),
+        }
+    }
+}
+
+/// Inverts the storage keys of map entries keyed by addresses seen in a state diff.
+#[derive(Debug, Clone)]
+pub struct PreimageResolver {
+    variables: Vec<String>,
+    max_depth: usize,
+}
+
+impl Default for PreimageResolver {
+    fn default() -> Self {
+        PreimageResolver::new(DEFAULT_VARIABLES.map(str::to_string), 1)
+    }
+}
+
+impl PreimageResolver {
+    /// Creates a resolver.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - The names of the storage variables to try.
+    /// * `max_depth` - The number of map levels to try, 2 for maps such as allowances.
+    ///   Resolving computes `variables * candidates^max_depth` Pedersen hashes, so a diff
+    ///   with a thousand candidate keys takes a million hashes per variable at depth 2.
+    pub fn new(variables: impl IntoIterator<Item = String>, max_depth: usize) -> Self {
+        PreimageResolver {
+            variables: variables.into_iter().collect(),
+            max_depth,
+        }
+    }
+
+    /// Gathers the address-like felts of a state diff: the addresses of its contracts,
+    /// and the values it writes of more than 128 bits, below the bound of addresses.
+    pub fn candidate_keys(data: &DataJson) -> BTreeMap<BigUint, KeySource> {
+        let mut candidates = BTreeMap::new();
+        for contract in &data.state_update {
+            candidates.insert(contract.address.clone(), KeySource::Contract);
+        }
+        for update in data
+            .state_update
+            .iter()
+            .flat_map(|contract| &contract.storage_updates)
+        {
+            if update.value.bits() > MIN_ADDRESS_BITS && update.value < *ADDR_BOUND {
+                candidates
+                    .entry(update.value.clone())
+                    .or_insert(KeySource::StorageValue);
+            }
+        }
+        candidates
+    }
+
+    /// Resolves the storage keys of a state diff.
+    ///
+    /// # Returns
+    ///
+    /// The storage writes whose key could be inverted, in the order of the diff.
+    pub fn resolve(&self, data: &DataJson) -> Vec<ResolvedKey> {
+        let written: HashSet<&BigUint> = data
+            .state_update
+            .iter()
+            .flat_map(|contract| &contract.storage_updates)
+            .map(|update| &update.key)
+            .collect();
+        let candidates: Vec<MapKey> = Self::candidate_keys(data)
+            .into_iter()
+            .map(|(value, source)| MapKey { value, source })
+            .collect();
+
+        // The variable, keys and offset of every written key found
+        let mut found: HashMap<BigUint, (&str, Vec<MapKey>, u32)> = HashMap::new();
+        for variable in &self.variables {
+            // Unreduced hashes of the entries of the previous level, with their keys
+            let mut level = vec![(sn_keccak(variable), Vec::new())];
+            for depth in 1..=self.max_depth {
+                let mut next = Vec::with_capacity(level.len() * candidates.len());
+                for (hash, keys) in &level {
+                    for candidate in &candidates {
+                        // Values out of the field cannot be map keys
+                        let Ok(hash) = pedersen_hash(hash, &candidate.value) else {
+                            continue;
+                        };
+                        let mut keys: Vec<MapKey> = keys.clone();
+                        keys.push(candidate.clone());
+                        let address = &hash % &*ADDR_BOUND;
+                        for offset in 0..=MAX_OFFSET {
+                            let slot = &address + offset;
+                            if written.contains(&slot) {
+                                found.insert(slot, (variable.as_str(), keys.clone(), offset));
+                            }
+                        }
+                        if depth < self.max_depth {
+                            next.push((hash, keys));
+                        }
+                    }
+                }
+                level = next;
+            }
+        }
+
+        data.state_update
+            .iter()
+            .flat_map(|contract| {
+                contract.storage_updates.iter().filter_map(|update| {
+                    let (variable, keys, offset) = found.get(&update.key)?;
+                    Some(ResolvedKey {
+                        contract: contract.address.clone(),
+                        key: update.key.clone(),
+                        variable: variable.to_string(),
+                        keys: keys.clone(),
+                        offset: *offset,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage_labels::storage_address;
+    use majin_blob_types::state_diffs::{ContractUpdate, StorageUpdate};
+
+    fn contract(address: &BigUint, storage: Vec<(BigUint, BigUint)>) -> ContractUpdate {
+        ContractUpdate {
+            address: address.clone(),
+            nonce: 1,
+            number_of_storage_updates: storage.len() as u64,
+            new_class_hash: None,
+            storage_updates: storage
+                .into_iter()
+                .map(|(key, value)| StorageUpdate { key, value })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let token = BigUint::from(0x70u32) << 240u32;
+        let owner = (BigUint::from(0x0au32) << 240u32) + 1u32;
+        let spender = (BigUint::from(0x0bu32) << 240u32) + 2u32;
+        let balance = storage_address("ERC20_balances", std::slice::from_ref(&owner)).unwrap();
+        let allowance =
+            storage_address("ERC20_allowances", &[owner.clone(), spender.clone()]).unwrap();
+        let data = DataJson {
+            state_update_size: 2,
+            state_update: vec![
+                // The owner is a contract of the diff
+                contract(&owner, Vec::new()),
+                contract(
+                    &token,
+                    vec![
+                        (balance.clone(), BigUint::from(5u32)),
+                        (&balance + 1u32, BigUint::from(0u32)),
+                        // The spender is only seen as a value
+                        (BigUint::from(9u32), spender.clone()),
+                        (allowance.clone(), BigUint::from(7u32)),
+                    ],
+                ),
+            ],
+            class_declaration_size: 0,
+            class_declaration: Vec::new(),
+        };
+
+        let candidates = PreimageResolver::candidate_keys(&data);
+        assert_eq!(candidates[&spender], KeySource::StorageValue);
+        assert_eq!(candidates[&owner], KeySource::Contract);
+        // Small values are not addresses
+        assert_eq!(candidates.len(), 3);
+
+        let resolved =
+            PreimageResolver::new(DEFAULT_VARIABLES.map(str::to_string), 2).resolve(&data);
+        let labels: Vec<String> = resolved.iter().map(ResolvedKey::label).collect();
+        let owner_hex = owner.to_str_radix(16);
+        assert_eq!(
+            labels,
+            [
+                format!("ERC20_balances[0x{}]", owner_hex),
+                format!("ERC20_balances[0x{}]+1", owner_hex),
+                format!(
+                    "ERC20_allowances[0x{}][0x{}]",
+                    owner_hex,
+                    spender.to_str_radix(16)
+                ),
+            ]
+        );
+        assert_eq!(resolved[2].keys[1].source, KeySource::StorageValue);
+        assert!(resolved.iter().all(|resolved| resolved.contract == token));
+
+        // A single level does not reach the allowance
+        let resolved = PreimageResolver::default().resolve(&data);
+        assert_eq!(resolved.len(), 2);
+    }
+}
@@ -0,0 +1,427 @@
+//! Names of well-known contracts and classes.
+//!
+//! A registry maps contract addresses and class hashes to names, so that outputs can show
+//! `ETH token` next to `0x49d3...dc7`. It starts from built-in defaults for a network, on
+//! top of which label files are layered, later files overriding earlier ones.
+//!
+//! Label files are JSON, `{"addresses": {"0x..": "name"}, "classes": {"0x..": "name"}}`,
+//! or the equivalent TOML tables:
+//!
+//! ```toml
+//! [addresses]
+//! "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7" = "ETH token"
+//!
+//! [classes]
+//! 0x061dac032f228abef9c6626f995015233097ae253a7f72d68552db02f2971b8f = "OpenZeppelin account"
+//! ```
+//!
+//! Any other table or key, and names that are not strings, are rejected.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+use num_bigint::BigUint;
+use num_traits::Num;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::tokens::{ETH_ADDRESS, STRK_ADDRESS};
+use majin_blob_types::state_diffs::{self, DataJson};
+
+/// Entries shared by mainnet and Sepolia: the tokens have the same addresses on both, and
+/// class hashes do not depend on the network.
+const COMMON_ADDRESSES: [(&str, &str); 2] =
+    [(ETH_ADDRESS, "ETH token"), (STRK_ADDRESS, "STRK token")];
+
+const COMMON_CLASSES: [(&str, &str); 2] = [
+    (
+        "061dac032f228abef9c6626f995015233097ae253a7f72d68552db02f2971b8f",
+        "OpenZeppelin account v0.8.1",
+    ),
+    (
+        "01a736d6ed154502257f02b1ccdf4d9d1089f80811cd6acad48e6b6a9d1f2003",
+        "Argent account v0.3.0",
+    ),
+];
+
+const MAINNET_ADDRESSES: [(&str, &str); 1] = [(
+    "073314940630fd6dcda0d772d4c972c4e0a9946bef9dabf4ef84eda8ef542b82",
+    "StarkGate ETH bridge",
+)];
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("failed to read the label file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON label file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid TOML label file, line {line}: {message}")]
+    Toml { line: usize, message: String },
+    #[error("invalid label file: {0}")]
+    Format(String),
+    #[error("invalid felt {0}")]
+    InvalidFelt(String),
+    #[error("unknown network {0}, expected mainnet or sepolia")]
+    UnknownNetwork(String),
+}
+
+/// A Starknet network with built-in labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Sepolia,
+}
+
+impl FromStr for Network {
+    type Err = RegistryError;
+
+    fn from_str(network: &str) -> Result<Self, Self::Err> {
+        match network {
+            "mainnet" => Ok(Network::Mainnet),
+            "sepolia" => Ok(Network::Sepolia),
+            _ => Err(RegistryError::UnknownNetwork(network.to_string())),
+        }
+    }
+}
+
+/// The tables of a TOML label file, before their felts are parsed.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlLabels {
+    #[serde(default)]
+    addresses: BTreeMap<String, String>,
+    #[serde(default)]
+    classes: BTreeMap<String, String>,
+}
+
+/// Names of contract addresses and class hashes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelRegistry {
+    addresses: HashMap<BigUint, String>,
+    classes: HashMap<BigUint, String>,
+}
+
+impl LabelRegistry {
+    /// Returns the built-in labels of a network.
+    pub fn defaults(network: Network) -> Self {
+        let network_addresses: &[(&str, &str)] = match network {
+            Network::Mainnet => &MAINNET_ADDRESSES,
+            Network::Sepolia => &[],
+        };
+        let entries = |entries: &[(&str, &str)]| -> HashMap<BigUint, String> {
+            entries
+                .iter()
+                .map(|(felt, name)| (BigUint::from_str_radix(felt, 16).unwrap(), name.to_string()))
+                .collect()
+        };
+        let mut addresses = entries(&COMMON_ADDRESSES);
+        addresses.extend(entries(network_addresses));
+        LabelRegistry {
+            addresses,
+            classes: entries(&COMMON_CLASSES),
+        }
+    }
+
+    /// Reads a label file, as JSON if its extension is `.json` and as TOML otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        match path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            true => Self::parse_json(&contents),
+            false => Self::parse_toml(&contents),
+        }
+    }
+
+    /// Parses a JSON label file.
+    pub fn parse_json(json: &str) -> Result<Self, RegistryError> {
+        let json: Value = serde_json::from_str(json)?;
+        let table = |name: &str| -> Result<HashMap<BigUint, String>, RegistryError> {
+            let Some(table) = json.get(name) else {
+                return Ok(HashMap::new());
+            };
+            let table = table
+                .as_object()
+                .ok_or_else(|| RegistryError::Format(format!("{} must be an object", name)))?;
+            let entries = table
+                .iter()
+                .map(|(felt, name)| {
+                    let name = name.as_str().ok_or_else(|| {
+                        RegistryError::Format(format!("the name of {} must be a string", felt))
+                    })?;
+                    Ok((felt, name))
+                })
+                .collect::<Result<Vec<_>, RegistryError>>()?;
+            parse_table(entries)
+        };
+        Ok(LabelRegistry {
+            addresses: table("addresses")?,
+            classes: table("classes")?,
+        })
+    }
+
+    /// Parses a TOML label file, made of `[addresses]` and `[classes]` tables whose
+    /// entries map felts to names.
+    pub fn parse_toml(toml: &str) -> Result<Self, RegistryError> {
+        let labels: TomlLabels = toml::from_str(toml).map_err(|e| RegistryError::Toml {
+            line: e
+                .span()
+                .map_or(1, |span| toml[..span.start].matches('\n').count() + 1),
+            message: e.message().to_string(),
+        })?;
+        Ok(LabelRegistry {
+            addresses: parse_table(&labels.addresses)?,
+            classes: parse_table(&labels.classes)?,
+        })
+    }
+
+    /// Layers `other` on top of this registry, its names replacing existing ones.
+    pub fn extend(&mut self, other: LabelRegistry) {
+        self.addresses.extend(other.addresses);
+        self.classes.extend(other.classes);
+    }
+
+    /// Returns the name of a contract address.
+    pub fn address_name(&self, address: &BigUint) -> Option<&str> {
+        self.addresses.get(address).map(String::as_str)
+    }
+
+    /// Returns the name of a class hash.
+    pub fn class_name(&self, class_hash: &BigUint) -> Option<&str> {
+        self.classes.get(class_hash).map(String::as_str)
+    }
+
+    /// Returns the named contract addresses.
+    pub fn addresses(&self) -> impl Iterator<Item = &BigUint> {
+        self.addresses.keys()
+    }
+
+    /// Names the felts of a report, such as the tokens and accounts of balances, each as an
+    /// address or a class hash.
+    pub fn names_of<'a>(&self, felts: impl IntoIterator<Item = &'a BigUint>) -> Names {
+        let mut names = Names::default();
+        for felt in felts {
+            if let Some(name) = self.address_name(felt) {
+                names.addresses.insert(felt.clone(), name.to_string());
+            }
+            if let Some(name) = self.class_name(felt) {
+                names.classes.insert(felt.clone(), name.to_string());
+            }
+        }
+        names
+    }
+
+    /// Names the felts of state diffs: the addresses of contracts and the storage values
+    /// equal to a named address, and the class hashes of contracts and declared classes.
+    pub fn names(&self, data: &DataJson) -> Names {
+        let mut names = Names::default();
+        let mut name_address = |address: &BigUint| {
+            if let Some(name) = self.address_name(address) {
+                names.addresses.insert(address.clone(), name.to_string());
+            }
+        };
+        for contract in &data.state_update {
+            name_address(&contract.address);
+            for update in &contract.storage_updates {
+                name_address(&update.value);
+            }
+        }
+        let class_hashes = data
+            .state_update
+            .iter()
+            .filter_map(|contract| contract.new_class_hash.as_ref())
+            .chain(data.class_declaration.iter().map(|class| &class.class_hash));
+        for class_hash in class_hashes {
+            if let Some(name) = self.class_name(class_hash) {
+                names.classes.insert(class_hash.clone(), name.to_string());
+            }
+        }
+        names
+    }
+}
+
+/// The names of the felts of state diffs, see [`LabelRegistry::names`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Names {
+    pub addresses: BTreeMap<BigUint, String>,
+    pub classes: BTreeMap<BigUint, String>,
+}
+
+impl Serialize for Names {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let decimal = |names: &BTreeMap<BigUint, String>| -> BTreeMap<String, String> {
+            names
+                .iter()
+                .map(|(felt, name)| (felt.to_str_radix(10), name.clone()))
+                .collect()
+        };
+        serializer.collect_map([
+            ("addresses", decimal(&self.addresses)),
+            ("classes", decimal(&self.classes)),
+        ])
+    }
+}
+
+fn parse_felt(value: &str) -> Result<BigUint, RegistryError> {
+    state_diffs::parse_felt(value).map_err(|_| RegistryError::InvalidFelt(value.to_string()))
+}
+
+/// Parses the felts of a table mapping felts to names.
+fn parse_table<'a>(
+    entries: impl IntoIterator<Item = (&'a String, impl AsRef<str>)>,
+) -> Result<HashMap<BigUint, String>, RegistryError> {
+    entries
+        .into_iter()
+        .map(|(felt, name)| Ok((parse_felt(felt)?, name.as_ref().to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin_blob_types::state_diffs::{ClassDeclaration, ContractUpdate, StorageUpdate};
+
+    fn felt(hex: &str) -> BigUint {
+        BigUint::from_str_radix(hex, 16).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let toml = r#"
+            # Team labels
+            [addresses]
+            "0x10" = "Treasury"  # multisig
+            0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7 = "Ether"
+
+            [classes]
+            0xc1 = "Vault \"v2\""
+        "#;
+        let from_toml = LabelRegistry::parse_toml(toml).unwrap();
+        let from_json = LabelRegistry::parse_json(
+            r#"{
+                "addresses": {
+                    "0x10": "Treasury",
+                    "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7": "Ether"
+                },
+                "classes": {"193": "Vault \"v2\""}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(from_toml, from_json);
+        assert_eq!(
+            from_toml.class_name(&BigUint::from(0xc1u32)),
+            Some("Vault \"v2\"")
+        );
+
+        assert!(matches!(
+            LabelRegistry::parse_toml("[addresses]\n0x10 = Treasury"),
+            Err(RegistryError::Toml { line: 2, .. })
+        ));
+        assert!(matches!(
+            LabelRegistry::parse_toml("0x10 = \"Treasury\""),
+            Err(RegistryError::Toml { line: 1, .. })
+        ));
+        assert!(matches!(
+            LabelRegistry::parse_toml("[addresses]\n0x10 = \"Treasury\"\n\n[tokens]\n"),
+            Err(RegistryError::Toml { line: 4, .. })
+        ));
+        assert!(matches!(
+            LabelRegistry::parse_toml("[addresses]\n0x10 = 16\n"),
+            Err(RegistryError::Toml { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_toml_syntax() {
+        let toml = r#"
+            [ addresses ] # spaced header
+            0x10 = 'C:\treasury' # = the multisig
+            "0x11" = "Vault \u00e9"
+
+            [classes]
+            "0xc1" = """multi
+line"""
+        "#;
+        let registry = LabelRegistry::parse_toml(toml).unwrap();
+        assert_eq!(
+            registry.address_name(&BigUint::from(0x10u32)),
+            Some("C:\\treasury")
+        );
+        assert_eq!(
+            registry.address_name(&BigUint::from(0x11u32)),
+            Some("Vault \u{e9}")
+        );
+        assert_eq!(
+            registry.class_name(&BigUint::from(0xc1u32)),
+            Some("multi\nline")
+        );
+        assert!(matches!(
+            LabelRegistry::parse_toml("[addresses]\nbob = \"Bob\"\n"),
+            Err(RegistryError::InvalidFelt(felt)) if felt == "bob"
+        ));
+    }
+
+    #[test]
+    fn test_layers_and_names() {
+        let eth = felt(COMMON_ADDRESSES[0].0);
+        let bridge = felt(MAINNET_ADDRESSES[0].0);
+        let mut registry = LabelRegistry::defaults(Network::Mainnet);
+        assert_eq!(registry.address_name(&eth), Some("ETH token"));
+        assert_eq!(
+            LabelRegistry::defaults(Network::Sepolia).address_name(&bridge),
+            None
+        );
+
+        registry.extend(LabelRegistry::parse_toml("[addresses]\n0x10 = \"Treasury\"\n").unwrap());
+        registry.extend(LabelRegistry::parse_json(r#"{"addresses": {"0x10": "Vault"}}"#).unwrap());
+        let account_class = felt(COMMON_CLASSES[1].0);
+        let data = DataJson {
+            state_update_size: 1,
+            state_update: vec![ContractUpdate {
+                address: eth.clone(),
+                nonce: 0,
+                number_of_storage_updates: 2,
+                new_class_hash: None,
+                storage_updates: vec![
+                    StorageUpdate {
+                        key: BigUint::from(1u32),
+                        value: BigUint::from(0x10u32),
+                    },
+                    StorageUpdate {
+                        key: BigUint::from(2u32),
+                        value: BigUint::from(0x11u32),
+                    },
+                ],
+            }],
+            class_declaration_size: 1,
+            class_declaration: vec![ClassDeclaration {
+                class_hash: account_class.clone(),
+                compiled_class_hash: BigUint::from(1u32),
+            }],
+        };
+        let names = registry.names(&data);
+        assert_eq!(
+            names.addresses,
+            BTreeMap::from([
+                (eth.clone(), "ETH token".to_string()),
+                (BigUint::from(0x10u32), "Vault".to_string())
+            ])
+        );
+        assert_eq!(
+            serde_json::to_value(&names).unwrap()["classes"][account_class.to_str_radix(10)],
+            "Argent account v0.3.0"
+        );
+
+        let names = registry.names_of([&eth, &account_class, &BigUint::from(0x11u32)]);
+        assert_eq!(names.addresses.len(), 1);
+        assert_eq!(names.addresses[&eth], "ETH token");
+        assert_eq!(names.classes[&account_class], "Argent account v0.3.0");
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::{BTreeMap, HashMap};
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::hash::{pedersen_hash, sn_keccak, HashError};
+use majin_blob_types::state_diffs::{self, DataJson};
+
+lazy_static! {
+    /// The bound of storage addresses, `2**251 - 256`.
+    pub(crate) static ref ADDR_BOUND: BigUint = (BigUint::from(1u32) << 251u32) - 256u32;
+}
+
+/// Number of slots following a labelled address that are labelled with an offset, such
+/// as the high half of a `u256`, stored after its low half.
+pub(crate) const MAX_OFFSET: u32 = 1;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LabelError {
+    #[error("invalid label file: {0}")]
+    Json(String),
+    #[error("invalid felt {0}")]
+    InvalidFelt(String),
+}
+
+/// Computes the storage address of a storage variable, or of an entry of a storage map.
+///
+/// # Arguments
+///
+/// * `name` - The name of the variable, such as `ERC20_balances`.
+/// * `keys` - The keys of the map entry, none for a plain variable.
+///
+/// # Returns
+///
+/// `sn_keccak(name)`, hashed with Pedersen with each key in turn, reduced below
+/// `2**251 - 256`, or an error if a key is not a felt.
+pub fn storage_address(name: &str, keys: &[BigUint]) -> Result<BigUint, HashError> {
+    let address = keys
+        .iter()
+        .try_fold(sn_keccak(name), |address, key| pedersen_hash(&address, key))?;
+    Ok(address % &*ADDR_BOUND)
+}
+
+/// Storage variables to label, with the keys to try for maps.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSpec {
+    /// The contract whose storage is labelled.
+    pub contract: Option<BigUint>,
+    /// The class whose contracts have their storage labelled.
+    pub class_hash: Option<BigUint>,
+    /// The names of the storage variables.
+    pub variables: Vec<String>,
+    /// The keys of map entries to label.
+    pub keys: Vec<BigUint>,
+}
+
+impl LabelSpec {
+    /// Parses specs from JSON, a list of objects such as
+    /// `{"contract": "0x49d3...", "variables": ["ERC20_balances"], "keys": ["0x123"]}`.
+    /// Specs without contract nor class hash apply to every contract.
+    pub fn parse_json(json: &str) -> Result<Vec<LabelSpec>, LabelError> {
+        let specs: Value =
+            serde_json::from_str(json).map_err(|e| LabelError::Json(e.to_string()))?;
+        let specs = specs
+            .as_array()
+            .ok_or_else(|| LabelError::Json("expected a list of specs".to_string()))?;
+        specs
+            .iter()
+            .map(|spec| {
+                let felt = |field: &str| spec.get(field).map(parse_felt).transpose();
+                let strings = |field: &str| -> Result<Vec<&str>, LabelError> {
+                    match spec.get(field) {
+                        None => Ok(Vec::new()),
+                        Some(values) => values
+                            .as_array()
+                            .and_then(|values| values.iter().map(Value::as_str).collect())
+                            .ok_or_else(|| {
+                                LabelError::Json(format!("{} must be a list of strings", field))
+                            }),
+                    }
+                };
+                Ok(LabelSpec {
+                    contract: felt("contract")?,
+                    class_hash: felt("class_hash")?,
+                    variables: strings("variables")?
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                    keys: strings("keys")?
+                        .into_iter()
+                        .map(|key| parse_felt(&Value::from(key)))
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Names the storage keys of known storage variables.
+#[derive(Debug, Clone, Default)]
+pub struct StorageLabeller {
+    /// Labels applying to every contract, by storage key.
+    any: HashMap<BigUint, String>,
+    /// Labels by contract address, then storage key.
+    contracts: HashMap<BigUint, HashMap<BigUint, String>>,
+    /// Labels by class hash, then storage key.
+    classes: HashMap<BigUint, HashMap<BigUint, String>>,
+}
+
+impl StorageLabeller {
+    /// Creates a labeller for the variables of `specs`.
+    pub fn new(specs: &[LabelSpec]) -> Self {
+        let mut labeller = StorageLabeller::default();
+        for spec in specs {
+            let labels = match (&spec.contract, &spec.class_hash) {
+                (Some(contract), _) => labeller.contracts.entry(contract.clone()).or_default(),
+                (None, Some(class_hash)) => labeller.classes.entry(class_hash.clone()).or_default(),
+                (None, None) => &mut labeller.any,
+            };
+            for name in &spec.variables {
+                if let Ok(address) = storage_address(name, &[]) {
+                    add_labels(labels, address, name.clone());
+                }
+                // Keys out of the field cannot be map keys
+                for key in &spec.keys {
+                    if let Ok(address) = storage_address(name, std::slice::from_ref(key)) {
+                        add_labels(
+                            labels,
+                            address,
+                            format!("{}[0x{}]", name, key.to_str_radix(16)),
+                        );
+                    }
+                }
+            }
+        }
+        labeller
+    }
+
+    /// Returns the label of a storage key of a contract, looked up for the contract, then
+    /// for its class, then for every contract.
+    pub fn label(
+        &self,
+        contract: &BigUint,
+        class_hash: Option<&BigUint>,
+        key: &BigUint,
+    ) -> Option<&str> {
+        let by_class = class_hash.and_then(|class_hash| self.classes.get(class_hash));
+        [self.contracts.get(contract), by_class, Some(&self.any)]
+            .into_iter()
+            .flatten()
+            .find_map(|labels| labels.get(key))
+            .map(String::as_str)
+    }
+
+    /// Labels the storage keys written by state diffs.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The state diffs.
+    /// * `classes` - The class hashes of contracts, known from elsewhere. The class hashes
+    ///   set by the state diffs take precedence.
+    pub fn label_state_diffs(
+        &self,
+        data: &DataJson,
+        classes: &HashMap<BigUint, BigUint>,
+    ) -> StorageLabels {
+        let mut labels = StorageLabels::default();
+        for contract in &data.state_update {
+            let class_hash = contract
+                .new_class_hash
+                .as_ref()
+                .or_else(|| classes.get(&contract.address));
+            for update in &contract.storage_updates {
+                if let Some(label) = self.label(&contract.address, class_hash, &update.key) {
+                    labels
+                        .0
+                        .entry(contract.address.clone())
+                        .or_default()
+                        .insert(update.key.clone(), label.to_string());
+                }
+            }
+        }
+        labels
+    }
+}
+
+/// Labels of storage keys, by contract address, then storage key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageLabels(pub BTreeMap<BigUint, BTreeMap<BigUint, String>>);
+
+impl Serialize for StorageLabels {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(contract, labels)| {
+            let labels: BTreeMap<String, &String> = labels
+                .iter()
+                .map(|(key, label)| (key.to_str_radix(10), label))
+                .collect();
+            (contract.to_str_radix(10), labels)
+        }))
+    }
+}
+
+/// Labels a storage address, and the slots following it with their offset.
+fn add_labels(labels: &mut HashMap<BigUint, String>, address: BigUint, label: String) {
+    for offset in 1..=MAX_OFFSET {
+        labels.insert(&address + offset, format!("{}+{}", label, offset));
+    }
+    labels.insert(address, label);
+}
+
+fn parse_felt(value: &Value) -> Result<BigUint, LabelError> {
+    let value = value
+        .as_str()
+        .ok_or_else(|| LabelError::InvalidFelt(value.to_string()))?;
+    state_diffs::parse_felt(value).map_err(|_| LabelError::InvalidFelt(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin_blob_types::state_diffs::{ContractUpdate, StorageUpdate};
+    use num_traits::Num;
+
+    fn felt(hex: &str) -> BigUint {
+        BigUint::from_str_radix(hex, 16).unwrap()
+    }
+
+    #[test]
+    fn test_sn_keccak() {
+        assert_eq!(
+            sn_keccak("transfer"),
+            felt("83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e")
+        );
+        assert_eq!(storage_address("transfer", &[]), Ok(sn_keccak("transfer")));
+        assert_eq!(
+            storage_address("balances", &[BigUint::from(5u32)]),
+            Ok(pedersen_hash(&sn_keccak("balances"), &BigUint::from(5u32)).unwrap() % &*ADDR_BOUND)
+        );
+    }
+
+    #[test]
+    fn test_label_state_diffs() {
+        let specs = LabelSpec::parse_json(
+            r#"[
+                {"contract": "0x10", "variables": ["ERC20_balances"], "keys": ["0x123"]},
+                {"class_hash": "0xc1", "variables": ["Account_public_key"]},
+                {"variables": ["Ownable_owner"]}
+            ]"#,
+        )
+        .unwrap();
+        let labeller = StorageLabeller::new(&specs);
+        let balance = storage_address("ERC20_balances", &[felt("123")]).unwrap();
+        let update = |address: u32, class_hash: Option<u32>, keys: Vec<BigUint>| ContractUpdate {
+            address: BigUint::from(address),
+            nonce: 0,
+            number_of_storage_updates: keys.len() as u64,
+            new_class_hash: class_hash.map(BigUint::from),
+            storage_updates: keys
+                .into_iter()
+                .map(|key| StorageUpdate {
+                    key,
+                    value: BigUint::from(1u32),
+                })
+                .collect(),
+        };
+        let data = DataJson {
+            state_update_size: 3,
+            state_update: vec![
+                update(
+                    0x10,
+                    None,
+                    vec![balance.clone(), &balance + 1u32, BigUint::from(7u32)],
+                ),
+                update(
+                    0x20,
+                    None,
+                    vec![storage_address("Account_public_key", &[]).unwrap()],
+                ),
+                update(
+                    0x30,
+                    None,
+                    vec![storage_address("Ownable_owner", &[]).unwrap()],
+                ),
+            ],
+            class_declaration_size: 0,
+            class_declaration: Vec::new(),
+        };
+        let classes = HashMap::from([(BigUint::from(0x20u32), BigUint::from(0xc1u32))]);
+        let labels = labeller.label_state_diffs(&data, &classes);
+
+        let contract = &labels.0[&BigUint::from(0x10u32)];
+        assert_eq!(contract[&balance], "ERC20_balances[0x123]");
+        assert_eq!(contract[&(&balance + 1u32)], "ERC20_balances[0x123]+1");
+        assert_eq!(contract.len(), 2);
+        assert_eq!(
+            labels.0[&BigUint::from(0x20u32)].values().next().unwrap(),
+            "Account_public_key"
+        );
+        assert_eq!(
+            labels.0[&BigUint::from(0x30u32)].values().next().unwrap(),
+            "Ownable_owner"
+        );
+        // Without its class, the key of the account is not labelled
+        let labels = labeller.label_state_diffs(&data, &HashMap::new());
+        assert!(!labels.0.contains_key(&BigUint::from(0x20u32)));
+    }
+}
@@ -0,0 +1,256 @@
+//! Balance changes of ERC20 tokens in state diffs.
+//!
+//! ERC20 contracts store balances in a map keyed by account, as `u256` values split in a
+//! low half at the key of the entry and a high half at the next key. State diffs only
+//! carry the halves that changed.
+
+use std::collections::{BTreeSet, HashMap};
+
+use num_bigint::{BigInt, BigUint};
+use serde::{Serialize, Serializer};
+
+use crate::preimage::PreimageResolver;
+use crate::storage_labels::storage_address;
+use majin_blob_types::state_diffs::{serialize_biguint, serialize_option_biguint, DataJson};
+
+/// Name of the balances storage variable of the ERC20 contracts of Starknet.
+pub const ERC20_BALANCES: &str = "ERC20_balances";
+
+/// Address of the ETH token, on mainnet and Sepolia.
+pub const ETH_ADDRESS: &str = "049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+/// Address of the STRK token, on mainnet and Sepolia.
+pub const STRK_ADDRESS: &str = "04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+
+/// Reads the value of a storage slot of a contract, by contract address and key, in the
+/// state preceding state diffs. Slots never written are zero.
+pub type PreviousStorage<'a> = &'a dyn Fn(&BigUint, &BigUint) -> BigUint;
+
+/// An ERC20 token contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub name: String,
+    pub address: BigUint,
+    /// The name of the storage variable of balances.
+    pub balances_variable: String,
+}
+
+impl Token {
+    /// Creates a token storing balances in `ERC20_balances`.
+    pub fn new(name: &str, address: BigUint) -> Self {
+        Token {
+            name: name.to_string(),
+            address,
+            balances_variable: ERC20_BALANCES.to_string(),
+        }
+    }
+
+    /// Returns the ETH and STRK tokens, which have the same addresses on mainnet and
+    /// Sepolia.
+    pub fn defaults() -> Vec<Token> {
+        [("ETH", ETH_ADDRESS), ("STRK", STRK_ADDRESS)]
+            .into_iter()
+            .map(|(name, address)| {
+                Token::new(name, BigUint::parse_bytes(address.as_bytes(), 16).unwrap())
+            })
+            .collect()
+    }
+}
+
+/// The new balance of an account whose balance slots were written.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceChange {
+    pub token: String,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub token_address: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub account: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub balance: BigUint,
+    /// The balance in the previous state, if given.
+    #[serde(serialize_with = "serialize_option_biguint")]
+    pub previous_balance: Option<BigUint>,
+    /// The balance minus the previous balance.
+    #[serde(serialize_with = "serialize_option_bigint")]
+    pub delta: Option<BigInt>,
+    /// Whether both halves of the balance are known. A half neither written by the state
+    /// diffs nor found in a previous state is taken as zero.
+    pub complete: bool,
+}
+
+/// Reports the new balances written by state diffs.
+///
+/// # Arguments
+///
+/// * `data` - The state diffs.
+/// * `tokens` - The tokens to report on.
+/// * `accounts` - Accounts to look for, on top of the addresses seen in the state diffs.
+/// * `previous` - The storage of the state preceding the state diffs, to compute deltas.
+///
+/// # Returns
+///
+/// The balance changes, by token then account. Balances of accounts that appear neither
+/// in the state diffs nor in `accounts` cannot be located and are not reported.
+pub fn balance_changes(
+    data: &DataJson,
+    tokens: &[Token],
+    accounts: &[BigUint],
+    previous: Option<PreviousStorage>,
+) -> Vec<BalanceChange> {
+    let mut candidates: BTreeSet<BigUint> =
+        PreimageResolver::candidate_keys(data).into_keys().collect();
+    candidates.extend(accounts.iter().cloned());
+
+    let mut changes = Vec::new();
+    for token in tokens {
+        let Some(contract) = data
+            .state_update
+            .iter()
+            .find(|contract| contract.address == token.address)
+        else {
+            continue;
+        };
+        let storage: HashMap<&BigUint, &BigUint> = contract
+            .storage_updates
+            .iter()
+            .map(|update| (&update.key, &update.value))
+            .collect();
+        let written = |key: &BigUint| storage.get(key).map(|value| (*value).clone());
+        for account in &candidates {
+            // Accounts out of the field cannot hold a balance
+            let Ok(low_key) =
+                storage_address(&token.balances_variable, std::slice::from_ref(account))
+            else {
+                continue;
+            };
+            let high_key = &low_key + 1u32;
+            let (low, high) = (written(&low_key), written(&high_key));
+            if low.is_none() && high.is_none() {
+                continue;
+            }
+            let previous_slot =
+                |key: &BigUint| previous.map(|previous| previous(&token.address, key));
+            let previous_balance = previous.map(|_| {
+                join_u256(
+                    &previous_slot(&low_key).unwrap_or_default(),
+                    &previous_slot(&high_key).unwrap_or_default(),
+                )
+            });
+            let complete = previous.is_some() || (low.is_some() && high.is_some());
+            let balance = join_u256(
+                &low.or_else(|| previous_slot(&low_key)).unwrap_or_default(),
+                &high
+                    .or_else(|| previous_slot(&high_key))
+                    .unwrap_or_default(),
+            );
+            let delta = previous_balance
+                .as_ref()
+                .map(|previous| BigInt::from(balance.clone()) - BigInt::from(previous.clone()));
+            changes.push(BalanceChange {
+                token: token.name.clone(),
+                token_address: token.address.clone(),
+                account: account.clone(),
+                balance,
+                delta,
+                previous_balance,
+                complete,
+            });
+        }
+    }
+    changes
+}
+
+/// Joins the low and high 128-bit halves of a `u256`.
+fn join_u256(low: &BigUint, high: &BigUint) -> BigUint {
+    (high << 128u32) + low
+}
+
+fn serialize_option_bigint<S>(value: &Option<BigInt>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(value) => serializer.serialize_some(&value.to_str_radix(10)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin_blob_types::state_diffs::{ContractUpdate, StorageUpdate};
+    use num_traits::Zero;
+
+    fn contract(address: BigUint, storage: Vec<(BigUint, BigUint)>) -> ContractUpdate {
+        ContractUpdate {
+            address,
+            nonce: 1,
+            number_of_storage_updates: storage.len() as u64,
+            new_class_hash: None,
+            storage_updates: storage
+                .into_iter()
+                .map(|(key, value)| StorageUpdate { key, value })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_balance_changes() {
+        let eth = Token::defaults().remove(0);
+        let alice = BigUint::from(0xa11ceu32) << 200u32;
+        let bob = BigUint::from(0xb0bu32) << 200u32;
+        let balance_key = |account: &BigUint| {
+            storage_address(ERC20_BALANCES, std::slice::from_ref(account)).unwrap()
+        };
+        let data = DataJson {
+            state_update_size: 2,
+            state_update: vec![
+                contract(alice.clone(), Vec::new()),
+                contract(
+                    eth.address.clone(),
+                    vec![
+                        // Alice gets 2**128 + 5, Bob is only known from `accounts`
+                        (balance_key(&alice), BigUint::from(5u32)),
+                        (balance_key(&alice) + 1u32, BigUint::from(1u32)),
+                        (balance_key(&bob), BigUint::from(7u32)),
+                    ],
+                ),
+            ],
+            class_declaration_size: 0,
+            class_declaration: Vec::new(),
+        };
+        let two_pow_128 = BigUint::from(1u32) << 128u32;
+
+        let (tokens, accounts) = ([eth], [bob.clone()]);
+        let changes = balance_changes(&data, &tokens, &accounts, None);
+        assert_eq!(changes.len(), 2);
+        let (for_alice, for_bob) = match changes[0].account == alice {
+            true => (&changes[0], &changes[1]),
+            false => (&changes[1], &changes[0]),
+        };
+        assert_eq!(for_alice.balance, &two_pow_128 + 5u32);
+        assert!(for_alice.complete);
+        // The high half of Bob's balance did not change and is unknown
+        assert_eq!(for_bob.balance, BigUint::from(7u32));
+        assert!(!for_bob.complete);
+        assert_eq!(for_bob.delta, None);
+
+        // Bob had 2**128 + 10
+        let bob_high = balance_key(&bob) + 1u32;
+        let previous = |_: &BigUint, key: &BigUint| match *key == bob_high {
+            true => BigUint::from(1u32),
+            false if *key == balance_key(&bob) => BigUint::from(10u32),
+            false => BigUint::zero(),
+        };
+        let changes = balance_changes(&data, &tokens, &accounts, Some(&previous));
+        let for_bob = changes.iter().find(|change| change.account == bob).unwrap();
+        assert_eq!(for_bob.balance, &two_pow_128 + 7u32);
+        assert_eq!(for_bob.previous_balance, Some(&two_pow_128 + 10u32));
+        assert_eq!(for_bob.delta, Some(BigInt::from(-3)));
+        assert!(for_bob.complete);
+        assert_eq!(
+            serde_json::to_value(for_bob).unwrap()["delta"],
+            serde_json::json!("-3")
+        );
+    }
+}
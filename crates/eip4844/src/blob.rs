@@ -0,0 +1,127 @@
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{BLOB_LEN, BLS_MODULUS, BYTES_PER_BLOB, BYTES_PER_COMMITMENT, BYTES_PER_FIELD_ELEMENT};
+
+/// Version byte of the versioned hashes of KZG commitments.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BlobError {
+    #[error("expected {expected} bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("field element {0} is not lower than the BLS modulus")]
+    NonCanonicalFieldElement(usize),
+    #[error("{0} is not lower than the BLS modulus")]
+    InvalidFieldElement(BigUint),
+}
+
+/// Serializes blob field elements into the 131072 bytes of a blob.
+///
+/// # Arguments
+///
+/// * `data` - The `BLOB_LEN` field elements of the blob.
+///
+/// # Returns
+///
+/// The big-endian encoding of every field element, concatenated.
+pub fn blob_to_bytes(data: &[BigUint]) -> Result<Vec<u8>, BlobError> {
+    if data.len() != BLOB_LEN {
+        return Err(BlobError::InvalidLength {
+            expected: BLOB_LEN,
+            actual: data.len(),
+        });
+    }
+    let mut bytes = vec![0u8; BYTES_PER_BLOB];
+    for (i, element) in data.iter().enumerate() {
+        if *element >= *BLS_MODULUS {
+            return Err(BlobError::NonCanonicalFieldElement(i));
+        }
+        let element_bytes = element.to_bytes_be();
+        let end = (i + 1) * BYTES_PER_FIELD_ELEMENT;
+        bytes[end - element_bytes.len()..end].copy_from_slice(&element_bytes);
+    }
+    Ok(bytes)
+}
+
+/// Deserializes the 131072 bytes of a blob into its field elements.
+///
+/// # Arguments
+///
+/// * `bytes` - The blob bytes.
+///
+/// # Returns
+///
+/// A vector of `BigUint` in the same form as `parse_str_to_blob_data` returns, or an
+/// error if a field element is not lower than the BLS modulus.
+pub fn blob_from_bytes(bytes: &[u8]) -> Result<Vec<BigUint>, BlobError> {
+    if bytes.len() != BYTES_PER_BLOB {
+        return Err(BlobError::InvalidLength {
+            expected: BYTES_PER_BLOB,
+            actual: bytes.len(),
+        });
+    }
+    bytes
+        .chunks(BYTES_PER_FIELD_ELEMENT)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let element = BigUint::from_bytes_be(chunk);
+            if element >= *BLS_MODULUS {
+                return Err(BlobError::NonCanonicalFieldElement(i));
+            }
+            Ok(element)
+        })
+        .collect()
+}
+
+/// Computes the versioned hash of a KZG commitment, as `kzg_to_versioned_hash` in EIP-4844.
+pub fn kzg_to_versioned_hash(commitment: &[u8; BYTES_PER_COMMITMENT]) -> [u8; 32] {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+/// Encodes a field element as the 32 big-endian bytes EIP-4844 uses for `z` and `y`.
+///
+/// # Returns
+///
+/// The bytes, or an error if the value is not lower than the BLS modulus.
+pub fn field_element_to_bytes(value: &BigUint) -> Result<[u8; 32], BlobError> {
+    if *value >= *BLS_MODULUS {
+        return Err(BlobError::InvalidFieldElement(value.clone()));
+    }
+    let bytes = value.to_bytes_be();
+    let mut buffer = [0u8; 32];
+    buffer[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_from_bytes_rejects_non_canonical_elements() {
+        let mut bytes = vec![0u8; BYTES_PER_BLOB];
+        let modulus = BLS_MODULUS.to_bytes_be();
+        let element = 7 * BYTES_PER_FIELD_ELEMENT..8 * BYTES_PER_FIELD_ELEMENT;
+
+        bytes[element.clone()].copy_from_slice(&(&*BLS_MODULUS - 1u32).to_bytes_be());
+        let data = blob_from_bytes(&bytes).unwrap();
+        assert_eq!(data[7], &*BLS_MODULUS - 1u32);
+        assert_eq!(blob_to_bytes(&data).unwrap(), bytes);
+
+        bytes[element.clone()].copy_from_slice(&modulus);
+        assert_eq!(
+            blob_from_bytes(&bytes),
+            Err(BlobError::NonCanonicalFieldElement(7))
+        );
+
+        bytes[element].fill(0xff);
+        assert_eq!(
+            blob_from_bytes(&bytes),
+            Err(BlobError::NonCanonicalFieldElement(7))
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::math::{
+    batch_inverse, bit_reversal_permutation, fft, inv_mod, inverse_fft, powers, reverse_bits,
+    root_of_unity,
+};
+use crate::{BLOB_LEN, BLS_MODULUS, PRIMITIVE_ROOT_OF_UNITY};
+
+/// Number of field elements of an extended blob (EIP-7594 parameter).
+pub const FIELD_ELEMENTS_PER_EXT_BLOB: usize = 2 * BLOB_LEN;
+/// Number of field elements in a cell (EIP-7594 parameter).
+pub const FIELD_ELEMENTS_PER_CELL: usize = 64;
+/// Number of cells of an extended blob.
+pub const CELLS_PER_EXT_BLOB: usize = FIELD_ELEMENTS_PER_EXT_BLOB / FIELD_ELEMENTS_PER_CELL;
+
+/// A cell: `FIELD_ELEMENTS_PER_CELL` consecutive evaluations of the extended blob,
+/// in bit-reversed order.
+pub type Cell = Vec<BigUint>;
+
+#[derive(Debug, Error)]
+pub enum CellError {
+    #[error("expected a blob of {BLOB_LEN} field elements, got {0}")]
+    InvalidBlobLength(usize),
+    #[error("got {indices} cell indices for {cells} cells")]
+    LengthMismatch { indices: usize, cells: usize },
+    #[error("cell {0} does not have {FIELD_ELEMENTS_PER_CELL} field elements")]
+    InvalidCellLength(u64),
+    #[error("cell index {0} is out of range")]
+    InvalidCellIndex(u64),
+    #[error("cell {0} is given more than once")]
+    DuplicateCellIndex(u64),
+    #[error("cell {0} holds a value that is not lower than the BLS modulus")]
+    NonCanonicalFieldElement(u64),
+    #[error("need at least {} cells to recover a blob, got {0}", CELLS_PER_EXT_BLOB / 2)]
+    NotEnoughCells(usize),
+    #[error("cells are not evaluations of a single blob polynomial")]
+    InconsistentCells,
+    #[cfg(feature = "kzg")]
+    #[error(transparent)]
+    Kzg(#[from] crate::kzg::KzgError),
+}
+
+/// Extends a blob to `FIELD_ELEMENTS_PER_EXT_BLOB` evaluations and splits them into cells.
+///
+/// The first `CELLS_PER_EXT_BLOB / 2` cells hold the blob itself, the others the
+/// Reed-Solomon extension.
+///
+/// # Arguments
+///
+/// * `blob` - The blob field elements, as returned by `parse_str_to_blob_data`.
+///
+/// # Returns
+///
+/// The `CELLS_PER_EXT_BLOB` cells of the extended blob.
+pub fn compute_cells(blob: &[BigUint]) -> Result<Vec<Cell>, CellError> {
+    if blob.len() != BLOB_LEN {
+        return Err(CellError::InvalidBlobLength(blob.len()));
+    }
+    let p = &*BLS_MODULUS;
+    let coefficients = inverse_fft(
+        &bit_reversal_permutation(blob),
+        &root_of_unity(BLOB_LEN, &PRIMITIVE_ROOT_OF_UNITY, p),
+        p,
+    );
+    Ok(coefficients_to_cells(coefficients))
+}
+
+/// Rebuilds a blob from at least half of the cells of its extension.
+///
+/// # Arguments
+///
+/// * `cell_indices` - The indices of the available cells.
+/// * `cells` - The available cells, in the same order as `cell_indices`.
+///
+/// # Returns
+///
+/// The blob field elements, in the same form as `parse_str_to_blob_data` returns.
+pub fn recover_blob(cell_indices: &[u64], cells: &[Cell]) -> Result<Vec<BigUint>, CellError> {
+    let coefficients = recover_coefficients(cell_indices, cells)?;
+    let cells = coefficients_to_cells(coefficients);
+    Ok(cells[..CELLS_PER_EXT_BLOB / 2].concat())
+}
+
+/// Rebuilds all the cells of an extended blob from at least half of them.
+///
+/// # Arguments
+///
+/// * `cell_indices` - The indices of the available cells.
+/// * `cells` - The available cells, in the same order as `cell_indices`.
+///
+/// # Returns
+///
+/// The `CELLS_PER_EXT_BLOB` cells of the extended blob.
+pub fn recover_cells(cell_indices: &[u64], cells: &[Cell]) -> Result<Vec<Cell>, CellError> {
+    Ok(coefficients_to_cells(recover_coefficients(
+        cell_indices,
+        cells,
+    )?))
+}
+
+/// Computes the cells of a blob extension and the KZG proof of every cell.
+#[cfg(feature = "kzg")]
+pub fn compute_cells_and_kzg_proofs(
+    blob: &[BigUint],
+) -> Result<(Vec<Cell>, Vec<crate::kzg::KzgProof>), CellError> {
+    let cells = compute_cells(blob)?;
+    let proofs = compute_cell_proofs(blob)?;
+    Ok((cells, proofs))
+}
+
+/// Rebuilds all the cells of an extended blob from at least half of them, and computes
+/// the KZG proof of every cell.
+#[cfg(feature = "kzg")]
+pub fn recover_cells_and_kzg_proofs(
+    cell_indices: &[u64],
+    cells: &[Cell],
+) -> Result<(Vec<Cell>, Vec<crate::kzg::KzgProof>), CellError> {
+    let cells = recover_cells(cell_indices, cells)?;
+    let blob = cells[..CELLS_PER_EXT_BLOB / 2].concat();
+    let proofs = compute_cell_proofs(&blob)?;
+    Ok((cells, proofs))
+}
+
+#[cfg(feature = "kzg")]
+fn compute_cell_proofs(blob: &[BigUint]) -> Result<Vec<crate::kzg::KzgProof>, CellError> {
+    use crate::kzg::{kzg_settings, to_kzg_blob, KzgError};
+
+    let blob = to_kzg_blob(blob)?;
+    let (_, proofs) = kzg_settings()
+        .compute_cells_and_kzg_proofs(&blob)
+        .map_err(KzgError::from)?;
+    Ok(proofs.iter().map(|proof| *proof.to_bytes()).collect())
+}
+
+/// Evaluates the polynomial over the extended domain and splits the result into cells.
+fn coefficients_to_cells(mut coefficients: Vec<BigUint>) -> Vec<Cell> {
+    let p = &*BLS_MODULUS;
+    coefficients.resize(FIELD_ELEMENTS_PER_EXT_BLOB, BigUint::zero());
+    let root = root_of_unity(FIELD_ELEMENTS_PER_EXT_BLOB, &PRIMITIVE_ROOT_OF_UNITY, p);
+    let extended = bit_reversal_permutation(&fft(&coefficients, &root, p));
+    extended
+        .chunks(FIELD_ELEMENTS_PER_CELL)
+        .map(|cell| cell.to_vec())
+        .collect()
+}
+
+/// Recovers the coefficients of the blob polynomial from a subset of its cells.
+///
+/// With `E` the extended evaluations (zero where missing) and `Z` the polynomial vanishing
+/// on the missing evaluations, `E * Z` and `P * Z` agree on the whole domain, so `P * Z`
+/// is interpolated from `E * Z` and `P` is obtained by dividing by `Z` over a coset of
+/// the domain, where `Z` has no roots.
+fn recover_coefficients(cell_indices: &[u64], cells: &[Cell]) -> Result<Vec<BigUint>, CellError> {
+    if cell_indices.len() != cells.len() {
+        return Err(CellError::LengthMismatch {
+            indices: cell_indices.len(),
+            cells: cells.len(),
+        });
+    }
+    let mut available = BTreeMap::new();
+    for (index, cell) in cell_indices.iter().zip(cells) {
+        if *index as usize >= CELLS_PER_EXT_BLOB {
+            return Err(CellError::InvalidCellIndex(*index));
+        }
+        if cell.len() != FIELD_ELEMENTS_PER_CELL {
+            return Err(CellError::InvalidCellLength(*index));
+        }
+        // Values would otherwise be silently reduced to other ones
+        if cell.iter().any(|value| *value >= *BLS_MODULUS) {
+            return Err(CellError::NonCanonicalFieldElement(*index));
+        }
+        if available.insert(*index as usize, cell).is_some() {
+            return Err(CellError::DuplicateCellIndex(*index));
+        }
+    }
+    if available.len() < CELLS_PER_EXT_BLOB / 2 {
+        return Err(CellError::NotEnoughCells(available.len()));
+    }
+
+    let p = &*BLS_MODULUS;
+    let n = FIELD_ELEMENTS_PER_EXT_BLOB;
+    let root = root_of_unity(n, &PRIMITIVE_ROOT_OF_UNITY, p);
+    let bits = n.trailing_zeros();
+
+    // The evaluations of a cell are the roots of `x^FIELD_ELEMENTS_PER_CELL - s`, with `s`
+    // the `FIELD_ELEMENTS_PER_CELL`-th power of the first point of the cell.
+    let missing: Vec<usize> = (0..CELLS_PER_EXT_BLOB)
+        .filter(|index| !available.contains_key(index))
+        .collect();
+    let mut vanishing = vec![BigUint::one()];
+    for index in &missing {
+        let first = reverse_bits(index * FIELD_ELEMENTS_PER_CELL, bits);
+        let s = root.modpow(&BigUint::from(first * FIELD_ELEMENTS_PER_CELL), p);
+        let mut next = vec![BigUint::zero(); vanishing.len() + 1];
+        for (i, c) in vanishing.iter().enumerate() {
+            next[i + 1] = (&next[i + 1] + c) % p;
+            next[i] = (&next[i] + p - c * &s % p) % p;
+        }
+        vanishing = next;
+    }
+    let mut vanishing_coefficients = vec![BigUint::zero(); n];
+    for (i, c) in vanishing.into_iter().enumerate() {
+        vanishing_coefficients[i * FIELD_ELEMENTS_PER_CELL] = c;
+    }
+    let vanishing_evaluations = fft(&vanishing_coefficients, &root, p);
+
+    let mut extended = vec![BigUint::zero(); n];
+    for (index, cell) in available {
+        for (k, value) in cell.iter().enumerate() {
+            extended[index * FIELD_ELEMENTS_PER_CELL + k] = value.clone();
+        }
+    }
+    let extended = bit_reversal_permutation(&extended);
+    let product: Vec<BigUint> = extended
+        .iter()
+        .zip(vanishing_evaluations.iter())
+        .map(|(e, z)| e * z % p)
+        .collect();
+    let product_coefficients = inverse_fft(&product, &root, p);
+
+    // Divide over the coset `k * <root>` with `k` the primitive root, not a root of `Z`.
+    let shift = powers(&PRIMITIVE_ROOT_OF_UNITY, n, p);
+    let shifted = |coefficients: &[BigUint]| -> Vec<BigUint> {
+        let scaled: Vec<BigUint> = coefficients
+            .iter()
+            .zip(shift.iter())
+            .map(|(c, k)| c * k % p)
+            .collect();
+        fft(&scaled, &root, p)
+    };
+    let quotient: Vec<BigUint> = shifted(&product_coefficients)
+        .iter()
+        .zip(batch_inverse(&shifted(&vanishing_coefficients), p))
+        .map(|(a, b)| a * b % p)
+        .collect();
+    let shift_inverse = powers(&inv_mod(&PRIMITIVE_ROOT_OF_UNITY, p), n, p);
+    let mut coefficients: Vec<BigUint> = inverse_fft(&quotient, &root, p)
+        .iter()
+        .zip(shift_inverse.iter())
+        .map(|(c, k)| c * k % p)
+        .collect();
+
+    if coefficients[BLOB_LEN..].iter().any(|c| !c.is_zero()) {
+        return Err(CellError::InconsistentCells);
+    }
+    coefficients.truncate(BLOB_LEN);
+    Ok(coefficients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::example_blob;
+
+    #[test]
+    fn test_compute_cells_keeps_blob_in_first_half() {
+        let blob = example_blob();
+        let cells = compute_cells(&blob).unwrap();
+        assert_eq!(cells.len(), CELLS_PER_EXT_BLOB);
+        assert_eq!(cells[..CELLS_PER_EXT_BLOB / 2].concat(), blob);
+    }
+
+    #[test]
+    fn test_recover_from_half_of_the_cells() {
+        let blob = example_blob();
+        let cells = compute_cells(&blob).unwrap();
+        // Keep every odd cell, so that half of the original blob is missing.
+        let (indices, sampled): (Vec<u64>, Vec<Cell>) = cells
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 1)
+            .map(|(i, cell)| (i as u64, cell.clone()))
+            .unzip();
+        assert_eq!(recover_cells(&indices, &sampled).unwrap(), cells);
+        assert_eq!(recover_blob(&indices, &sampled).unwrap(), blob);
+    }
+
+    #[test]
+    fn test_recover_rejects_bad_input() {
+        let cells = compute_cells(&example_blob()).unwrap();
+        let indices: Vec<u64> = (0..63).collect();
+        assert!(matches!(
+            recover_blob(&indices, &cells[..63]),
+            Err(CellError::NotEnoughCells(63))
+        ));
+
+        // With exactly half of the cells any data interpolates, so use one more.
+        let indices: Vec<u64> = (63..128).collect();
+        let mut tampered = cells[63..].to_vec();
+        tampered[0][0] += 1u32;
+        assert!(matches!(
+            recover_blob(&indices, &tampered),
+            Err(CellError::InconsistentCells)
+        ));
+
+        let mut indices: Vec<u64> = (64..128).collect();
+        indices[1] = 64;
+        assert!(matches!(
+            recover_blob(&indices, &cells[64..]),
+            Err(CellError::DuplicateCellIndex(64))
+        ));
+    }
+
+    #[test]
+    fn test_recover_rejects_non_canonical_values() {
+        let cells = compute_cells(&example_blob()).unwrap();
+        let indices: Vec<u64> = (64..128).collect();
+        // The same value modulo the modulus, which recovery would reduce without notice
+        let mut unreduced = cells[64..].to_vec();
+        unreduced[3][0] += &*BLS_MODULUS;
+        assert!(matches!(
+            recover_blob(&indices, &unreduced),
+            Err(CellError::NonCanonicalFieldElement(67))
+        ));
+    }
+
+    #[cfg(feature = "kzg")]
+    #[test]
+    fn test_cells_and_proofs_match_c_kzg() {
+        use crate::kzg::{kzg_settings, to_kzg_blob};
+
+        let blob = example_blob();
+        let (cells, proofs) = compute_cells_and_kzg_proofs(&blob).unwrap();
+        let (expected_cells, _) = kzg_settings()
+            .compute_cells_and_kzg_proofs(&to_kzg_blob(&blob).unwrap())
+            .unwrap();
+        for (cell, expected) in cells.iter().zip(expected_cells.iter()) {
+            assert_eq!(cell.concat_bytes(), expected.to_bytes().to_vec());
+        }
+
+        let indices: Vec<u64> = (32..96).collect();
+        let (recovered_cells, recovered_proofs) =
+            recover_cells_and_kzg_proofs(&indices, &cells[32..96]).unwrap();
+        assert_eq!(recovered_cells, cells);
+        assert_eq!(recovered_proofs, proofs);
+    }
+
+    #[cfg(feature = "kzg")]
+    trait ConcatBytes {
+        fn concat_bytes(&self) -> Vec<u8>;
+    }
+
+    #[cfg(feature = "kzg")]
+    impl ConcatBytes for Cell {
+        fn concat_bytes(&self) -> Vec<u8> {
+            self.iter()
+                .flat_map(|value| crate::blob::field_element_to_bytes(value).unwrap())
+                .collect()
+        }
+    }
+}
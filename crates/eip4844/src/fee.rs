@@ -0,0 +1,251 @@
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Gas consumed by a single blob (EIP-4844 parameter).
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+/// Minimum price of a unit of blob gas, in wei (EIP-4844 parameter).
+pub const MIN_BASE_FEE_PER_BLOB_GAS: u64 = 1;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FeeError {
+    #[error("{fork} blocks hold at most {max} blobs, got {actual}")]
+    TooManyBlobs { fork: Fork, max: u64, actual: u64 },
+}
+
+/// Blob parameters of a fork.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobSchedule {
+    pub target_blobs_per_block: u64,
+    pub max_blobs_per_block: u64,
+    pub base_fee_update_fraction: u64,
+}
+
+impl BlobSchedule {
+    /// Blob gas a block must use to keep the blob base fee constant.
+    pub fn target_blob_gas_per_block(&self) -> u64 {
+        self.target_blobs_per_block * GAS_PER_BLOB
+    }
+
+    /// Maximum blob gas a block can use.
+    pub fn max_blob_gas_per_block(&self) -> u64 {
+        self.max_blobs_per_block * GAS_PER_BLOB
+    }
+}
+
+/// Forks with a distinct blob schedule.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Fork {
+    /// EIP-4844.
+    Cancun,
+    /// EIP-7691.
+    Prague,
+}
+
+impl Fork {
+    /// Returns the blob parameters of the fork.
+    pub fn blob_schedule(&self) -> BlobSchedule {
+        match self {
+            Fork::Cancun => BlobSchedule {
+                target_blobs_per_block: 3,
+                max_blobs_per_block: 6,
+                base_fee_update_fraction: 3_338_477,
+            },
+            Fork::Prague => BlobSchedule {
+                target_blobs_per_block: 6,
+                max_blobs_per_block: 9,
+                base_fee_update_fraction: 5_007_716,
+            },
+        }
+    }
+}
+
+impl FromStr for Fork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cancun" => Ok(Fork::Cancun),
+            "prague" => Ok(Fork::Prague),
+            _ => Err(format!("unknown fork {}, expected cancun or prague", s)),
+        }
+    }
+}
+
+impl fmt::Display for Fork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fork::Cancun => write!(f, "cancun"),
+            Fork::Prague => write!(f, "prague"),
+        }
+    }
+}
+
+/// Cost of the blobs of a transaction.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlobFee {
+    pub fork: Fork,
+    pub excess_blob_gas: u64,
+    pub blob_count: u64,
+    pub blob_gas_used: u64,
+    pub base_fee_per_blob_gas: u128,
+    /// Blob fee paid, in wei.
+    pub blob_fee: u128,
+}
+
+/// Approximates `factor * e ** (numerator / denominator)` using a Taylor expansion,
+/// as `fake_exponential` in EIP-4844.
+///
+/// # Arguments
+///
+/// * `factor` - The multiplier.
+/// * `numerator` - The numerator of the exponent.
+/// * `denominator` - The denominator of the exponent.
+///
+/// # Returns
+///
+/// The approximation, saturated to `u128::MAX`.
+pub fn fake_exponential(factor: u64, numerator: u64, denominator: u64) -> u128 {
+    let numerator = BigUint::from(numerator);
+    let denominator = BigUint::from(denominator);
+    let mut i = 1u64;
+    let mut output = BigUint::zero();
+    let mut numerator_accum = BigUint::from(factor) * &denominator;
+    // The terms are positive: past this bound, the result saturates whatever follows
+    let limit = BigUint::from(u128::MAX) * &denominator;
+    while !numerator_accum.is_zero() {
+        output += &numerator_accum;
+        if output > limit {
+            return u128::MAX;
+        }
+        numerator_accum = numerator_accum * &numerator / (&denominator * i);
+        i += 1;
+    }
+    (output / denominator).to_u128().unwrap_or(u128::MAX)
+}
+
+/// Computes the excess blob gas of a block from its parent, as `calc_excess_blob_gas` in
+/// EIP-4844.
+///
+/// # Arguments
+///
+/// * `parent_excess_blob_gas` - The excess blob gas of the parent block.
+/// * `parent_blob_gas_used` - The blob gas used by the parent block.
+/// * `schedule` - The blob parameters of the fork of the block.
+pub fn calc_excess_blob_gas(
+    parent_excess_blob_gas: u64,
+    parent_blob_gas_used: u64,
+    schedule: &BlobSchedule,
+) -> u64 {
+    parent_excess_blob_gas
+        .saturating_add(parent_blob_gas_used)
+        .saturating_sub(schedule.target_blob_gas_per_block())
+}
+
+/// Computes the price of a unit of blob gas, as `get_base_fee_per_blob_gas` in EIP-4844.
+///
+/// # Arguments
+///
+/// * `excess_blob_gas` - The excess blob gas of the block.
+/// * `schedule` - The blob parameters of the fork of the block.
+pub fn base_fee_per_blob_gas(excess_blob_gas: u64, schedule: &BlobSchedule) -> u128 {
+    fake_exponential(
+        MIN_BASE_FEE_PER_BLOB_GAS,
+        excess_blob_gas,
+        schedule.base_fee_update_fraction,
+    )
+}
+
+/// Computes what a number of blobs cost in a block with the given excess blob gas.
+///
+/// # Arguments
+///
+/// * `fork` - The fork of the block.
+/// * `excess_blob_gas` - The excess blob gas of the block.
+/// * `blob_count` - The number of blobs, at most the maximum of the fork.
+pub fn blob_fee(fork: Fork, excess_blob_gas: u64, blob_count: u64) -> Result<BlobFee, FeeError> {
+    let schedule = fork.blob_schedule();
+    if blob_count > schedule.max_blobs_per_block {
+        return Err(FeeError::TooManyBlobs {
+            fork,
+            max: schedule.max_blobs_per_block,
+            actual: blob_count,
+        });
+    }
+    let base_fee_per_blob_gas = base_fee_per_blob_gas(excess_blob_gas, &schedule);
+    let blob_gas_used = blob_count * GAS_PER_BLOB;
+    Ok(BlobFee {
+        fork,
+        excess_blob_gas,
+        blob_count,
+        blob_gas_used,
+        base_fee_per_blob_gas,
+        blob_fee: base_fee_per_blob_gas.saturating_mul(blob_gas_used as u128),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_exponential() {
+        assert_eq!(fake_exponential(1, 0, 3_338_477), 1);
+        assert_eq!(fake_exponential(1, 10_000_000, 3_338_477), 19);
+        assert_eq!(
+            fake_exponential(1, 100_000_000, 3_338_477),
+            10_203_769_476_395
+        );
+        assert_eq!(fake_exponential(1, 10_000_000, 5_007_716), 7);
+    }
+
+    #[test]
+    fn test_calc_excess_blob_gas() {
+        let cancun = Fork::Cancun.blob_schedule();
+        assert_eq!(calc_excess_blob_gas(0, 2 * GAS_PER_BLOB, &cancun), 0);
+        assert_eq!(
+            calc_excess_blob_gas(0, 6 * GAS_PER_BLOB, &cancun),
+            3 * GAS_PER_BLOB
+        );
+        assert_eq!(
+            calc_excess_blob_gas(GAS_PER_BLOB, 2 * GAS_PER_BLOB, &cancun),
+            0
+        );
+        let prague = Fork::Prague.blob_schedule();
+        assert_eq!(
+            calc_excess_blob_gas(0, 9 * GAS_PER_BLOB, &prague),
+            3 * GAS_PER_BLOB
+        );
+        assert_eq!(
+            calc_excess_blob_gas(u64::MAX, GAS_PER_BLOB, &cancun),
+            u64::MAX - 3 * GAS_PER_BLOB
+        );
+    }
+
+    #[test]
+    fn test_blob_fee() {
+        let fee = blob_fee(Fork::Cancun, 10_000_000, 2).unwrap();
+        assert_eq!(fee.base_fee_per_blob_gas, 19);
+        assert_eq!(fee.blob_gas_used, 2 * GAS_PER_BLOB);
+        assert_eq!(fee.blob_fee, 19 * 2 * GAS_PER_BLOB as u128);
+        assert!(blob_fee(Fork::Prague, 0, 9).is_ok());
+        assert_eq!(
+            blob_fee(Fork::Cancun, 0, u64::MAX),
+            Err(FeeError::TooManyBlobs {
+                fork: Fork::Cancun,
+                max: 6,
+                actual: u64::MAX
+            })
+        );
+
+        // The fee saturates instead of summing the Taylor series to the end
+        let fee = blob_fee(Fork::Cancun, u64::MAX, 1).unwrap();
+        assert_eq!(fee.base_fee_per_blob_gas, u128::MAX);
+        assert_eq!(fee.blob_fee, u128::MAX);
+        assert_eq!("Prague".parse::<Fork>(), Ok(Fork::Prague));
+    }
+}
@@ -0,0 +1,125 @@
+use c_kzg::{ethereum_kzg_settings, Blob, Bytes32, Bytes48, KzgSettings};
+use num_bigint::BigUint;
+use thiserror::Error;
+
+use crate::blob::{blob_to_bytes, field_element_to_bytes, BlobError};
+use crate::{BYTES_PER_COMMITMENT, BYTES_PER_PROOF};
+
+/// A KZG commitment, as a compressed BLS12-381 G1 point.
+pub type KzgCommitment = [u8; BYTES_PER_COMMITMENT];
+/// A KZG proof, as a compressed BLS12-381 G1 point.
+pub type KzgProof = [u8; BYTES_PER_PROOF];
+
+#[derive(Debug, Error)]
+pub enum KzgError {
+    #[error(transparent)]
+    Blob(#[from] BlobError),
+    #[error("KZG error: {0:?}")]
+    CKzg(c_kzg::Error),
+}
+
+impl From<c_kzg::Error> for KzgError {
+    fn from(error: c_kzg::Error) -> Self {
+        KzgError::CKzg(error)
+    }
+}
+
+/// Returns the KZG settings loaded from the Ethereum mainnet trusted setup.
+pub fn kzg_settings() -> &'static KzgSettings {
+    ethereum_kzg_settings(0)
+}
+
+/// Converts blob field elements into a c-kzg blob.
+pub fn to_kzg_blob(data: &[BigUint]) -> Result<Blob, KzgError> {
+    Ok(Blob::from_bytes(&blob_to_bytes(data)?)?)
+}
+
+/// Computes the KZG commitment of a blob.
+///
+/// # Arguments
+///
+/// * `data` - The blob field elements.
+pub fn blob_to_commitment(data: &[BigUint]) -> Result<KzgCommitment, KzgError> {
+    let blob = to_kzg_blob(data)?;
+    Ok(*kzg_settings().blob_to_kzg_commitment(&blob)?.to_bytes())
+}
+
+/// Computes the proof that the polynomial of a blob evaluates to `y` at `z`.
+///
+/// # Arguments
+///
+/// * `data` - The blob field elements.
+/// * `z` - The evaluation point. Must be lower than the BLS modulus.
+///
+/// # Returns
+///
+/// The proof and `y`.
+pub fn compute_proof(data: &[BigUint], z: &BigUint) -> Result<(KzgProof, BigUint), KzgError> {
+    let blob = to_kzg_blob(data)?;
+    let z = Bytes32::from(field_element_to_bytes(z)?);
+    let (proof, y) = kzg_settings().compute_kzg_proof(&blob, &z)?;
+    Ok((*proof.to_bytes(), BigUint::from_bytes_be(y.as_ref())))
+}
+
+/// Computes the blob proof checked by `verify_blob_kzg_proof`, as carried in blob transactions.
+pub fn compute_blob_proof(
+    data: &[BigUint],
+    commitment: &KzgCommitment,
+) -> Result<KzgProof, KzgError> {
+    let blob = to_kzg_blob(data)?;
+    let proof = kzg_settings().compute_blob_kzg_proof(&blob, &Bytes48::from(*commitment))?;
+    Ok(*proof.to_bytes())
+}
+
+/// Verifies the proof that the polynomial committed to evaluates to `y` at `z`.
+///
+/// # Returns
+///
+/// `Ok(false)` if the proof does not hold, and an error if an input is malformed.
+pub fn verify_proof(
+    commitment: &KzgCommitment,
+    z: &[u8; 32],
+    y: &[u8; 32],
+    proof: &KzgProof,
+) -> Result<bool, KzgError> {
+    Ok(kzg_settings().verify_kzg_proof(
+        &Bytes48::from(*commitment),
+        &Bytes32::from(*z),
+        &Bytes32::from(*y),
+        &Bytes48::from(*proof),
+    )?)
+}
+
+/// Verifies a blob proof against a blob and its commitment.
+pub fn verify_blob_proof(
+    data: &[BigUint],
+    commitment: &KzgCommitment,
+    proof: &KzgProof,
+) -> Result<bool, KzgError> {
+    let blob = to_kzg_blob(data)?;
+    Ok(kzg_settings().verify_blob_kzg_proof(
+        &blob,
+        &Bytes48::from(*commitment),
+        &Bytes48::from(*proof),
+    )?)
+}
+
+/// Verifies the cell proofs of a blob, as carried in EIP-7594 blob transactions.
+///
+/// # Arguments
+///
+/// * `data` - The blob field elements.
+/// * `commitment` - The commitment to the blob.
+/// * `proofs` - The proof of every cell of the extended blob, in cell order.
+pub fn verify_cell_proofs(
+    data: &[BigUint],
+    commitment: &KzgCommitment,
+    proofs: &[KzgProof],
+) -> Result<bool, KzgError> {
+    let blob = to_kzg_blob(data)?;
+    let cells = kzg_settings().compute_cells(&blob)?;
+    let commitments = vec![Bytes48::from(*commitment); cells.len()];
+    let indices: Vec<u64> = (0..cells.len() as u64).collect();
+    let proofs: Vec<Bytes48> = proofs.iter().map(|proof| Bytes48::from(*proof)).collect();
+    Ok(kzg_settings().verify_cell_kzg_proof_batch(&commitments, &indices, &cells[..], &proofs)?)
+}
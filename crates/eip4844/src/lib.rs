@@ -1,3 +1,16 @@
+pub mod blob;
+pub mod cells;
+pub mod fee;
+#[cfg(feature = "kzg")]
+pub mod kzg;
+pub mod math;
+#[cfg(feature = "kzg")]
+pub mod precompile;
+pub mod rlp;
+#[cfg(test)]
+mod testutils;
+pub mod transaction;
+
 use lazy_static::lazy_static;
 use num_bigint::{BigUint, ToBigUint};
 use std::str::FromStr;
@@ -7,6 +20,14 @@ use std::str::FromStr;
 // ****************************************************************************
 /// Length of the blob.
 pub const BLOB_LEN: usize = 4096;
+/// Size in bytes of a field element of a blob.
+pub const BYTES_PER_FIELD_ELEMENT: usize = 32;
+/// Size in bytes of a blob.
+pub const BYTES_PER_BLOB: usize = BLOB_LEN * BYTES_PER_FIELD_ELEMENT;
+/// Size in bytes of a KZG commitment.
+pub const BYTES_PER_COMMITMENT: usize = 48;
+/// Size in bytes of a KZG proof.
+pub const BYTES_PER_PROOF: usize = 48;
 lazy_static! {
     /// EIP-4844 BLS12-381 modulus.
     ///
@@ -15,6 +36,8 @@ lazy_static! {
         "52435875175126190479447740508185965837690552500527637822603658699938581184513",
     )
     .unwrap();
+    /// Primitive root of unity of the BLS12-381 scalar field (EIP-4844 parameter).
+    pub static ref PRIMITIVE_ROOT_OF_UNITY: BigUint = 7u32.to_biguint().unwrap();
     /// Generator of the group of evaluation points (EIP-4844 parameter).
     pub static ref GENERATOR: BigUint = BigUint::from_str(
         "39033254847818212395286706435128746857159659164139250548781411570340225835782",
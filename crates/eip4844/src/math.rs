@@ -0,0 +1,253 @@
+use lazy_static::lazy_static;
+use num_bigint::{BigUint, ToBigUint};
+use num_traits::{One, Zero};
+
+lazy_static! {
+    pub static ref TWO: BigUint = 2u32.to_biguint().unwrap();
+}
+
+/// Performs the inverse Fast Fourier Transform on a vector of `BigUint`.
+///
+/// # Arguments
+///
+/// * `arr` - A vector of `BigUint` representing the input array.
+/// * `xs` - A vector of `BigUint` representing the evaluation points.
+/// * `p` - The modulus as a `BigUint`.
+///
+/// # Returns
+///
+/// A vector of `BigUint` representing the transformed array.
+pub fn ifft(arr: Vec<BigUint>, xs: Vec<BigUint>, p: &BigUint) -> Vec<BigUint> {
+    // Base case: return immediately if the array length is 1
+    if arr.len() == 1 {
+        return arr;
+    }
+
+    let n = arr.len() / 2;
+    let mut res0 = Vec::with_capacity(n);
+    let mut res1 = Vec::with_capacity(n);
+    let mut new_xs = Vec::with_capacity(n);
+
+    for i in (0..2 * n).step_by(2) {
+        let a = &arr[i];
+        let b = &arr[i + 1];
+        let x = &xs[i];
+
+        res0.push(div_mod(a + b, TWO.clone(), p));
+        // Handle subtraction to avoid underflow
+        let diff = if b > a { p - (b - a) } else { a - b };
+        res1.push(div_mod(diff, TWO.clone() * x, p));
+
+        new_xs.push(x.modpow(&TWO.clone(), p));
+    }
+
+    // Recursive calls
+    let merged_res0 = ifft(res0, new_xs.clone(), p);
+    let merged_res1 = ifft(res1, new_xs, p);
+
+    // Merging the results
+    let mut merged = Vec::with_capacity(arr.len());
+    for i in 0..n {
+        merged.push(merged_res0[i].clone());
+        merged.push(merged_res1[i].clone());
+    }
+    merged
+}
+
+/// Divides two `BigUint` numbers modulo a third `BigUint` number.
+///
+/// # Arguments
+///
+/// * `a` - The numerator as a `BigUint`.
+/// * `b` - The denominator as a `BigUint`.
+/// * `p` - The modulus as a `BigUint`.
+///
+/// # Returns
+///
+/// The result of the division modulo `p` as a `BigUint`.
+pub fn div_mod(a: BigUint, b: BigUint, p: &BigUint) -> BigUint {
+    a * b.modpow(&(p - TWO.clone()), p) % p
+}
+
+/// Performs the forward Fast Fourier Transform on a vector of `BigUint`.
+///
+/// Evaluates the polynomial whose coefficients are `arr` over the powers of `root`,
+/// which must be a primitive `arr.len()`-th root of unity modulo `p`. Both input and
+/// output are in natural order.
+///
+/// # Arguments
+///
+/// * `arr` - A vector of `BigUint` representing the coefficients. Its length must be a power of two.
+/// * `root` - The primitive root of unity generating the evaluation domain.
+/// * `p` - The modulus as a `BigUint`.
+///
+/// # Returns
+///
+/// A vector of `BigUint` representing the evaluations `[f(root^0), f(root^1), ...]`.
+pub fn fft(arr: &[BigUint], root: &BigUint, p: &BigUint) -> Vec<BigUint> {
+    let n = arr.len();
+    assert!(n.is_power_of_two(), "FFT size must be a power of two");
+    let mut values = bit_reversal_permutation(arr);
+
+    let mut len = 2;
+    while len <= n {
+        let step = root.modpow(&BigUint::from(n / len), p);
+        let half = len / 2;
+        let twiddles = powers(&step, half, p);
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                let t = &values[start + k + half] * &twiddles[k] % p;
+                let u = values[start + k].clone();
+                values[start + k] = (&u + &t) % p;
+                values[start + k + half] = (u + p - t) % p;
+            }
+        }
+        len *= 2;
+    }
+    values
+}
+
+/// Performs the inverse of [`fft`].
+///
+/// # Arguments
+///
+/// * `arr` - A vector of `BigUint` representing the evaluations, in natural order.
+/// * `root` - The primitive root of unity generating the evaluation domain.
+/// * `p` - The modulus as a `BigUint`.
+///
+/// # Returns
+///
+/// A vector of `BigUint` representing the coefficients.
+pub fn inverse_fft(arr: &[BigUint], root: &BigUint, p: &BigUint) -> Vec<BigUint> {
+    let n = BigUint::from(arr.len());
+    let n_inv = inv_mod(&n, p);
+    fft(arr, &inv_mod(root, p), p)
+        .into_iter()
+        .map(|value| value * &n_inv % p)
+        .collect()
+}
+
+/// Returns the primitive `n`-th root of unity derived from a primitive root of the field.
+///
+/// # Arguments
+///
+/// * `n` - The size of the domain. Must divide `p - 1`.
+/// * `primitive_root` - A generator of the multiplicative group modulo `p`.
+/// * `p` - The modulus as a `BigUint`.
+pub fn root_of_unity(n: usize, primitive_root: &BigUint, p: &BigUint) -> BigUint {
+    let order = p - 1u32;
+    assert!(
+        (&order % n).is_zero(),
+        "domain size must divide the multiplicative group order"
+    );
+    primitive_root.modpow(&(order / n), p)
+}
+
+/// Returns `[1, base, base^2, ..., base^(n-1)]` modulo `p`.
+pub fn powers(base: &BigUint, n: usize, p: &BigUint) -> Vec<BigUint> {
+    let mut result = Vec::with_capacity(n);
+    let mut current = BigUint::one();
+    for _ in 0..n {
+        result.push(current.clone());
+        current = current * base % p;
+    }
+    result
+}
+
+/// Computes the modular inverse of `a` modulo the prime `p`.
+pub fn inv_mod(a: &BigUint, p: &BigUint) -> BigUint {
+    a.modpow(&(p - TWO.clone()), p)
+}
+
+/// Inverts every element of `values` modulo `p` using a single modular exponentiation.
+///
+/// All elements must be non-zero modulo `p`.
+pub fn batch_inverse(values: &[BigUint], p: &BigUint) -> Vec<BigUint> {
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut acc = BigUint::one();
+    for value in values {
+        prefix.push(acc.clone());
+        acc = acc * value % p;
+    }
+
+    let mut acc_inv = inv_mod(&acc, p);
+    let mut result = vec![BigUint::zero(); values.len()];
+    for i in (0..values.len()).rev() {
+        result[i] = &acc_inv * &prefix[i] % p;
+        acc_inv = acc_inv * &values[i] % p;
+    }
+    result
+}
+
+/// Reverses the lowest `bits` bits of `index`.
+pub fn reverse_bits(index: usize, bits: u32) -> usize {
+    if bits == 0 {
+        return 0;
+    }
+    index.reverse_bits() >> (usize::BITS - bits)
+}
+
+/// Reorders `arr` by bit-reversing the indices. The permutation is its own inverse.
+///
+/// This converts between the natural order of [`fft`] and the bit-reversed order
+/// EIP-4844 uses for blob evaluations.
+pub fn bit_reversal_permutation<T: Clone>(arr: &[T]) -> Vec<T> {
+    let n = arr.len();
+    assert!(
+        n.is_power_of_two(),
+        "bit-reversal permutation size must be a power of two"
+    );
+    let bits = n.trailing_zeros();
+    (0..n).map(|i| arr[reverse_bits(i, bits)].clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLS_MODULUS, GENERATOR, PRIMITIVE_ROOT_OF_UNITY};
+
+    #[test]
+    fn test_root_of_unity_matches_blob_generator() {
+        assert_eq!(
+            root_of_unity(4096, &PRIMITIVE_ROOT_OF_UNITY, &BLS_MODULUS),
+            *GENERATOR
+        );
+    }
+
+    #[test]
+    fn test_fft_round_trip() {
+        let root = root_of_unity(8, &PRIMITIVE_ROOT_OF_UNITY, &BLS_MODULUS);
+        let coefficients: Vec<BigUint> = (1u32..=8).map(BigUint::from).collect();
+        let evaluations = fft(&coefficients, &root, &BLS_MODULUS);
+        // f(1) is the sum of the coefficients.
+        assert_eq!(evaluations[0], BigUint::from(36u32));
+        assert_eq!(inverse_fft(&evaluations, &root, &BLS_MODULUS), coefficients);
+    }
+
+    #[test]
+    fn test_fft_matches_ifft() {
+        let coefficients: Vec<BigUint> = (0u32..16).map(|i| BigUint::from(i * i + 3)).collect();
+        let root = root_of_unity(16, &PRIMITIVE_ROOT_OF_UNITY, &BLS_MODULUS);
+        let evaluations = bit_reversal_permutation(&fft(&coefficients, &root, &BLS_MODULUS));
+        let xs = bit_reversal_permutation(&powers(&root, 16, &BLS_MODULUS));
+        assert_eq!(ifft(evaluations, xs, &BLS_MODULUS), coefficients);
+    }
+
+    #[test]
+    fn test_batch_inverse() {
+        let values: Vec<BigUint> = (1u32..10).map(BigUint::from).collect();
+        let inverses = batch_inverse(&values, &BLS_MODULUS);
+        for (value, inverse) in values.iter().zip(inverses) {
+            assert_eq!(value * inverse % &*BLS_MODULUS, BigUint::one());
+        }
+    }
+
+    #[test]
+    fn test_bit_reversal_permutation() {
+        assert_eq!(reverse_bits(1, 12), 2048);
+        assert_eq!(
+            bit_reversal_permutation(&[0, 1, 2, 3, 4, 5, 6, 7]),
+            vec![0, 4, 2, 6, 1, 5, 3, 7]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use num_bigint::BigUint;
+use thiserror::Error;
+
+use crate::blob::{field_element_to_bytes, kzg_to_versioned_hash, BlobError};
+use crate::kzg::{
+    blob_to_commitment, compute_proof, verify_proof, KzgCommitment, KzgError, KzgProof,
+};
+use crate::{BLOB_LEN, BLS_MODULUS};
+
+/// Address of the point evaluation precompile.
+pub const POINT_EVALUATION_PRECOMPILE_ADDRESS: u8 = 0x0a;
+/// Size in bytes of the input of the point evaluation precompile.
+pub const POINT_EVALUATION_INPUT_LEN: usize = 192;
+/// Size in bytes of the output of the point evaluation precompile.
+pub const POINT_EVALUATION_OUTPUT_LEN: usize = 64;
+/// Gas charged by the point evaluation precompile.
+pub const POINT_EVALUATION_PRECOMPILE_GAS: u64 = 50_000;
+
+#[derive(Debug, Error)]
+pub enum PrecompileError {
+    #[error("expected {POINT_EVALUATION_INPUT_LEN} bytes of input, got {0}")]
+    InvalidInputLength(usize),
+    #[error("versioned hash does not match the commitment")]
+    VersionedHashMismatch,
+    #[error("KZG proof does not hold")]
+    InvalidProof,
+    #[error(transparent)]
+    Blob(#[from] BlobError),
+    #[error(transparent)]
+    Kzg(#[from] KzgError),
+}
+
+/// Input of the point evaluation precompile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointEvaluationInput {
+    pub versioned_hash: [u8; 32],
+    pub z: [u8; 32],
+    pub y: [u8; 32],
+    pub commitment: KzgCommitment,
+    pub proof: KzgProof,
+}
+
+impl PointEvaluationInput {
+    /// Builds the precompile input proving the evaluation of a blob at `z`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The blob field elements, as returned by `parse_str_to_blob_data`.
+    /// * `z` - The evaluation point. Fails if it is not lower than the BLS modulus.
+    pub fn from_blob(data: &[BigUint], z: &BigUint) -> Result<Self, PrecompileError> {
+        let commitment = blob_to_commitment(data)?;
+        let (proof, y) = compute_proof(data, z)?;
+        Ok(PointEvaluationInput {
+            versioned_hash: kzg_to_versioned_hash(&commitment),
+            z: field_element_to_bytes(z)?,
+            y: field_element_to_bytes(&y)?,
+            commitment,
+            proof,
+        })
+    }
+
+    /// Decodes the 192 bytes of a precompile input.
+    pub fn from_bytes(input: &[u8]) -> Result<Self, PrecompileError> {
+        if input.len() != POINT_EVALUATION_INPUT_LEN {
+            return Err(PrecompileError::InvalidInputLength(input.len()));
+        }
+        Ok(PointEvaluationInput {
+            versioned_hash: input[0..32].try_into().unwrap(),
+            z: input[32..64].try_into().unwrap(),
+            y: input[64..96].try_into().unwrap(),
+            commitment: input[96..144].try_into().unwrap(),
+            proof: input[144..192].try_into().unwrap(),
+        })
+    }
+
+    /// Encodes the input as the 192 bytes passed to the precompile.
+    pub fn to_bytes(&self) -> [u8; POINT_EVALUATION_INPUT_LEN] {
+        let mut input = [0u8; POINT_EVALUATION_INPUT_LEN];
+        input[0..32].copy_from_slice(&self.versioned_hash);
+        input[32..64].copy_from_slice(&self.z);
+        input[64..96].copy_from_slice(&self.y);
+        input[96..144].copy_from_slice(&self.commitment);
+        input[144..192].copy_from_slice(&self.proof);
+        input
+    }
+
+    /// Runs the checks of the precompile on this input, see [`point_evaluation`].
+    pub fn verify(&self) -> Result<[u8; POINT_EVALUATION_OUTPUT_LEN], PrecompileError> {
+        if kzg_to_versioned_hash(&self.commitment) != self.versioned_hash {
+            return Err(PrecompileError::VersionedHashMismatch);
+        }
+        if !verify_proof(&self.commitment, &self.z, &self.y, &self.proof)? {
+            return Err(PrecompileError::InvalidProof);
+        }
+        Ok(point_evaluation_output())
+    }
+}
+
+/// Executes the point evaluation precompile, as specified in EIP-4844.
+///
+/// # Arguments
+///
+/// * `input` - The 192 bytes of input: versioned hash, z, y, commitment and proof.
+///
+/// # Returns
+///
+/// The 64 bytes the precompile returns on success, `FIELD_ELEMENTS_PER_BLOB` followed by
+/// `BLS_MODULUS`, or the reason the EVM call would revert.
+pub fn point_evaluation(
+    input: &[u8],
+) -> Result<[u8; POINT_EVALUATION_OUTPUT_LEN], PrecompileError> {
+    PointEvaluationInput::from_bytes(input)?.verify()
+}
+
+/// Returns the output of a successful point evaluation precompile call.
+pub fn point_evaluation_output() -> [u8; POINT_EVALUATION_OUTPUT_LEN] {
+    // Both are 256-bit words, the modulus is no field element
+    let mut output = [0u8; POINT_EVALUATION_OUTPUT_LEN];
+    let modulus = BLS_MODULUS.to_bytes_be();
+    output[32 - 2..32].copy_from_slice(&(BLOB_LEN as u16).to_be_bytes());
+    output[64 - modulus.len()..64].copy_from_slice(&modulus);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::example_blob;
+
+    #[test]
+    fn test_point_evaluation_round_trip() {
+        let input =
+            PointEvaluationInput::from_blob(&example_blob(), &BigUint::from(42u32)).unwrap();
+        let bytes = input.to_bytes();
+        assert_eq!(PointEvaluationInput::from_bytes(&bytes).unwrap(), input);
+
+        let output = point_evaluation(&bytes).unwrap();
+        assert_eq!(output[30..32], [0x10, 0x00]);
+        assert_eq!(BigUint::from_bytes_be(&output[32..]), *BLS_MODULUS);
+    }
+
+    #[test]
+    fn test_point_evaluation_rejects_bad_input() {
+        let input =
+            PointEvaluationInput::from_blob(&example_blob(), &BigUint::from(42u32)).unwrap();
+
+        assert!(matches!(
+            point_evaluation(&input.to_bytes()[..191]),
+            Err(PrecompileError::InvalidInputLength(191))
+        ));
+
+        let mut wrong_hash = input.clone();
+        wrong_hash.versioned_hash[31] ^= 1;
+        assert!(matches!(
+            wrong_hash.verify(),
+            Err(PrecompileError::VersionedHashMismatch)
+        ));
+
+        let mut wrong_y = input;
+        wrong_y.y[31] ^= 1;
+        assert!(matches!(
+            wrong_y.verify(),
+            Err(PrecompileError::InvalidProof)
+        ));
+
+        assert!(matches!(
+            PointEvaluationInput::from_blob(&example_blob(), &BLS_MODULUS),
+            Err(PrecompileError::Kzg(KzgError::Blob(
+                BlobError::InvalidFieldElement(_)
+            )))
+        ));
+    }
+}
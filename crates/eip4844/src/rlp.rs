@@ -0,0 +1,290 @@
+use num_bigint::BigUint;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RlpError {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    #[error("{0} trailing bytes after the RLP item")]
+    TrailingBytes(usize),
+    #[error("non-canonical RLP encoding")]
+    NonCanonical,
+    #[error("expected a byte string, got a list")]
+    ExpectedBytes,
+    #[error("expected a list, got a byte string")]
+    ExpectedList,
+    #[error("expected a list of {expected} items, got {actual}")]
+    InvalidListLength { expected: usize, actual: usize },
+    #[error("expected {expected} bytes, got {actual}")]
+    InvalidBytesLength { expected: usize, actual: usize },
+    #[error("integer does not fit in {0} bytes")]
+    IntegerOverflow(usize),
+    #[error("lists are nested more than {MAX_DEPTH} levels deep")]
+    TooDeep,
+}
+
+/// The deepest nesting of lists decoded. Blob transactions nest lists a few levels deep.
+pub const MAX_DEPTH: usize = 16;
+
+/// A decoded RLP item, borrowing from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RlpItem<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<RlpItem<'a>>),
+}
+
+impl<'a> RlpItem<'a> {
+    /// Returns the content of a byte string item.
+    pub fn as_bytes(&self) -> Result<&'a [u8], RlpError> {
+        match self {
+            RlpItem::Bytes(bytes) => Ok(bytes),
+            RlpItem::List(_) => Err(RlpError::ExpectedBytes),
+        }
+    }
+
+    /// Returns the items of a list item.
+    pub fn as_list(&self) -> Result<&[RlpItem<'a>], RlpError> {
+        match self {
+            RlpItem::List(items) => Ok(items),
+            RlpItem::Bytes(_) => Err(RlpError::ExpectedList),
+        }
+    }
+
+    /// Returns the items of a list item, checking how many there are.
+    pub fn as_list_of(&self, len: usize) -> Result<&[RlpItem<'a>], RlpError> {
+        let items = self.as_list()?;
+        if items.len() != len {
+            return Err(RlpError::InvalidListLength {
+                expected: len,
+                actual: items.len(),
+            });
+        }
+        Ok(items)
+    }
+
+    /// Returns the content of a byte string item of exactly `N` bytes.
+    pub fn as_array<const N: usize>(&self) -> Result<[u8; N], RlpError> {
+        let bytes = self.as_bytes()?;
+        bytes.try_into().map_err(|_| RlpError::InvalidBytesLength {
+            expected: N,
+            actual: bytes.len(),
+        })
+    }
+
+    /// Decodes an unsigned integer item.
+    pub fn as_biguint(&self) -> Result<BigUint, RlpError> {
+        let bytes = self.as_bytes()?;
+        if bytes.first() == Some(&0) {
+            return Err(RlpError::NonCanonical);
+        }
+        Ok(BigUint::from_bytes_be(bytes))
+    }
+
+    /// Decodes an unsigned integer item that fits in a `u64`.
+    pub fn as_u64(&self) -> Result<u64, RlpError> {
+        Ok(self.as_uint(8)? as u64)
+    }
+
+    /// Decodes an unsigned integer item that fits in a `u128`.
+    pub fn as_u128(&self) -> Result<u128, RlpError> {
+        self.as_uint(16)
+    }
+
+    fn as_uint(&self, max_len: usize) -> Result<u128, RlpError> {
+        let bytes = self.as_bytes()?;
+        if bytes.first() == Some(&0) {
+            return Err(RlpError::NonCanonical);
+        }
+        if bytes.len() > max_len {
+            return Err(RlpError::IntegerOverflow(max_len));
+        }
+        Ok(bytes.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128))
+    }
+}
+
+/// Decodes a single RLP item spanning the whole input.
+pub fn decode(input: &[u8]) -> Result<RlpItem<'_>, RlpError> {
+    let (item, rest) = decode_item(input, 0)?;
+    if !rest.is_empty() {
+        return Err(RlpError::TrailingBytes(rest.len()));
+    }
+    Ok(item)
+}
+
+/// Decodes the first RLP item of the input, inside `depth` lists, and returns it with the
+/// remaining bytes.
+fn decode_item(input: &[u8], depth: usize) -> Result<(RlpItem<'_>, &[u8]), RlpError> {
+    let prefix = *input.first().ok_or(RlpError::UnexpectedEnd)?;
+    match prefix {
+        0x00..=0x7f => Ok((RlpItem::Bytes(&input[..1]), &input[1..])),
+        0x80..=0xb7 => {
+            let len = (prefix - 0x80) as usize;
+            let (payload, rest) = split(&input[1..], len)?;
+            if len == 1 && payload[0] < 0x80 {
+                return Err(RlpError::NonCanonical);
+            }
+            Ok((RlpItem::Bytes(payload), rest))
+        }
+        0xb8..=0xbf => {
+            let (len, input) = decode_length(&input[1..], (prefix - 0xb7) as usize)?;
+            let (payload, rest) = split(input, len)?;
+            Ok((RlpItem::Bytes(payload), rest))
+        }
+        0xc0..=0xf7 => {
+            let (payload, rest) = split(&input[1..], (prefix - 0xc0) as usize)?;
+            Ok((RlpItem::List(decode_list(payload, depth + 1)?), rest))
+        }
+        0xf8..=0xff => {
+            let (len, input) = decode_length(&input[1..], (prefix - 0xf7) as usize)?;
+            let (payload, rest) = split(input, len)?;
+            Ok((RlpItem::List(decode_list(payload, depth + 1)?), rest))
+        }
+    }
+}
+
+fn decode_list(mut payload: &[u8], depth: usize) -> Result<Vec<RlpItem<'_>>, RlpError> {
+    if depth > MAX_DEPTH {
+        return Err(RlpError::TooDeep);
+    }
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let (item, rest) = decode_item(payload, depth)?;
+        items.push(item);
+        payload = rest;
+    }
+    Ok(items)
+}
+
+/// Decodes the big-endian length of a long string or list.
+fn decode_length(input: &[u8], len_of_len: usize) -> Result<(usize, &[u8]), RlpError> {
+    let (len_bytes, rest) = split(input, len_of_len)?;
+    if len_bytes[0] == 0 || len_of_len > std::mem::size_of::<usize>() {
+        return Err(RlpError::NonCanonical);
+    }
+    let len = len_bytes
+        .iter()
+        .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    if len < 56 {
+        return Err(RlpError::NonCanonical);
+    }
+    Ok((len, rest))
+}
+
+fn split(input: &[u8], len: usize) -> Result<(&[u8], &[u8]), RlpError> {
+    if input.len() < len {
+        return Err(RlpError::UnexpectedEnd);
+    }
+    Ok(input.split_at(len))
+}
+
+/// Encodes a byte string.
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = encode_header(0x80, bytes.len());
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Encodes a list from the encodings of its items.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_len = items.iter().map(Vec::len).sum();
+    let mut out = encode_header(0xc0, payload_len);
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+/// Encodes an unsigned integer.
+pub fn encode_uint(value: u128) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    encode_bytes(&bytes[start..])
+}
+
+/// Encodes an unsigned integer of arbitrary size.
+pub fn encode_biguint(value: &BigUint) -> Vec<u8> {
+    if value.bits() == 0 {
+        return encode_bytes(&[]);
+    }
+    encode_bytes(&value.to_bytes_be())
+}
+
+fn encode_header(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let len_bytes = len.to_be_bytes();
+    let start = len_bytes.iter().position(|b| *b != 0).unwrap();
+    let mut out = vec![offset + 55 + (len_bytes.len() - start) as u8];
+    out.extend_from_slice(&len_bytes[start..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let long = vec![0xaa; 1024];
+        let encoded = encode_list(&[
+            encode_uint(0),
+            encode_uint(0x7f),
+            encode_uint(1024),
+            encode_bytes(b"dog"),
+            encode_bytes(&long),
+            encode_list(&[]),
+        ]);
+        let item = decode(&encoded).unwrap();
+        let items = item.as_list_of(6).unwrap();
+        assert_eq!(items[0].as_u64(), Ok(0));
+        assert_eq!(items[1].as_u64(), Ok(0x7f));
+        assert_eq!(items[2].as_u64(), Ok(1024));
+        assert_eq!(items[3].as_bytes(), Ok(&b"dog"[..]));
+        assert_eq!(items[4].as_bytes(), Ok(&long[..]));
+        assert_eq!(items[5].as_list().map(|l| l.len()), Ok(0));
+    }
+
+    #[test]
+    fn test_known_encodings() {
+        assert_eq!(encode_bytes(b"dog"), vec![0x83, b'd', b'o', b'g']);
+        assert_eq!(encode_uint(1024), vec![0x82, 0x04, 0x00]);
+        assert_eq!(
+            encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")]),
+            vec![0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g']
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_input() {
+        assert_eq!(decode(&[0x83, b'd', b'o']), Err(RlpError::UnexpectedEnd));
+        assert_eq!(decode(&[0x81, 0x05]), Err(RlpError::NonCanonical));
+        assert_eq!(decode(&[0x05, 0x05]), Err(RlpError::TrailingBytes(1)));
+        assert_eq!(
+            decode(&[0x82, 0x00, 0x01]).unwrap().as_u64(),
+            Err(RlpError::NonCanonical)
+        );
+    }
+
+    /// Nests empty lists `depth` levels deep.
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        // Built from the innermost list out, backwards
+        let mut reversed = Vec::new();
+        for _ in 0..depth {
+            let header = encode_header(0xc0, reversed.len());
+            reversed.extend(header.iter().rev());
+        }
+        reversed.reverse();
+        reversed
+    }
+
+    #[test]
+    fn test_decode_rejects_deep_nesting() {
+        assert!(decode(&nested_lists(MAX_DEPTH)).is_ok());
+        assert_eq!(decode(&nested_lists(MAX_DEPTH + 1)), Err(RlpError::TooDeep));
+        assert_eq!(decode(&nested_lists(1_000_000)), Err(RlpError::TooDeep));
+    }
+}
@@ -0,0 +1,13 @@
+use num_bigint::BigUint;
+use num_traits::Num;
+
+use crate::BLOB_LEN;
+
+/// The field elements of `examples/blob/mainnet.txt`.
+pub fn example_blob() -> Vec<BigUint> {
+    let blob_hex = std::fs::read_to_string("../../examples/blob/mainnet.txt").unwrap();
+    let blob_hex = blob_hex.trim();
+    (0..BLOB_LEN)
+        .map(|i| BigUint::from_str_radix(&blob_hex[i * 64..(i + 1) * 64], 16).unwrap())
+        .collect()
+}
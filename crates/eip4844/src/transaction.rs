@@ -0,0 +1,689 @@
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use crate::blob::{blob_from_bytes, blob_to_bytes, kzg_to_versioned_hash, BlobError};
+use crate::rlp::{self, RlpError, RlpItem};
+use crate::{BYTES_PER_COMMITMENT, BYTES_PER_PROOF};
+
+/// EIP-2718 type of blob transactions.
+pub const BLOB_TX_TYPE: u8 = 0x03;
+/// Version of network wrappers carrying one KZG proof per blob (EIP-4844).
+pub const WRAPPER_VERSION_BLOB_PROOFS: u8 = 0;
+/// Version of network wrappers carrying one KZG proof per cell (EIP-7594).
+pub const WRAPPER_VERSION_CELL_PROOFS: u8 = 1;
+/// Number of fields of a blob transaction before its signature.
+const UNSIGNED_FIELDS: usize = 11;
+
+#[derive(Debug, Error)]
+pub enum TransactionError {
+    #[error("empty transaction")]
+    Empty,
+    #[error("expected a blob transaction (type 0x03), got type {0:#04x}")]
+    NotBlobTransaction(u8),
+    #[error("invalid RLP: {0}")]
+    Rlp(#[from] RlpError),
+    #[error("unsupported network wrapper version {0}")]
+    UnsupportedWrapperVersion(u64),
+    #[error("invalid y parity {0}")]
+    InvalidYParity(u64),
+    #[error(transparent)]
+    Blob(#[from] BlobError),
+    #[error("sidecar has {blobs} blobs, {commitments} commitments and {proofs} proofs")]
+    SidecarLengthMismatch {
+        blobs: usize,
+        commitments: usize,
+        proofs: usize,
+    },
+    #[error("transaction references {expected} blobs, the sidecar has {actual}")]
+    VersionedHashCount { expected: usize, actual: usize },
+    #[error("commitment {0} does not match its versioned hash")]
+    VersionedHashMismatch(usize),
+    #[error("KZG proof of blob {0} does not hold")]
+    InvalidProof(usize),
+    #[error("invalid secp256k1 secret key")]
+    InvalidSecretKey,
+    #[error("missing or invalid signature")]
+    InvalidSignature,
+    #[cfg(feature = "kzg")]
+    #[error(transparent)]
+    Kzg(#[from] crate::kzg::KzgError),
+}
+
+/// An entry of an EIP-2930 access list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListItem {
+    pub address: [u8; 20],
+    pub storage_keys: Vec<[u8; 32]>,
+}
+
+/// The signature of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub y_parity: bool,
+    pub r: BigUint,
+    pub s: BigUint,
+}
+
+/// An EIP-4844 blob transaction, as included in execution blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub gas_limit: u64,
+    pub to: [u8; 20],
+    pub value: BigUint,
+    pub data: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: u128,
+    pub blob_versioned_hashes: Vec<[u8; 32]>,
+    /// `None` for a transaction that has not been signed yet.
+    pub signature: Option<Signature>,
+}
+
+/// The blobs, commitments and proofs that accompany a blob transaction on the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobTransactionSidecar {
+    /// `WRAPPER_VERSION_BLOB_PROOFS` or `WRAPPER_VERSION_CELL_PROOFS`.
+    pub version: u8,
+    /// The blobs, each in the same form as `parse_str_to_blob_data` returns.
+    pub blobs: Vec<Vec<BigUint>>,
+    pub commitments: Vec<[u8; BYTES_PER_COMMITMENT]>,
+    /// One proof per blob for version 0, one proof per cell for version 1.
+    pub proofs: Vec<[u8; BYTES_PER_PROOF]>,
+}
+
+/// A blob transaction in network form: the transaction and its sidecar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobTransactionNetworkWrapper {
+    pub transaction: BlobTransaction,
+    pub sidecar: BlobTransactionSidecar,
+}
+
+impl BlobTransaction {
+    /// Decodes a blob transaction, `0x03 || rlp([chain_id, ..., y_parity, r, s])`.
+    ///
+    /// Unsigned transactions, as [`BlobTransaction::encode`] writes them, stop before
+    /// `y_parity` and are decoded without a signature.
+    pub fn decode(raw: &[u8]) -> Result<Self, TransactionError> {
+        let payload = strip_type(raw)?;
+        BlobTransaction::decode_fields(&rlp::decode(payload)?)
+    }
+
+    fn decode_fields(item: &RlpItem) -> Result<Self, TransactionError> {
+        let fields = item.as_list()?;
+        if fields.len() != UNSIGNED_FIELDS && fields.len() != UNSIGNED_FIELDS + 3 {
+            return Err(RlpError::InvalidListLength {
+                expected: UNSIGNED_FIELDS + 3,
+                actual: fields.len(),
+            }
+            .into());
+        }
+        let access_list = fields[8]
+            .as_list()?
+            .iter()
+            .map(|entry| {
+                let entry = entry.as_list_of(2)?;
+                Ok(AccessListItem {
+                    address: entry[0].as_array()?,
+                    storage_keys: entry[1]
+                        .as_list()?
+                        .iter()
+                        .map(|key| key.as_array())
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_, RlpError>>()?;
+        let signature = match &fields[UNSIGNED_FIELDS..] {
+            [y_parity, r, s] => Some(Signature {
+                y_parity: match y_parity.as_u64()? {
+                    0 => false,
+                    1 => true,
+                    other => return Err(TransactionError::InvalidYParity(other)),
+                },
+                r: r.as_biguint()?,
+                s: s.as_biguint()?,
+            }),
+            _ => None,
+        };
+
+        Ok(BlobTransaction {
+            chain_id: fields[0].as_u64()?,
+            nonce: fields[1].as_u64()?,
+            max_priority_fee_per_gas: fields[2].as_u128()?,
+            max_fee_per_gas: fields[3].as_u128()?,
+            gas_limit: fields[4].as_u64()?,
+            to: fields[5].as_array()?,
+            value: fields[6].as_biguint()?,
+            data: fields[7].as_bytes()?.to_vec(),
+            access_list,
+            max_fee_per_blob_gas: fields[9].as_u128()?,
+            blob_versioned_hashes: fields[10]
+                .as_list()?
+                .iter()
+                .map(|hash| hash.as_array())
+                .collect::<Result<_, _>>()?,
+            signature,
+        })
+    }
+
+    /// Returns the RLP encodings of the fields covered by the signature.
+    fn unsigned_fields(&self) -> Vec<Vec<u8>> {
+        let access_list: Vec<Vec<u8>> = self
+            .access_list
+            .iter()
+            .map(|entry| {
+                let keys: Vec<Vec<u8>> = entry
+                    .storage_keys
+                    .iter()
+                    .map(|key| rlp::encode_bytes(key))
+                    .collect();
+                rlp::encode_list(&[rlp::encode_bytes(&entry.address), rlp::encode_list(&keys)])
+            })
+            .collect();
+        let hashes: Vec<Vec<u8>> = self
+            .blob_versioned_hashes
+            .iter()
+            .map(|hash| rlp::encode_bytes(hash))
+            .collect();
+        vec![
+            rlp::encode_uint(self.chain_id as u128),
+            rlp::encode_uint(self.nonce as u128),
+            rlp::encode_uint(self.max_priority_fee_per_gas),
+            rlp::encode_uint(self.max_fee_per_gas),
+            rlp::encode_uint(self.gas_limit as u128),
+            rlp::encode_bytes(&self.to),
+            rlp::encode_biguint(&self.value),
+            rlp::encode_bytes(&self.data),
+            rlp::encode_list(&access_list),
+            rlp::encode_uint(self.max_fee_per_blob_gas),
+            rlp::encode_list(&hashes),
+        ]
+    }
+
+    /// Returns the RLP list of the transaction, with its signature if it is signed.
+    fn encode_body(&self) -> Vec<u8> {
+        let mut fields = self.unsigned_fields();
+        if let Some(signature) = &self.signature {
+            fields.push(rlp::encode_uint(signature.y_parity as u128));
+            fields.push(rlp::encode_biguint(&signature.r));
+            fields.push(rlp::encode_biguint(&signature.s));
+        }
+        rlp::encode_list(&fields)
+    }
+
+    /// Encodes the transaction as included in blocks, `0x03 || rlp(fields)`.
+    pub fn encode(&self) -> Vec<u8> {
+        with_type(self.encode_body())
+    }
+
+    /// Returns the hash the sender signs, `keccak256(0x03 || rlp(unsigned fields))`.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak256(&with_type(rlp::encode_list(&self.unsigned_fields())))
+    }
+
+    /// Returns the transaction hash, `keccak256(0x03 || rlp(fields))`.
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(&self.encode())
+    }
+}
+
+impl BlobTransactionSidecar {
+    /// Returns the versioned hashes of the commitments.
+    pub fn versioned_hashes(&self) -> Vec<[u8; 32]> {
+        self.commitments.iter().map(kzg_to_versioned_hash).collect()
+    }
+
+    /// Checks that the commitments match the versioned hashes of a transaction.
+    pub fn verify_versioned_hashes(&self, hashes: &[[u8; 32]]) -> Result<(), TransactionError> {
+        if hashes.len() != self.commitments.len() {
+            return Err(TransactionError::VersionedHashCount {
+                expected: hashes.len(),
+                actual: self.commitments.len(),
+            });
+        }
+        match self
+            .versioned_hashes()
+            .iter()
+            .zip(hashes)
+            .position(|(actual, expected)| actual != expected)
+        {
+            Some(index) => Err(TransactionError::VersionedHashMismatch(index)),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that the proofs bind every blob to its commitment.
+    #[cfg(feature = "kzg")]
+    pub fn verify_kzg_proofs(&self) -> Result<(), TransactionError> {
+        use crate::cells::CELLS_PER_EXT_BLOB;
+        use crate::kzg::{verify_blob_proof, verify_cell_proofs};
+
+        for (index, (blob, commitment)) in self.blobs.iter().zip(&self.commitments).enumerate() {
+            let valid = match self.version {
+                WRAPPER_VERSION_BLOB_PROOFS => {
+                    verify_blob_proof(blob, commitment, &self.proofs[index])?
+                }
+                _ => {
+                    let proofs = &self.proofs[index * CELLS_PER_EXT_BLOB..][..CELLS_PER_EXT_BLOB];
+                    verify_cell_proofs(blob, commitment, proofs)?
+                }
+            };
+            if !valid {
+                return Err(TransactionError::InvalidProof(index));
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_fields(
+        version: u8,
+        blobs: &RlpItem,
+        commitments: &RlpItem,
+        proofs: &RlpItem,
+    ) -> Result<Self, TransactionError> {
+        let sidecar = BlobTransactionSidecar {
+            version,
+            blobs: blobs
+                .as_list()?
+                .iter()
+                .map(|blob| Ok(blob_from_bytes(blob.as_bytes()?)?))
+                .collect::<Result<_, TransactionError>>()?,
+            commitments: commitments
+                .as_list()?
+                .iter()
+                .map(|commitment| commitment.as_array())
+                .collect::<Result<_, _>>()?,
+            proofs: proofs
+                .as_list()?
+                .iter()
+                .map(|proof| proof.as_array())
+                .collect::<Result<_, _>>()?,
+        };
+        let proofs_per_blob = match version {
+            WRAPPER_VERSION_BLOB_PROOFS => 1,
+            _ => crate::cells::CELLS_PER_EXT_BLOB,
+        };
+        if sidecar.commitments.len() != sidecar.blobs.len()
+            || sidecar.proofs.len() != sidecar.blobs.len() * proofs_per_blob
+        {
+            return Err(TransactionError::SidecarLengthMismatch {
+                blobs: sidecar.blobs.len(),
+                commitments: sidecar.commitments.len(),
+                proofs: sidecar.proofs.len(),
+            });
+        }
+        Ok(sidecar)
+    }
+}
+
+impl BlobTransactionNetworkWrapper {
+    /// Decodes a blob transaction in network form.
+    ///
+    /// Both the EIP-4844 form, `0x03 || rlp([tx, blobs, commitments, proofs])`, and the
+    /// EIP-7594 form, `0x03 || rlp([tx, 1, blobs, commitments, cell_proofs])`, are accepted.
+    pub fn decode(raw: &[u8]) -> Result<Self, TransactionError> {
+        let payload = strip_type(raw)?;
+        let item = rlp::decode(payload)?;
+        let fields = item.as_list()?;
+        let sidecar = match fields.len() {
+            4 => BlobTransactionSidecar::decode_fields(
+                WRAPPER_VERSION_BLOB_PROOFS,
+                &fields[1],
+                &fields[2],
+                &fields[3],
+            )?,
+            5 => match fields[1].as_u64()? {
+                version if version == WRAPPER_VERSION_CELL_PROOFS as u64 => {
+                    BlobTransactionSidecar::decode_fields(
+                        WRAPPER_VERSION_CELL_PROOFS,
+                        &fields[2],
+                        &fields[3],
+                        &fields[4],
+                    )?
+                }
+                version => return Err(TransactionError::UnsupportedWrapperVersion(version)),
+            },
+            actual => {
+                return Err(RlpError::InvalidListLength {
+                    expected: 4,
+                    actual,
+                }
+                .into())
+            }
+        };
+        Ok(BlobTransactionNetworkWrapper {
+            transaction: BlobTransaction::decode_fields(&fields[0])?,
+            sidecar,
+        })
+    }
+
+    /// Encodes the transaction in network form.
+    pub fn encode(&self) -> Result<Vec<u8>, TransactionError> {
+        let blobs: Vec<Vec<u8>> = self
+            .sidecar
+            .blobs
+            .iter()
+            .map(|blob| Ok(rlp::encode_bytes(&blob_to_bytes(blob)?)))
+            .collect::<Result<_, BlobError>>()?;
+        let commitments: Vec<Vec<u8>> = self
+            .sidecar
+            .commitments
+            .iter()
+            .map(|commitment| rlp::encode_bytes(commitment))
+            .collect();
+        let proofs: Vec<Vec<u8>> = self
+            .sidecar
+            .proofs
+            .iter()
+            .map(|proof| rlp::encode_bytes(proof))
+            .collect();
+
+        let mut fields = vec![self.transaction.encode_body()];
+        if self.sidecar.version != WRAPPER_VERSION_BLOB_PROOFS {
+            fields.push(rlp::encode_uint(self.sidecar.version as u128));
+        }
+        fields.push(rlp::encode_list(&blobs));
+        fields.push(rlp::encode_list(&commitments));
+        fields.push(rlp::encode_list(&proofs));
+        Ok(with_type(rlp::encode_list(&fields)))
+    }
+
+    /// Checks the sidecar against the versioned hashes of the transaction.
+    pub fn verify_versioned_hashes(&self) -> Result<(), TransactionError> {
+        self.sidecar
+            .verify_versioned_hashes(&self.transaction.blob_versioned_hashes)
+    }
+}
+
+#[cfg(feature = "kzg")]
+impl BlobTransactionSidecar {
+    /// Builds the sidecar of a list of blobs, computing their commitments and blob proofs.
+    ///
+    /// # Arguments
+    ///
+    /// * `blobs` - The blob field elements of each blob.
+    pub fn from_blobs(blobs: Vec<Vec<BigUint>>) -> Result<Self, TransactionError> {
+        use crate::kzg::{blob_to_commitment, compute_blob_proof};
+
+        let mut commitments = Vec::with_capacity(blobs.len());
+        let mut proofs = Vec::with_capacity(blobs.len());
+        for blob in &blobs {
+            let commitment = blob_to_commitment(blob)?;
+            proofs.push(compute_blob_proof(blob, &commitment)?);
+            commitments.push(commitment);
+        }
+        Ok(BlobTransactionSidecar {
+            version: WRAPPER_VERSION_BLOB_PROOFS,
+            blobs,
+            commitments,
+            proofs,
+        })
+    }
+}
+
+#[cfg(feature = "signer")]
+impl BlobTransaction {
+    /// Signs the transaction with a secp256k1 secret key, replacing any previous signature.
+    pub fn sign(&mut self, secret_key: &[u8; 32]) -> Result<(), TransactionError> {
+        let key = k256::ecdsa::SigningKey::from_slice(secret_key)
+            .map_err(|_| TransactionError::InvalidSecretKey)?;
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(&self.signing_hash())
+            .map_err(|_| TransactionError::InvalidSignature)?;
+        let (r, s) = signature.split_bytes();
+        self.signature = Some(Signature {
+            y_parity: recovery_id.is_y_odd(),
+            r: BigUint::from_bytes_be(&r),
+            s: BigUint::from_bytes_be(&s),
+        });
+        Ok(())
+    }
+
+    /// Recovers the address of the sender from the signature.
+    pub fn sender(&self) -> Result<[u8; 20], TransactionError> {
+        use k256::ecdsa::{RecoveryId, VerifyingKey};
+
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or(TransactionError::InvalidSignature)?;
+        let mut bytes = [0u8; 64];
+        for (value, out) in [&signature.r, &signature.s]
+            .into_iter()
+            .zip(bytes.chunks_mut(32))
+        {
+            let value = value.to_bytes_be();
+            if value.len() > 32 {
+                return Err(TransactionError::InvalidSignature);
+            }
+            out[32 - value.len()..].copy_from_slice(&value);
+        }
+        let ecdsa_signature = k256::ecdsa::Signature::from_slice(&bytes)
+            .map_err(|_| TransactionError::InvalidSignature)?;
+        let key = VerifyingKey::recover_from_prehash(
+            &self.signing_hash(),
+            &ecdsa_signature,
+            RecoveryId::new(signature.y_parity, false),
+        )
+        .map_err(|_| TransactionError::InvalidSignature)?;
+        Ok(address_of(&key))
+    }
+}
+
+/// Returns the Ethereum address of a secp256k1 secret key.
+#[cfg(feature = "signer")]
+pub fn secret_key_to_address(secret_key: &[u8; 32]) -> Result<[u8; 20], TransactionError> {
+    let key = k256::ecdsa::SigningKey::from_slice(secret_key)
+        .map_err(|_| TransactionError::InvalidSecretKey)?;
+    Ok(address_of(key.verifying_key()))
+}
+
+#[cfg(feature = "signer")]
+fn address_of(key: &k256::ecdsa::VerifyingKey) -> [u8; 20] {
+    let point = key.to_encoded_point(false);
+    keccak256(&point.as_bytes()[1..])[12..].try_into().unwrap()
+}
+
+/// Decodes a blob transaction given either in network form or as included in blocks.
+///
+/// # Returns
+///
+/// The transaction, and its sidecar when the input is in network form.
+pub fn decode_blob_transaction(
+    raw: &[u8],
+) -> Result<(BlobTransaction, Option<BlobTransactionSidecar>), TransactionError> {
+    let payload = strip_type(raw)?;
+    let item = rlp::decode(payload)?;
+    match item.as_list()?.first() {
+        Some(RlpItem::List(_)) => {
+            let wrapper = BlobTransactionNetworkWrapper::decode(raw)?;
+            Ok((wrapper.transaction, Some(wrapper.sidecar)))
+        }
+        _ => Ok((BlobTransaction::decode_fields(&item)?, None)),
+    }
+}
+
+/// Computes the Keccak-256 hash of the input.
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    Keccak256::digest(input).into()
+}
+
+fn strip_type(raw: &[u8]) -> Result<&[u8], TransactionError> {
+    match raw.split_first() {
+        None => Err(TransactionError::Empty),
+        Some((&BLOB_TX_TYPE, payload)) => Ok(payload),
+        Some((tx_type, _)) => Err(TransactionError::NotBlobTransaction(*tx_type)),
+    }
+}
+
+fn with_type(payload: Vec<u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 1);
+    out.push(BLOB_TX_TYPE);
+    out.extend(payload);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLOB_LEN;
+
+    fn example_wrapper() -> BlobTransactionNetworkWrapper {
+        let blob: Vec<BigUint> = (0..BLOB_LEN as u32).map(BigUint::from).collect();
+        let commitment = [0xc0; BYTES_PER_COMMITMENT];
+        BlobTransactionNetworkWrapper {
+            transaction: BlobTransaction {
+                chain_id: 1,
+                nonce: 7,
+                max_priority_fee_per_gas: 1_000_000_000,
+                max_fee_per_gas: 30_000_000_000,
+                gas_limit: 100_000,
+                to: [0x11; 20],
+                value: BigUint::from(0u32),
+                data: vec![0xde, 0xad, 0xbe, 0xef],
+                access_list: vec![AccessListItem {
+                    address: [0x22; 20],
+                    storage_keys: vec![[0x33; 32]],
+                }],
+                max_fee_per_blob_gas: 1,
+                blob_versioned_hashes: vec![kzg_to_versioned_hash(&commitment)],
+                signature: Some(Signature {
+                    y_parity: true,
+                    r: BigUint::from(12345u32),
+                    s: BigUint::from(67890u32),
+                }),
+            },
+            sidecar: BlobTransactionSidecar {
+                version: WRAPPER_VERSION_BLOB_PROOFS,
+                blobs: vec![blob],
+                commitments: vec![commitment],
+                proofs: vec![[0xc0; BYTES_PER_PROOF]],
+            },
+        }
+    }
+
+    #[test]
+    fn test_network_wrapper_round_trip() {
+        let wrapper = example_wrapper();
+        let raw = wrapper.encode().unwrap();
+        assert_eq!(
+            BlobTransactionNetworkWrapper::decode(&raw).unwrap(),
+            wrapper
+        );
+        wrapper.verify_versioned_hashes().unwrap();
+
+        let (transaction, sidecar) = decode_blob_transaction(&raw).unwrap();
+        assert_eq!(transaction, wrapper.transaction);
+        assert_eq!(sidecar, Some(wrapper.sidecar));
+
+        let raw_transaction = transaction.encode();
+        let (decoded, sidecar) = decode_blob_transaction(&raw_transaction).unwrap();
+        assert_eq!(decoded, transaction);
+        assert_eq!(sidecar, None);
+        assert_eq!(decoded.hash(), keccak256(&raw_transaction));
+    }
+
+    #[test]
+    fn test_decode_unsigned_transaction() {
+        let mut transaction = example_wrapper().transaction;
+        transaction.signature = None;
+        let raw = transaction.encode();
+        assert_eq!(BlobTransaction::decode(&raw).unwrap(), transaction);
+        assert_eq!(decode_blob_transaction(&raw).unwrap(), (transaction, None));
+
+        // Twelve fields are neither signed nor unsigned
+        let fields = vec![rlp::encode_uint(1); UNSIGNED_FIELDS + 1];
+        assert!(matches!(
+            BlobTransaction::decode(&with_type(rlp::encode_list(&fields))),
+            Err(TransactionError::Rlp(RlpError::InvalidListLength {
+                expected: 14,
+                actual: 12
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_verify_versioned_hashes() {
+        let mut wrapper = example_wrapper();
+        wrapper.sidecar.commitments[0][47] ^= 1;
+        assert!(matches!(
+            wrapper.verify_versioned_hashes(),
+            Err(TransactionError::VersionedHashMismatch(0))
+        ));
+        wrapper.transaction.blob_versioned_hashes.push([0x01; 32]);
+        assert!(matches!(
+            wrapper.verify_versioned_hashes(),
+            Err(TransactionError::VersionedHashCount {
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_rejects_other_types() {
+        assert!(matches!(
+            decode_blob_transaction(&[0x02, 0xc0]),
+            Err(TransactionError::NotBlobTransaction(0x02))
+        ));
+        assert!(matches!(
+            decode_blob_transaction(&[]),
+            Err(TransactionError::Empty)
+        ));
+    }
+
+    #[cfg(feature = "kzg")]
+    #[test]
+    fn test_verify_kzg_proofs() {
+        use crate::kzg::{blob_to_commitment, compute_blob_proof};
+
+        let mut wrapper = example_wrapper();
+        let blob = &wrapper.sidecar.blobs[0];
+        let commitment = blob_to_commitment(blob).unwrap();
+        wrapper.sidecar.proofs = vec![compute_blob_proof(blob, &commitment).unwrap()];
+        wrapper.sidecar.commitments = vec![commitment];
+        wrapper.sidecar.verify_kzg_proofs().unwrap();
+
+        wrapper.sidecar.blobs[0][0] += 1u32;
+        assert!(matches!(
+            wrapper.sidecar.verify_kzg_proofs(),
+            Err(TransactionError::InvalidProof(0))
+        ));
+    }
+
+    #[cfg(feature = "signer")]
+    #[test]
+    fn test_sign_and_recover_sender() {
+        let secret_key = [0x42; 32];
+        let mut transaction = example_wrapper().transaction;
+        transaction.signature = None;
+        transaction.sign(&secret_key).unwrap();
+
+        let expected = secret_key_to_address(&secret_key).unwrap();
+        assert_eq!(transaction.sender().unwrap(), expected);
+        assert_eq!(
+            BlobTransaction::decode(&transaction.encode()).unwrap(),
+            transaction
+        );
+
+        transaction.nonce += 1;
+        assert_ne!(transaction.sender().unwrap(), expected);
+    }
+
+    #[cfg(feature = "signer")]
+    #[test]
+    fn test_secret_key_to_address() {
+        let secret_key: [u8; 32] =
+            hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(
+            hex::encode(secret_key_to_address(&secret_key).unwrap()),
+            "2c7536e3605d9c16a7a3d7b1898e529396a65c23"
+        );
+    }
+}
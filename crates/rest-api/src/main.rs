@@ -11,33 +11,293 @@ async fn main() {
 
 mod filters {
     use super::handlers;
+    use std::collections::HashMap;
     use warp::Filter;
 
     pub fn blob() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
-        blob_recover()
+        blob_recover().or(state_update()).or(balances())
     }
 
     pub fn blob_recover(
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         warp::path!("blob")
             .and(warp::post())
+            .and(warp::query::<HashMap<String, String>>())
             .and(warp::body::bytes())
             .and_then(handlers::blob_recover)
     }
+
+    pub fn state_update(
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("state-update")
+            .and(warp::post())
+            .and(warp::body::bytes())
+            .and_then(handlers::state_update)
+    }
+
+    pub fn balances() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+    {
+        warp::path!("balances")
+            .and(warp::post())
+            .and(warp::body::bytes())
+            .and_then(handlers::balances)
+    }
 }
 
 pub mod handlers {
     use majin_blob_core::blob;
+    use majin_blob_core::commitment::StateDiff;
+    use majin_blob_core::l1::decode_state_update;
+    use majin_blob_core::registry::{LabelRegistry, Network, RegistryError};
+    use majin_blob_core::tokens::{balance_changes, PreviousStorage, Token};
+    use majin_blob_types::abi::{parse_logs_json, Log, STARKNET_CORE_CONTRACT};
+    use majin_blob_types::os_output::{OsOutputError, OsOutputVersion};
     use majin_blob_types::serde;
+    use majin_blob_types::sidecar::{self, Authentication};
+    use majin_blob_types::state_diffs::{self, DataJson};
+    use num_bigint::BigUint;
+    use std::collections::HashMap;
     use std::convert::Infallible;
+    use warp::http::StatusCode;
     use warp::hyper::body::Bytes;
 
-    pub async fn blob_recover(data: Bytes) -> Result<impl warp::Reply, Infallible> {
-        let data = String::from_utf8(data.to_vec()).unwrap();
-        let blob_data = serde::parse_str_to_blob_data(data.as_str());
-        let original_data = blob::recover(blob_data);
+    /// Accepts a blob as hex, or a beacon API `blob_sidecars` response in JSON or SSZ.
+    /// Sidecars are checked before their blobs are recovered, against the trusted block
+    /// root given as the `block_root` query parameter, if any. The `x-sidecars-authenticated`
+    /// header of the reply tells whether they were tied to that root: without it, forged
+    /// but consistent sidecars are accepted.
+    pub async fn blob_recover(
+        query: HashMap<String, String>,
+        data: Bytes,
+    ) -> Result<Box<dyn warp::Reply>, Infallible> {
+        let block_root = match query.get("block_root").map(|root| parse_block_root(root)) {
+            Some(Ok(block_root)) => Some(block_root),
+            Some(Err(e)) => {
+                return Ok(Box::new(warp::reply::with_status(
+                    e,
+                    StatusCode::BAD_REQUEST,
+                )))
+            }
+            None => None,
+        };
+        let blob_input = match sidecar::parse_blob_input(&data) {
+            Ok(blob_input) => blob_input,
+            Err(e) => {
+                return Ok(Box::new(warp::reply::with_status(
+                    e.to_string(),
+                    StatusCode::BAD_REQUEST,
+                )))
+            }
+        };
+        let authentication = match &blob_input {
+            sidecar::BlobInput::Sidecars(sidecars) => {
+                match sidecar::verify_sidecars(sidecars, block_root.as_ref()) {
+                    Ok(authentication) => Some(authentication),
+                    Err(e) => {
+                        return Ok(Box::new(warp::reply::with_status(
+                            e.to_string(),
+                            StatusCode::UNPROCESSABLE_ENTITY,
+                        )))
+                    }
+                }
+            }
+            sidecar::BlobInput::Blob(_) => None,
+        };
+        let original_data = blob::recover_blobs(blob_input.into_blobs());
         let state_diffs = serde::parse_state_diffs(original_data.as_slice());
         let state_diffs_json = serde::to_json(state_diffs);
-        Ok(state_diffs_json)
+        let reply = warp::reply::with_status(state_diffs_json, StatusCode::OK);
+        Ok(match authentication {
+            Some(authentication) => Box::new(warp::reply::with_header(
+                reply,
+                "x-sidecars-authenticated",
+                (authentication == Authentication::Authenticated).to_string(),
+            )),
+            None => Box::new(reply),
+        })
+    }
+
+    fn parse_block_root(value: &str) -> Result<[u8; 32], String> {
+        hex::decode(value.trim_start_matches("0x"))
+            .ok()
+            .and_then(|root| root.try_into().ok())
+            .ok_or_else(|| format!("invalid block root {}", value))
+    }
+
+    /// Accepts an `updateStateKzgDA` transaction as JSON:
+    /// `{"input": "0x..", "logs": [..], "blobs": [..], "os_version": "0.13.2"}`, where each
+    /// blob is a hex string or a beacon API `blob_sidecars` response, and `logs` and
+    /// `os_version` are optional. Replies with the state update linked to its blobs, with
+    /// the state diffs and the messages of the OS output. The felts of the state diffs are
+    /// named under `names` if requested, see [`parse_registry`].
+    pub async fn state_update(data: Bytes) -> Result<impl warp::Reply, Infallible> {
+        let (state_update, registry) = match parse_state_update_request(&data) {
+            Ok((input, logs, blob_inputs, version, registry)) => (
+                decode_state_update(&input, &logs, blob_inputs, &STARKNET_CORE_CONTRACT, version),
+                registry,
+            ),
+            Err(e) => return Ok(warp::reply::with_status(e, StatusCode::BAD_REQUEST)),
+        };
+        Ok(match state_update {
+            Ok(state_update) => {
+                let status = match state_update.is_valid() {
+                    true => StatusCode::OK,
+                    false => StatusCode::UNPROCESSABLE_ENTITY,
+                };
+                let mut json = serde_json::to_value(&state_update).unwrap_or_default();
+                if let Some(registry) = registry {
+                    json["names"] =
+                        serde_json::json!(registry.names(&state_update.transition.state_diffs));
+                }
+                warp::reply::with_status(json.to_string(), status)
+            }
+            Err(e) => warp::reply::with_status(e.to_string(), StatusCode::UNPROCESSABLE_ENTITY),
+        })
+    }
+
+    type StateUpdateRequest = (
+        Vec<u8>,
+        Vec<Log>,
+        Vec<sidecar::BlobInput>,
+        OsOutputVersion,
+        Option<LabelRegistry>,
+    );
+
+    fn parse_state_update_request(data: &[u8]) -> Result<StateUpdateRequest, String> {
+        let request: serde_json::Value = serde_json::from_slice(data).map_err(|e| e.to_string())?;
+        let input = request["input"]
+            .as_str()
+            .ok_or("missing input")?
+            .trim_start_matches("0x");
+        let input = hex::decode(input).map_err(|e| format!("invalid input: {}", e))?;
+        let logs = match request.get("logs") {
+            Some(logs) => parse_logs_json(&logs.to_string()).map_err(|e| e.to_string())?,
+            None => Vec::new(),
+        };
+        let mut blob_inputs = Vec::new();
+        for blob in request["blobs"].as_array().ok_or("missing blobs")? {
+            let blob = match blob.as_str() {
+                Some(blob) => blob.to_string(),
+                None => blob.to_string(),
+            };
+            blob_inputs
+                .push(sidecar::parse_blob_input(blob.as_bytes()).map_err(|e| e.to_string())?);
+        }
+        let version = match request["os_version"].as_str() {
+            Some(version) => version.parse().map_err(|e: OsOutputError| e.to_string())?,
+            None => OsOutputVersion::V0_13_2,
+        };
+        Ok((input, logs, blob_inputs, version, parse_registry(&request)?))
+    }
+
+    /// Accepts state diffs and reports the new ERC20 balances they write:
+    /// `{"state_diffs": {..}, "previous": [{..}], "tokens": [{"name": "USDC", "address":
+    /// "0x.."}], "default_tokens": true, "accounts": ["0x.."]}`. Only `state_diffs` is
+    /// required. `previous` lists the state diffs of the preceding updates from genesis, in
+    /// order, to compute deltas. ETH and STRK are reported unless `default_tokens` is false.
+    /// The tokens and accounts of each balance are named under `names` if requested, see
+    /// [`parse_registry`].
+    pub async fn balances(data: Bytes) -> Result<impl warp::Reply, Infallible> {
+        Ok(match parse_balances_request(&data) {
+            Ok((state_diffs, tokens, accounts, previous_state, registry)) => {
+                let previous = |address: &BigUint, key: &BigUint| {
+                    previous_state
+                        .as_ref()
+                        .and_then(|state| state.storage_diffs.get(address))
+                        .and_then(|storage| storage.get(key))
+                        .cloned()
+                        .unwrap_or_default()
+                };
+                let previous: Option<PreviousStorage> =
+                    previous_state.as_ref().map(|_| &previous as _);
+                let changes = balance_changes(&state_diffs, &tokens, &accounts, previous);
+                let json: serde_json::Value = changes
+                    .iter()
+                    .map(|change| {
+                        let mut json = serde_json::to_value(change).unwrap_or_default();
+                        if let Some(registry) = &registry {
+                            let names = registry.names_of([&change.token_address, &change.account]);
+                            json["names"] = serde_json::json!(names);
+                        }
+                        json
+                    })
+                    .collect();
+                warp::reply::with_status(json.to_string(), StatusCode::OK)
+            }
+            Err(e) => warp::reply::with_status(e, StatusCode::BAD_REQUEST),
+        })
+    }
+
+    type BalancesRequest = (
+        DataJson,
+        Vec<Token>,
+        Vec<BigUint>,
+        Option<StateDiff>,
+        Option<LabelRegistry>,
+    );
+
+    fn parse_balances_request(data: &[u8]) -> Result<BalancesRequest, String> {
+        let request: serde_json::Value = serde_json::from_slice(data).map_err(|e| e.to_string())?;
+        let parse_state_diffs = |value: &serde_json::Value| -> Result<DataJson, String> {
+            serde_json::from_value(value.clone()).map_err(|e| format!("invalid state diffs: {}", e))
+        };
+        let state_diffs =
+            parse_state_diffs(request.get("state_diffs").ok_or("missing state_diffs")?)?;
+        let previous_state = match request.get("previous") {
+            Some(previous) => {
+                let mut state = StateDiff::default();
+                for state_diffs in previous.as_array().ok_or("previous must be a list")? {
+                    state.merge(&StateDiff::from_data_json(&parse_state_diffs(state_diffs)?));
+                }
+                Some(state)
+            }
+            None => None,
+        };
+        let mut tokens = match request["default_tokens"].as_bool() {
+            Some(false) => Vec::new(),
+            _ => Token::defaults(),
+        };
+        for token in request["tokens"].as_array().into_iter().flatten() {
+            let name = token["name"].as_str().ok_or("missing token name")?;
+            let address = parse_felt(&token["address"])?;
+            tokens.push(Token::new(name, address));
+        }
+        let accounts = request["accounts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(parse_felt)
+            .collect::<Result<_, _>>()?;
+        Ok((
+            state_diffs,
+            tokens,
+            accounts,
+            previous_state,
+            parse_registry(&request)?,
+        ))
+    }
+
+    /// Reads the names requested by `"names": true`, from the built-in names of `network`,
+    /// mainnet by default, and the label file in JSON given as `registry`, if any.
+    fn parse_registry(request: &serde_json::Value) -> Result<Option<LabelRegistry>, String> {
+        let layer = request.get("registry");
+        if request["names"].as_bool() != Some(true) && layer.is_none() {
+            return Ok(None);
+        }
+        let network = match request["network"].as_str() {
+            Some(network) => network.parse().map_err(|e: RegistryError| e.to_string())?,
+            None => Network::Mainnet,
+        };
+        let mut registry = LabelRegistry::defaults(network);
+        if let Some(layer) = layer {
+            registry
+                .extend(LabelRegistry::parse_json(&layer.to_string()).map_err(|e| e.to_string())?);
+        }
+        Ok(Some(registry))
+    }
+
+    fn parse_felt(value: &serde_json::Value) -> Result<BigUint, String> {
+        let value = value.as_str().ok_or("felts must be strings")?;
+        state_diffs::parse_felt(value).map_err(|e| format!("invalid felt {}: {}", value, e))
     }
 }
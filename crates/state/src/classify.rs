@@ -0,0 +1,198 @@
+//! Classification of the class hashes of state diffs into deployments and replacements.
+//!
+//! State diffs give the new class hash of a contract without saying whether the contract
+//! was deployed or had its class replaced. The contracts seen in earlier updates tell them
+//! apart.
+
+use std::collections::HashSet;
+use std::io::BufRead;
+
+use majin_blob_types::state_diffs::{serialize_biguint, serialize_option_biguint, DataJson};
+use num_bigint::BigUint;
+use serde::Serialize;
+
+use crate::store::{read_snapshot, StateError};
+
+/// What a state update did to the class of a contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassChange {
+    /// The contract was deployed.
+    Deployed,
+    /// The class of an existing contract was replaced.
+    Replaced,
+    /// The class of the contract did not change.
+    Unchanged,
+    /// The contract has a new class, but the history is too incomplete to tell whether it
+    /// was deployed or replaced.
+    Unknown,
+}
+
+/// The class change of a contract in a state update.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassifiedContract {
+    #[serde(serialize_with = "serialize_biguint")]
+    pub address: BigUint,
+    pub class_change: ClassChange,
+    #[serde(serialize_with = "serialize_option_biguint")]
+    pub class_hash: Option<BigUint>,
+}
+
+/// Tracks the contracts known to exist across successive state updates.
+#[derive(Debug, Clone, Default)]
+pub struct ContractClassifier {
+    known: HashSet<BigUint>,
+    /// Whether the updates seen start from genesis, so that every existing contract is
+    /// known.
+    complete: bool,
+}
+
+impl ContractClassifier {
+    /// Creates a classifier for updates starting from genesis.
+    pub fn from_genesis() -> Self {
+        ContractClassifier {
+            known: HashSet::new(),
+            complete: true,
+        }
+    }
+
+    /// Creates a classifier for updates starting after genesis. New classes of contracts
+    /// not seen yet are [`ClassChange::Unknown`].
+    pub fn partial() -> Self {
+        ContractClassifier::default()
+    }
+
+    /// Creates a classifier knowing the contracts of the updates of a snapshot, see
+    /// [`crate::store::StateStore::export_snapshot`].
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The snapshot.
+    /// * `complete` - Whether the snapshot starts from genesis.
+    pub fn from_snapshot(input: impl BufRead, complete: bool) -> Result<Self, StateError> {
+        let mut classifier = ContractClassifier {
+            known: HashSet::new(),
+            complete,
+        };
+        for entry in read_snapshot(input) {
+            classifier.classify(&entry?.1);
+        }
+        Ok(classifier)
+    }
+
+    /// Returns `true` if the contract is known to exist.
+    pub fn is_known(&self, address: &BigUint) -> bool {
+        self.known.contains(address)
+    }
+
+    /// Classifies the contracts of the next state update, then records them as known.
+    ///
+    /// # Returns
+    ///
+    /// The class change of every contract of the update, in order.
+    pub fn classify(&mut self, state_diffs: &DataJson) -> Vec<ClassifiedContract> {
+        let classified: Vec<ClassifiedContract> = state_diffs
+            .state_update
+            .iter()
+            .map(|contract| {
+                let class_change = match &contract.new_class_hash {
+                    None => ClassChange::Unchanged,
+                    Some(_) if self.is_known(&contract.address) => ClassChange::Replaced,
+                    Some(_) if self.complete => ClassChange::Deployed,
+                    Some(_) => ClassChange::Unknown,
+                };
+                ClassifiedContract {
+                    address: contract.address.clone(),
+                    class_change,
+                    class_hash: contract.new_class_hash.clone(),
+                }
+            })
+            .collect();
+        self.known.extend(
+            state_diffs
+                .state_update
+                .iter()
+                .map(|contract| contract.address.clone()),
+        );
+        classified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::StateStore;
+    use majin_blob_types::state_diffs::ContractUpdate;
+
+    fn state_diffs(contracts: &[(u32, Option<u32>)]) -> DataJson {
+        DataJson {
+            state_update_size: contracts.len() as u64,
+            state_update: contracts
+                .iter()
+                .map(|(address, class_hash)| ContractUpdate {
+                    address: BigUint::from(*address),
+                    nonce: 0,
+                    number_of_storage_updates: 0,
+                    new_class_hash: class_hash.map(BigUint::from),
+                    storage_updates: Vec::new(),
+                })
+                .collect(),
+            class_declaration_size: 0,
+            class_declaration: Vec::new(),
+        }
+    }
+
+    fn changes(classified: &[ClassifiedContract]) -> Vec<ClassChange> {
+        classified
+            .iter()
+            .map(|contract| contract.class_change)
+            .collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        let first = state_diffs(&[(1, Some(100)), (2, None)]);
+        let second = state_diffs(&[(1, Some(200)), (2, Some(200)), (3, Some(100))]);
+
+        let mut classifier = ContractClassifier::from_genesis();
+        assert_eq!(
+            changes(&classifier.classify(&first)),
+            [ClassChange::Deployed, ClassChange::Unchanged]
+        );
+        assert_eq!(
+            changes(&classifier.classify(&second)),
+            [
+                ClassChange::Replaced,
+                ClassChange::Replaced,
+                ClassChange::Deployed
+            ]
+        );
+
+        // Without the first update, the new classes of contracts 1 and 3 are ambiguous
+        let mut classifier = ContractClassifier::partial();
+        assert_eq!(
+            changes(&classifier.classify(&second)),
+            [
+                ClassChange::Unknown,
+                ClassChange::Unknown,
+                ClassChange::Unknown
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::open(dir.path().join("state.redb")).unwrap();
+        store.apply(&state_diffs(&[(1, None)])).unwrap();
+        let mut snapshot = Vec::new();
+        store.export_snapshot(&mut snapshot).unwrap();
+
+        let mut classifier = ContractClassifier::from_snapshot(snapshot.as_slice(), false).unwrap();
+        assert!(classifier.is_known(&BigUint::from(1u32)));
+        assert_eq!(
+            changes(&classifier.classify(&state_diffs(&[(1, Some(5)), (2, Some(5))]))),
+            [ClassChange::Replaced, ClassChange::Unknown]
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod classify;
+pub mod state_root;
+pub mod store;
+pub mod trie;
@@ -0,0 +1,404 @@
+//! The Starknet global state root, rebuilt from a full state and the state diffs applied
+//! on top of it.
+//!
+//! The global root commits to the contracts trie, whose leaves commit to the storage trie
+//! of each contract, and to the classes trie.
+
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+use majin_blob_core::hash::{from_field_element, to_field_element};
+use majin_blob_types::os_output::StateTransition;
+use majin_blob_types::state_diffs::{serialize_biguint, DataJson};
+use num_bigint::BigUint;
+use serde::{Serialize, Serializer};
+use starknet_crypto::FieldElement;
+
+use crate::store::{read_snapshot, StateError, StateStore};
+use crate::trie::{trie_root, Pedersen, Poseidon, TrieHash};
+
+/// Version of the hash of the leaves of the classes trie.
+const CONTRACT_CLASS_LEAF_V0: &[u8] = b"CONTRACT_CLASS_LEAF_V0";
+/// Version of the global state root.
+const STARKNET_STATE_V0: &[u8] = b"STARKNET_STATE_V0";
+
+/// The state of a contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractState {
+    pub class_hash: BigUint,
+    pub nonce: BigUint,
+    pub storage: BTreeMap<BigUint, BigUint>,
+}
+
+/// The full Starknet state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StarknetState {
+    /// The contracts, by address.
+    pub contracts: BTreeMap<BigUint, ContractState>,
+    /// The compiled class hashes of the declared classes, by class hash.
+    pub classes: BTreeMap<BigUint, BigUint>,
+}
+
+impl StarknetState {
+    /// Rebuilds the state by applying the updates of a snapshot, see
+    /// [`crate::store::StateStore::export_snapshot`]. The snapshot must start from
+    /// genesis.
+    pub fn from_snapshot(input: impl BufRead) -> Result<Self, StateError> {
+        let mut state = StarknetState::default();
+        for entry in read_snapshot(input) {
+            state.apply(&entry?.1);
+        }
+        Ok(state)
+    }
+
+    /// Rebuilds the state after the update `at_update` of a store whose updates start from
+    /// genesis.
+    pub fn from_store(store: &StateStore, at_update: u64) -> Result<Self, StateError> {
+        let mut state = StarknetState::default();
+        for update in 0..=at_update {
+            match store.state_diffs(update)? {
+                Some(state_diffs) => state.apply(&state_diffs),
+                None => break,
+            }
+        }
+        Ok(state)
+    }
+
+    /// Applies state diffs. Storage slots set to zero are removed.
+    pub fn apply(&mut self, state_diffs: &DataJson) {
+        for update in &state_diffs.state_update {
+            let contract = self.contracts.entry(update.address.clone()).or_default();
+            contract.nonce = BigUint::from(update.nonce);
+            if let Some(class_hash) = &update.new_class_hash {
+                contract.class_hash = class_hash.clone();
+            }
+            for storage_update in &update.storage_updates {
+                match storage_update.value == BigUint::default() {
+                    true => contract.storage.remove(&storage_update.key),
+                    false => contract
+                        .storage
+                        .insert(storage_update.key.clone(), storage_update.value.clone()),
+                };
+            }
+        }
+        for class in &state_diffs.class_declaration {
+            self.classes
+                .insert(class.class_hash.clone(), class.compiled_class_hash.clone());
+        }
+    }
+
+    /// Computes the roots of the tries of the state.
+    pub fn roots(&self) -> Result<StateRoots, StateError> {
+        let mut storage_roots = BTreeMap::new();
+        let mut contract_leaves = Vec::with_capacity(self.contracts.len());
+        for (address, contract) in &self.contracts {
+            let storage: Vec<([u8; 32], FieldElement)> = contract
+                .storage
+                .iter()
+                .map(|(key, value)| Ok((felt(key)?.to_bytes_be(), felt(value)?)))
+                .collect::<Result<_, StateError>>()?;
+            let storage_root = trie_root::<Pedersen>(&storage);
+            // The hash of the contract state, of version 0
+            let hash = Pedersen::hash(&felt(&contract.class_hash)?, &storage_root);
+            let hash = Pedersen::hash(&hash, &felt(&contract.nonce)?);
+            let hash = Pedersen::hash(&hash, &FieldElement::ZERO);
+            contract_leaves.push((felt(address)?.to_bytes_be(), hash));
+            storage_roots.insert(address.clone(), from_field_element(&storage_root));
+        }
+        let contracts_root = trie_root::<Pedersen>(&contract_leaves);
+
+        let leaf_version = short_string(CONTRACT_CLASS_LEAF_V0);
+        let class_leaves: Vec<([u8; 32], FieldElement)> = self
+            .classes
+            .iter()
+            .map(|(class_hash, compiled_class_hash)| {
+                let leaf = Poseidon::hash(&leaf_version, &felt(compiled_class_hash)?);
+                Ok((felt(class_hash)?.to_bytes_be(), leaf))
+            })
+            .collect::<Result<_, StateError>>()?;
+        let classes_root = trie_root::<Poseidon>(&class_leaves);
+
+        let global_root = match classes_root == FieldElement::ZERO {
+            true => contracts_root,
+            false => starknet_crypto::poseidon_hash_many(&[
+                short_string(STARKNET_STATE_V0),
+                contracts_root,
+                classes_root,
+            ]),
+        };
+        Ok(StateRoots {
+            global_root: from_field_element(&global_root),
+            contracts_root: from_field_element(&contracts_root),
+            classes_root: from_field_element(&classes_root),
+            storage_roots,
+        })
+    }
+}
+
+/// The roots of the tries of a state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateRoots {
+    #[serde(serialize_with = "serialize_biguint")]
+    pub global_root: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub contracts_root: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub classes_root: BigUint,
+    /// The roots of the storage tries, by contract address.
+    #[serde(serialize_with = "serialize_storage_roots")]
+    pub storage_roots: BTreeMap<BigUint, BigUint>,
+}
+
+/// Roots to check a state against, as known from elsewhere. Roots left out are not
+/// checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpectedRoots {
+    pub global_root: Option<BigUint>,
+    pub contracts_root: Option<BigUint>,
+    pub classes_root: Option<BigUint>,
+    pub storage_roots: BTreeMap<BigUint, BigUint>,
+}
+
+/// A trie of the state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subtree {
+    Global,
+    Contracts,
+    Classes,
+    Storage(#[serde(serialize_with = "serialize_biguint")] BigUint),
+}
+
+/// A trie whose root is not the expected one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RootMismatch {
+    pub subtree: Subtree,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub expected: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub computed: BigUint,
+}
+
+impl StateRoots {
+    /// Compares the roots with the expected ones.
+    ///
+    /// # Returns
+    ///
+    /// The tries whose root differs, from the global root down to the storage tries.
+    pub fn compare(&self, expected: &ExpectedRoots) -> Vec<RootMismatch> {
+        let zero = BigUint::default();
+        let tops = [
+            (Subtree::Global, &expected.global_root, &self.global_root),
+            (
+                Subtree::Contracts,
+                &expected.contracts_root,
+                &self.contracts_root,
+            ),
+            (Subtree::Classes, &expected.classes_root, &self.classes_root),
+        ];
+        let tops = tops
+            .into_iter()
+            .filter_map(|(subtree, expected, computed)| {
+                Some((subtree, expected.as_ref()?, computed))
+            });
+        // A contract missing from the state has an empty storage trie
+        let storage = expected.storage_roots.iter().map(|(address, expected)| {
+            let computed = self.storage_roots.get(address).unwrap_or(&zero);
+            (Subtree::Storage(address.clone()), expected, computed)
+        });
+        tops.chain(storage)
+            .filter(|(_, expected, computed)| expected != computed)
+            .map(|(subtree, expected, computed)| RootMismatch {
+                subtree,
+                expected: expected.clone(),
+                computed: computed.clone(),
+            })
+            .collect()
+    }
+}
+
+/// The check of a state transition against the roots of the OS output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransitionCheck {
+    /// Whether the state before the transition has the previous root of the OS output.
+    pub previous_root_matches: bool,
+    /// The roots of the state after the transition.
+    pub roots: StateRoots,
+    /// The tries of the state after the transition whose root is not the expected one.
+    pub mismatches: Vec<RootMismatch>,
+}
+
+impl TransitionCheck {
+    /// Returns `true` if every root is the expected one.
+    pub fn is_valid(&self) -> bool {
+        self.previous_root_matches && self.mismatches.is_empty()
+    }
+}
+
+/// Applies the state diffs of a transition to a state, and checks the roots of the state
+/// before and after against the roots of the OS output.
+///
+/// # Arguments
+///
+/// * `state` - The state before the transition, updated in place.
+/// * `transition` - The state diffs and the roots of the OS output.
+/// * `expected` - The roots of the subtries after the transition, when known, to find the
+///   tries a wrong global root comes from. Its global root defaults to the new root of
+///   the OS output.
+pub fn check_transition(
+    state: &mut StarknetState,
+    transition: &StateTransition,
+    mut expected: ExpectedRoots,
+) -> Result<TransitionCheck, StateError> {
+    let previous_root = state.roots()?.global_root;
+    state.apply(&transition.state_diffs);
+    let roots = state.roots()?;
+    expected
+        .global_root
+        .get_or_insert_with(|| transition.new_state_root.clone());
+    Ok(TransitionCheck {
+        previous_root_matches: previous_root == transition.previous_state_root,
+        mismatches: roots.compare(&expected),
+        roots,
+    })
+}
+
+fn felt(value: &BigUint) -> Result<FieldElement, StateError> {
+    to_field_element(value).ok_or_else(|| StateError::FeltTooLarge(value.clone()))
+}
+
+/// Encodes an ASCII string of at most 31 characters as a felt, as Cairo short strings.
+fn short_string(value: &[u8]) -> FieldElement {
+    FieldElement::from_byte_slice_be(value).unwrap()
+}
+
+fn serialize_storage_roots<S>(
+    roots: &BTreeMap<BigUint, BigUint>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(
+        roots
+            .iter()
+            .map(|(address, root)| (address.to_str_radix(10), root.to_str_radix(10))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin_blob_types::state_diffs::{ClassDeclaration, ContractUpdate, StorageUpdate};
+
+    fn big(value: u64) -> BigUint {
+        BigUint::from(value)
+    }
+
+    fn state_diffs(storage: &[(u64, u64)], classes: &[(u64, u64)]) -> DataJson {
+        DataJson {
+            state_update_size: 1,
+            state_update: vec![ContractUpdate {
+                address: big(0x100),
+                nonce: 1,
+                number_of_storage_updates: storage.len() as u64,
+                new_class_hash: Some(big(0xc1)),
+                storage_updates: storage
+                    .iter()
+                    .map(|(key, value)| StorageUpdate {
+                        key: big(*key),
+                        value: big(*value),
+                    })
+                    .collect(),
+            }],
+            class_declaration_size: classes.len() as u64,
+            class_declaration: classes
+                .iter()
+                .map(|(class_hash, compiled_class_hash)| ClassDeclaration {
+                    class_hash: big(*class_hash),
+                    compiled_class_hash: big(*compiled_class_hash),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_roots() {
+        let mut state = StarknetState::default();
+        state.apply(&state_diffs(&[(5, 7)], &[]));
+        let roots = state.roots().unwrap();
+
+        let f = |value: u64| FieldElement::from(value);
+        let storage_root = trie_root::<Pedersen>(&[(f(5).to_bytes_be(), f(7))]);
+        let contract_hash = Pedersen::hash(
+            &Pedersen::hash(&Pedersen::hash(&f(0xc1), &storage_root), &f(1)),
+            &f(0),
+        );
+        let contracts_root = trie_root::<Pedersen>(&[(f(0x100).to_bytes_be(), contract_hash)]);
+        assert_eq!(
+            roots.storage_roots[&big(0x100)],
+            from_field_element(&storage_root)
+        );
+        assert_eq!(roots.contracts_root, from_field_element(&contracts_root));
+        // Without classes, the global root is the contracts root
+        assert_eq!(roots.classes_root, big(0));
+        assert_eq!(roots.global_root, roots.contracts_root);
+
+        // Clearing the slot empties the storage trie
+        state.apply(&state_diffs(&[(5, 0)], &[(0xc1, 0xcc)]));
+        let roots = state.roots().unwrap();
+        assert_eq!(roots.storage_roots[&big(0x100)], big(0));
+        let class_leaf = Poseidon::hash(&short_string(CONTRACT_CLASS_LEAF_V0), &f(0xcc));
+        let classes_root = trie_root::<Poseidon>(&[(f(0xc1).to_bytes_be(), class_leaf)]);
+        assert_eq!(roots.classes_root, from_field_element(&classes_root));
+        assert_eq!(
+            roots.global_root,
+            from_field_element(&starknet_crypto::poseidon_hash_many(&[
+                short_string(STARKNET_STATE_V0),
+                felt(&roots.contracts_root).unwrap(),
+                classes_root,
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_check_transition() {
+        let mut state = StarknetState::default();
+        state.apply(&state_diffs(&[(5, 7)], &[]));
+        let previous_state_root = state.roots().unwrap().global_root;
+        let diffs = state_diffs(&[(6, 8)], &[(0xc1, 0xcc)]);
+        let mut next = state.clone();
+        next.apply(&diffs);
+        let new_roots = next.roots().unwrap();
+
+        let mut transition = StateTransition {
+            first_block_number: 1,
+            last_block_number: 1,
+            previous_state_root,
+            new_state_root: new_roots.global_root.clone(),
+            new_block_hash: big(0),
+            messages: Default::default(),
+            state_diffs: diffs,
+        };
+        let check =
+            check_transition(&mut state.clone(), &transition, ExpectedRoots::default()).unwrap();
+        assert!(check.is_valid());
+        assert_eq!(check.roots, new_roots);
+
+        // A wrong root of the classes trie is told apart from a wrong contracts trie
+        transition.new_state_root += 1u32;
+        let expected = ExpectedRoots {
+            contracts_root: Some(new_roots.contracts_root.clone()),
+            classes_root: Some(big(1)),
+            ..ExpectedRoots::default()
+        };
+        let check = check_transition(&mut state, &transition, expected).unwrap();
+        assert!(check.previous_root_matches);
+        let subtrees: Vec<Subtree> = check
+            .mismatches
+            .into_iter()
+            .map(|mismatch| mismatch.subtree)
+            .collect();
+        assert_eq!(subtrees, [Subtree::Global, Subtree::Classes]);
+    }
+}
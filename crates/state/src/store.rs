@@ -0,0 +1,445 @@
+//! A Starknet state rebuilt by applying successive state diffs, in an embedded database.
+//!
+//! Every value is stored with the index of the update that wrote it, so the state can be
+//! queried as it was after any update.
+
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use majin_blob_types::state_diffs::{serialize_biguint, DataJson};
+use num_bigint::BigUint;
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A felt, as 32 big-endian bytes.
+type Felt = [u8; 32];
+
+/// The state diffs of every update, as JSON, by update index.
+const UPDATES: TableDefinition<u64, &str> = TableDefinition::new("updates");
+/// Storage values by contract address, storage key and update index.
+const STORAGE: TableDefinition<(Felt, Felt, u64), Felt> = TableDefinition::new("storage");
+/// Nonces by contract address and update index.
+const NONCES: TableDefinition<(Felt, u64), u64> = TableDefinition::new("nonces");
+/// Class hashes by contract address and update index.
+const CLASS_HASHES: TableDefinition<(Felt, u64), Felt> = TableDefinition::new("class_hashes");
+/// Compiled class hashes by class hash and update index.
+const DECLARED_CLASSES: TableDefinition<(Felt, u64), Felt> =
+    TableDefinition::new("declared_classes");
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error(transparent)]
+    Database(Box<redb::Error>),
+    #[error("{0} does not fit in a felt")]
+    FeltTooLarge(BigUint),
+    #[error("invalid snapshot: {0}")]
+    Snapshot(#[from] serde_json::Error),
+    #[error("the snapshot continues from update {actual}, the store has {expected} updates")]
+    SnapshotGap { expected: u64, actual: u64 },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Implements `From` for the errors of `redb`, boxed as they are large.
+macro_rules! impl_from_database_error {
+    ($($error:ty),*) => {
+        $(impl From<$error> for StateError {
+            fn from(error: $error) -> Self {
+                StateError::Database(Box::new(error.into()))
+            }
+        })*
+    };
+}
+
+impl_from_database_error!(
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
+
+/// A class declared in the state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeclaredClass {
+    #[serde(serialize_with = "serialize_biguint")]
+    pub class_hash: BigUint,
+    /// The latest compiled class hash of the class.
+    #[serde(serialize_with = "serialize_biguint")]
+    pub compiled_class_hash: BigUint,
+    /// The update declaring the class first.
+    pub declared_at: u64,
+}
+
+/// A line of a snapshot: the state diffs of an update.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotEntry {
+    update: u64,
+    state_diffs: DataJson,
+}
+
+/// The Starknet state, stored in a [`redb`] database.
+pub struct StateStore {
+    db: Database,
+}
+
+impl StateStore {
+    /// Opens the store at `path`, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StateError> {
+        let db = Database::create(path)?;
+        // Create the tables, so that reads never find them missing
+        let txn = db.begin_write()?;
+        txn.open_table(UPDATES)?;
+        txn.open_table(STORAGE)?;
+        txn.open_table(NONCES)?;
+        txn.open_table(CLASS_HASHES)?;
+        txn.open_table(DECLARED_CLASSES)?;
+        txn.commit()?;
+        Ok(StateStore { db })
+    }
+
+    /// Returns the number of updates applied.
+    pub fn update_count(&self) -> Result<u64, StateError> {
+        let txn = self.db.begin_read()?;
+        Ok(txn.open_table(UPDATES)?.len()?)
+    }
+
+    /// Applies the state diffs of the next update, atomically.
+    ///
+    /// # Returns
+    ///
+    /// The index of the update.
+    pub fn apply(&self, state_diffs: &DataJson) -> Result<u64, StateError> {
+        let txn = self.db.begin_write()?;
+        let update = {
+            let mut updates = txn.open_table(UPDATES)?;
+            let update = updates.len()?;
+            let mut storage = txn.open_table(STORAGE)?;
+            let mut nonces = txn.open_table(NONCES)?;
+            let mut class_hashes = txn.open_table(CLASS_HASHES)?;
+            for contract in &state_diffs.state_update {
+                let address = to_felt(&contract.address)?;
+                nonces.insert((address, update), contract.nonce)?;
+                if let Some(class_hash) = &contract.new_class_hash {
+                    class_hashes.insert((address, update), to_felt(class_hash)?)?;
+                }
+                for storage_update in &contract.storage_updates {
+                    storage.insert(
+                        (address, to_felt(&storage_update.key)?, update),
+                        to_felt(&storage_update.value)?,
+                    )?;
+                }
+            }
+            let mut declared_classes = txn.open_table(DECLARED_CLASSES)?;
+            for class in &state_diffs.class_declaration {
+                declared_classes.insert(
+                    (to_felt(&class.class_hash)?, update),
+                    to_felt(&class.compiled_class_hash)?,
+                )?;
+            }
+            let json = serde_json::to_string(state_diffs)?;
+            updates.insert(update, json.as_str())?;
+            update
+        };
+        txn.commit()?;
+        Ok(update)
+    }
+
+    /// Returns the state diffs of an update, or `None` if the store does not have it.
+    pub fn state_diffs(&self, update: u64) -> Result<Option<DataJson>, StateError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(UPDATES)?;
+        let json = table.get(update)?;
+        Ok(match json {
+            Some(json) => Some(serde_json::from_str(json.value())?),
+            None => None,
+        })
+    }
+
+    /// Returns the value of a storage slot after the update `at_update`, or `None` if no
+    /// update up to it wrote the slot.
+    pub fn storage_at(
+        &self,
+        address: &BigUint,
+        key: &BigUint,
+        at_update: u64,
+    ) -> Result<Option<BigUint>, StateError> {
+        let (address, key) = (to_felt(address)?, to_felt(key)?);
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(STORAGE)?;
+        let last = table
+            .range((address, key, 0)..=(address, key, at_update))?
+            .next_back()
+            .transpose()?;
+        Ok(last.map(|(_, value)| BigUint::from_bytes_be(&value.value())))
+    }
+
+    /// Returns the nonce of a contract after the update `at_update`, or `None` if no
+    /// update up to it touched the contract.
+    pub fn nonce_at(&self, address: &BigUint, at_update: u64) -> Result<Option<u64>, StateError> {
+        let address = to_felt(address)?;
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(NONCES)?;
+        let last = table
+            .range((address, 0)..=(address, at_update))?
+            .next_back()
+            .transpose()?;
+        Ok(last.map(|(_, nonce)| nonce.value()))
+    }
+
+    /// Returns the class hash of a contract after the update `at_update`, or `None` if no
+    /// update up to it deployed or replaced the contract.
+    pub fn class_hash_at(
+        &self,
+        address: &BigUint,
+        at_update: u64,
+    ) -> Result<Option<BigUint>, StateError> {
+        let address = to_felt(address)?;
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(CLASS_HASHES)?;
+        let last = table
+            .range((address, 0)..=(address, at_update))?
+            .next_back()
+            .transpose()?;
+        Ok(last.map(|(_, class_hash)| BigUint::from_bytes_be(&class_hash.value())))
+    }
+
+    /// Returns the classes declared up to the update `at_update`, by class hash.
+    pub fn declared_classes(&self, at_update: u64) -> Result<Vec<DeclaredClass>, StateError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(DECLARED_CLASSES)?;
+        let mut classes: Vec<DeclaredClass> = Vec::new();
+        // Entries are sorted by class hash, then by update
+        for entry in table.iter()? {
+            let (key, compiled_class_hash) = entry?;
+            let (class_hash, update) = key.value();
+            if update > at_update {
+                continue;
+            }
+            let class_hash = BigUint::from_bytes_be(&class_hash);
+            let compiled_class_hash = BigUint::from_bytes_be(&compiled_class_hash.value());
+            match classes.last_mut() {
+                Some(class) if class.class_hash == class_hash => {
+                    class.compiled_class_hash = compiled_class_hash
+                }
+                _ => classes.push(DeclaredClass {
+                    class_hash,
+                    compiled_class_hash,
+                    declared_at: update,
+                }),
+            }
+        }
+        Ok(classes)
+    }
+
+    /// Writes the state diffs of every update, as JSON lines, from which the store can be
+    /// rebuilt with [`StateStore::import_snapshot`].
+    pub fn export_snapshot(&self, mut out: impl Write) -> Result<(), StateError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(UPDATES)?;
+        for entry in table.iter()? {
+            let (update, state_diffs) = entry?;
+            writeln!(
+                out,
+                r#"{{"update":{},"state_diffs":{}}}"#,
+                update.value(),
+                state_diffs.value()
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Applies the updates of a snapshot written by [`StateStore::export_snapshot`]. The
+    /// updates the store already has are skipped, so a snapshot can extend a store.
+    ///
+    /// # Returns
+    ///
+    /// The number of updates applied.
+    pub fn import_snapshot(&self, input: impl BufRead) -> Result<u64, StateError> {
+        let mut applied = 0;
+        let mut expected = self.update_count()?;
+        for entry in read_snapshot(input) {
+            let (update, state_diffs) = entry?;
+            if update < expected {
+                continue;
+            }
+            if update > expected {
+                return Err(StateError::SnapshotGap {
+                    expected,
+                    actual: update,
+                });
+            }
+            self.apply(&state_diffs)?;
+            applied += 1;
+            expected += 1;
+        }
+        Ok(applied)
+    }
+}
+
+/// Reads the updates of a snapshot written by [`StateStore::export_snapshot`].
+///
+/// # Returns
+///
+/// The index and the state diffs of each update, in the order of the snapshot.
+pub fn read_snapshot(
+    input: impl BufRead,
+) -> impl Iterator<Item = Result<(u64, DataJson), StateError>> {
+    input
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            let entry: SnapshotEntry = serde_json::from_str(&line?)?;
+            Ok((entry.update, entry.state_diffs))
+        })
+}
+
+fn to_felt(value: &BigUint) -> Result<Felt, StateError> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > 32 {
+        return Err(StateError::FeltTooLarge(value.clone()));
+    }
+    let mut felt = [0u8; 32];
+    felt[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(felt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin_blob_types::state_diffs::{ClassDeclaration, ContractUpdate, StorageUpdate};
+
+    fn contract_update(
+        address: u32,
+        nonce: u64,
+        class_hash: Option<u32>,
+        storage: &[(u32, u32)],
+    ) -> ContractUpdate {
+        ContractUpdate {
+            address: BigUint::from(address),
+            nonce,
+            number_of_storage_updates: storage.len() as u64,
+            new_class_hash: class_hash.map(BigUint::from),
+            storage_updates: storage
+                .iter()
+                .map(|(key, value)| StorageUpdate {
+                    key: BigUint::from(*key),
+                    value: BigUint::from(*value),
+                })
+                .collect(),
+        }
+    }
+
+    fn state_diffs(
+        state_update: Vec<ContractUpdate>,
+        class_declaration: &[(u32, u32)],
+    ) -> DataJson {
+        DataJson {
+            state_update_size: state_update.len() as u64,
+            state_update,
+            class_declaration_size: class_declaration.len() as u64,
+            class_declaration: class_declaration
+                .iter()
+                .map(|(class_hash, compiled_class_hash)| ClassDeclaration {
+                    class_hash: BigUint::from(*class_hash),
+                    compiled_class_hash: BigUint::from(*compiled_class_hash),
+                })
+                .collect(),
+        }
+    }
+
+    fn history() -> Vec<DataJson> {
+        vec![
+            state_diffs(
+                vec![contract_update(1, 0, Some(100), &[(10, 5), (11, 6)])],
+                &[(100, 1000)],
+            ),
+            state_diffs(
+                vec![
+                    contract_update(1, 1, None, &[(10, 7)]),
+                    contract_update(2, 0, Some(200), &[]),
+                ],
+                &[(200, 2000)],
+            ),
+            state_diffs(vec![contract_update(1, 2, Some(200), &[])], &[(100, 1001)]),
+        ]
+    }
+
+    #[test]
+    fn test_point_in_time_queries() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::open(dir.path().join("state.redb")).unwrap();
+        for (i, update) in history().iter().enumerate() {
+            assert_eq!(store.apply(update).unwrap(), i as u64);
+        }
+        assert_eq!(store.update_count().unwrap(), 3);
+
+        let felt = |value: u32| BigUint::from(value);
+        assert_eq!(
+            store.storage_at(&felt(1), &felt(10), 0).unwrap(),
+            Some(felt(5))
+        );
+        assert_eq!(
+            store.storage_at(&felt(1), &felt(10), 1).unwrap(),
+            Some(felt(7))
+        );
+        assert_eq!(
+            store.storage_at(&felt(1), &felt(11), 2).unwrap(),
+            Some(felt(6))
+        );
+        assert_eq!(store.storage_at(&felt(1), &felt(12), 2).unwrap(), None);
+        assert_eq!(store.nonce_at(&felt(1), 0).unwrap(), Some(0));
+        assert_eq!(store.nonce_at(&felt(1), 2).unwrap(), Some(2));
+        assert_eq!(store.nonce_at(&felt(2), 0).unwrap(), None);
+        assert_eq!(store.class_hash_at(&felt(1), 1).unwrap(), Some(felt(100)));
+        assert_eq!(store.class_hash_at(&felt(1), 2).unwrap(), Some(felt(200)));
+
+        assert_eq!(store.declared_classes(0).unwrap().len(), 1);
+        assert_eq!(
+            store.declared_classes(2).unwrap(),
+            vec![
+                DeclaredClass {
+                    class_hash: felt(100),
+                    compiled_class_hash: felt(1001),
+                    declared_at: 0,
+                },
+                DeclaredClass {
+                    class_hash: felt(200),
+                    compiled_class_hash: felt(2000),
+                    declared_at: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::open(dir.path().join("state.redb")).unwrap();
+        for update in history() {
+            store.apply(&update).unwrap();
+        }
+        let mut snapshot = Vec::new();
+        store.export_snapshot(&mut snapshot).unwrap();
+
+        // A store with the first update is extended with the others
+        let imported = StateStore::open(dir.path().join("imported.redb")).unwrap();
+        imported.apply(&history()[0]).unwrap();
+        assert_eq!(imported.import_snapshot(snapshot.as_slice()).unwrap(), 2);
+        let mut reexported = Vec::new();
+        imported.export_snapshot(&mut reexported).unwrap();
+        assert_eq!(reexported, snapshot);
+
+        // The updates of a snapshot must follow the updates of the store
+        let lines: Vec<&[u8]> = snapshot.split(|b| *b == b'\n').collect();
+        let empty = StateStore::open(dir.path().join("empty.redb")).unwrap();
+        assert!(matches!(
+            empty.import_snapshot(lines[1]),
+            Err(StateError::SnapshotGap {
+                expected: 0,
+                actual: 1
+            })
+        ));
+    }
+}
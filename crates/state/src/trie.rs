@@ -0,0 +1,159 @@
+//! Roots of the binary Merkle-Patricia tries committing to the Starknet state.
+//!
+//! The tries have a height of 251 and are keyed by felts. A node is a leaf, a binary node
+//! hashing its two children, or an edge node skipping a path of bits down to a single
+//! child. The hash of an edge node is `H(child, path) + length`.
+
+use starknet_crypto::FieldElement;
+
+/// Height of the Starknet tries, the number of bits of their keys.
+pub const TRIE_HEIGHT: usize = 251;
+
+/// The hash function of a trie.
+pub trait TrieHash {
+    fn hash(x: &FieldElement, y: &FieldElement) -> FieldElement;
+}
+
+/// The Pedersen hash, used by the storage tries and the contracts trie.
+pub struct Pedersen;
+
+impl TrieHash for Pedersen {
+    fn hash(x: &FieldElement, y: &FieldElement) -> FieldElement {
+        starknet_crypto::pedersen_hash(x, y)
+    }
+}
+
+/// The Poseidon hash, used by the classes trie.
+pub struct Poseidon;
+
+impl TrieHash for Poseidon {
+    fn hash(x: &FieldElement, y: &FieldElement) -> FieldElement {
+        starknet_crypto::poseidon_hash(*x, *y)
+    }
+}
+
+/// A subtree: the hash of its top binary node or leaf, below an edge of `len` bits.
+struct Subtree {
+    bottom: FieldElement,
+    path: [u8; 32],
+    len: usize,
+}
+
+impl Subtree {
+    fn hash<H: TrieHash>(&self) -> FieldElement {
+        match self.len {
+            0 => self.bottom,
+            len => {
+                let path = FieldElement::from_bytes_be(&self.path).unwrap();
+                H::hash(&self.bottom, &path) + FieldElement::from(len as u64)
+            }
+        }
+    }
+}
+
+/// Computes the root of a trie.
+///
+/// # Arguments
+///
+/// * `leaves` - The keys, as 32 big-endian bytes, and the values of the leaves, sorted by
+///   key without duplicates. Zero values are not part of the trie.
+///
+/// # Returns
+///
+/// The root, zero for an empty trie.
+pub fn trie_root<H: TrieHash>(leaves: &[([u8; 32], FieldElement)]) -> FieldElement {
+    let leaves: Vec<&([u8; 32], FieldElement)> = leaves
+        .iter()
+        .filter(|(_, value)| *value != FieldElement::ZERO)
+        .collect();
+    match leaves.is_empty() {
+        true => FieldElement::ZERO,
+        false => subtree::<H>(&leaves, 0).hash::<H>(),
+    }
+}
+
+/// Builds the subtree of non-empty `leaves`, which share their first `depth` bits.
+fn subtree<H: TrieHash>(leaves: &[&([u8; 32], FieldElement)], depth: usize) -> Subtree {
+    if depth == TRIE_HEIGHT {
+        return Subtree {
+            bottom: leaves[0].1,
+            path: [0; 32],
+            len: 0,
+        };
+    }
+    let split = leaves.partition_point(|(key, _)| !bit(key, depth));
+    let (left, right) = leaves.split_at(split);
+    match (left.is_empty(), right.is_empty()) {
+        (false, false) => Subtree {
+            bottom: H::hash(
+                &subtree::<H>(left, depth + 1).hash::<H>(),
+                &subtree::<H>(right, depth + 1).hash::<H>(),
+            ),
+            path: [0; 32],
+            len: 0,
+        },
+        // The node has a single child, the edge above it grows by a bit
+        (true, _) | (_, true) => {
+            let mut child = subtree::<H>(leaves, depth + 1);
+            if right.len() == leaves.len() {
+                child.path[31 - child.len / 8] |= 1 << (child.len % 8);
+            }
+            child.len += 1;
+            child
+        }
+    }
+}
+
+/// Returns the bit of a key at `depth`, from the root.
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    // The 251 bits of the key are the low bits of its 256 bits
+    let index = 256 - TRIE_HEIGHT + depth;
+    key[index / 8] & (0x80 >> (index % 8)) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(value: u64) -> [u8; 32] {
+        FieldElement::from(value).to_bytes_be()
+    }
+
+    fn felt(value: u64) -> FieldElement {
+        FieldElement::from(value)
+    }
+
+    #[test]
+    fn test_single_leaf() {
+        // An edge from the root to the leaf, whose path is the key
+        assert_eq!(
+            trie_root::<Pedersen>(&[(key(5), felt(7))]),
+            Pedersen::hash(&felt(7), &felt(5)) + felt(251)
+        );
+        assert_eq!(trie_root::<Pedersen>(&[]), FieldElement::ZERO);
+        assert_eq!(
+            trie_root::<Pedersen>(&[(key(5), felt(0))]),
+            FieldElement::ZERO
+        );
+    }
+
+    #[test]
+    fn test_binary_node() {
+        // Keys 2 and 3 share their first 250 bits
+        let bottom = Pedersen::hash(&felt(10), &felt(11));
+        assert_eq!(
+            trie_root::<Pedersen>(&[(key(2), felt(10)), (key(3), felt(11))]),
+            Pedersen::hash(&bottom, &felt(1)) + felt(250)
+        );
+
+        // Keys 0 and 2^250 differ by their first bit, each is below an edge of 250 bits
+        let mut high = [0u8; 32];
+        high[0] = 0x04;
+        let left = Poseidon::hash(&felt(10), &felt(0)) + felt(250);
+        let right = Poseidon::hash(&felt(11), &felt(0)) + felt(250);
+        assert_eq!(
+            trie_root::<Poseidon>(&[(key(0), felt(10)), (high, felt(11))]),
+            Poseidon::hash(&left, &right)
+        );
+    }
+}
@@ -0,0 +1,727 @@
+//! Solidity ABI decoding of the calls and events of the Starknet core contract.
+
+use majin_blob_eip_4844::transaction::keccak256;
+use num_bigint::BigUint;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::state_diffs::serialize_biguint;
+
+/// Size of an ABI word, in bytes.
+const WORD_LEN: usize = 32;
+
+/// Address of the Starknet core contract on Ethereum mainnet.
+pub const STARKNET_CORE_CONTRACT: [u8; 20] = [
+    0xc6, 0x62, 0xc4, 0x10, 0xc0, 0xec, 0xf7, 0x47, 0x54, 0x3f, 0x5b, 0xa9, 0x06, 0x60, 0xf6, 0xab,
+    0xeb, 0xd9, 0xc8, 0xc4,
+];
+
+/// Signature of the function publishing a state update with its data in blobs.
+pub const UPDATE_STATE_KZG_DA: &str = "updateStateKzgDA(uint256[],bytes[])";
+/// Signature of the function publishing a state update with its data in calldata, before
+/// Starknet 0.13.1.
+pub const UPDATE_STATE: &str = "updateState(uint256[],uint256,uint256)";
+/// Signature of the function of the memory page fact registry registering a continuous
+/// memory page. Before blobs, the state diffs were published in such pages.
+pub const REGISTER_CONTINUOUS_MEMORY_PAGE: &str =
+    "registerContinuousMemoryPage(uint256,uint256[],uint256,uint256,uint256)";
+/// Signature of the event emitted for every state update.
+pub const LOG_STATE_UPDATE: &str = "LogStateUpdate(uint256,int256,uint256)";
+/// Signature of the event emitted for every message sent from L2 to L1.
+pub const LOG_MESSAGE_TO_L1: &str = "LogMessageToL1(uint256,address,uint256[])";
+/// Signature of the event emitted for every message sent from L1 to L2.
+pub const LOG_MESSAGE_TO_L2: &str =
+    "LogMessageToL2(address,uint256,uint256,uint256[],uint256,uint256)";
+/// Signature of the event emitted by the memory page fact registry for every continuous
+/// memory page.
+pub const LOG_MEMORY_PAGE_FACT_CONTINUOUS: &str =
+    "LogMemoryPageFactContinuous(bytes32,uint256,uint256)";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AbiError {
+    #[error("calldata does not call {0}")]
+    UnknownSelector(&'static str),
+    #[error("ABI data ends unexpectedly")]
+    UnexpectedEnd,
+    #[error("ABI value at byte {0} does not fit its type")]
+    InvalidValue(usize),
+    #[error("expected {expected} topics in the {event} event, got {actual}")]
+    TopicCount {
+        event: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("invalid log: {0}")]
+    InvalidLog(String),
+}
+
+/// Returns the 4-byte selector of a function signature.
+pub fn function_selector(signature: &str) -> [u8; 4] {
+    keccak256(signature.as_bytes())[..4].try_into().unwrap()
+}
+
+/// Returns the first topic of the logs of an event signature.
+pub fn event_topic(signature: &str) -> [u8; 32] {
+    keccak256(signature.as_bytes())
+}
+
+/// The arguments of an `updateStateKzgDA` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateStateKzgDa {
+    /// The output of the Starknet OS, as felts.
+    pub program_output: Vec<BigUint>,
+    /// The KZG proofs of the evaluation of each blob at the point of the OS output.
+    pub kzg_proofs: Vec<Vec<u8>>,
+}
+
+impl UpdateStateKzgDa {
+    /// Decodes the input of a transaction calling `updateStateKzgDA`.
+    pub fn decode(input: &[u8]) -> Result<Self, AbiError> {
+        match input.split_at_checked(4) {
+            Some((selector, args)) if *selector == function_selector(UPDATE_STATE_KZG_DA) => {
+                let decoder = Decoder(args);
+                Ok(UpdateStateKzgDa {
+                    program_output: decoder.uint_array(decoder.offset(0)?)?,
+                    kzg_proofs: decoder.bytes_array(decoder.offset(WORD_LEN)?)?,
+                })
+            }
+            _ => Err(AbiError::UnknownSelector(UPDATE_STATE_KZG_DA)),
+        }
+    }
+
+    /// Encodes the call, as the input of a transaction.
+    ///
+    /// # Panics
+    ///
+    /// If a value does not fit in 32 bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let program_output = encode_uint_array(&self.program_output);
+        let kzg_proofs = encode_bytes_array(&self.kzg_proofs);
+        let mut out = function_selector(UPDATE_STATE_KZG_DA).to_vec();
+        out.extend(encode_uint(&BigUint::from(2 * WORD_LEN)));
+        out.extend(encode_uint(&BigUint::from(
+            2 * WORD_LEN + program_output.len(),
+        )));
+        out.extend(program_output);
+        out.extend(kzg_proofs);
+        out
+    }
+}
+
+/// The arguments of an `updateState` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateState {
+    /// The output of the Starknet OS, as felts.
+    pub program_output: Vec<BigUint>,
+    /// The hash of the data published in memory pages rather than in the program output.
+    pub onchain_data_hash: BigUint,
+    /// The number of felts of the data published in memory pages.
+    pub onchain_data_size: BigUint,
+}
+
+impl UpdateState {
+    /// Decodes the input of a transaction calling `updateState`.
+    pub fn decode(input: &[u8]) -> Result<Self, AbiError> {
+        match input.split_at_checked(4) {
+            Some((selector, args)) if *selector == function_selector(UPDATE_STATE) => {
+                let decoder = Decoder(args);
+                Ok(UpdateState {
+                    program_output: decoder.uint_array(decoder.offset(0)?)?,
+                    onchain_data_hash: decoder.uint(WORD_LEN)?,
+                    onchain_data_size: decoder.uint(2 * WORD_LEN)?,
+                })
+            }
+            _ => Err(AbiError::UnknownSelector(UPDATE_STATE)),
+        }
+    }
+
+    /// Encodes the call, as the input of a transaction.
+    ///
+    /// # Panics
+    ///
+    /// If a value does not fit in 32 bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = function_selector(UPDATE_STATE).to_vec();
+        out.extend(encode_uint(&BigUint::from(3 * WORD_LEN)));
+        out.extend(encode_uint(&self.onchain_data_hash));
+        out.extend(encode_uint(&self.onchain_data_size));
+        out.extend(encode_uint_array(&self.program_output));
+        out
+    }
+}
+
+/// The arguments of a `registerContinuousMemoryPage` call: a page of `values` stored from
+/// `start_address` in the memory of a proven program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuousMemoryPage {
+    pub start_address: BigUint,
+    pub values: Vec<BigUint>,
+    pub z: BigUint,
+    pub alpha: BigUint,
+    pub prime: BigUint,
+}
+
+impl ContinuousMemoryPage {
+    /// Decodes the input of a transaction calling `registerContinuousMemoryPage`.
+    pub fn decode(input: &[u8]) -> Result<Self, AbiError> {
+        match input.split_at_checked(4) {
+            Some((selector, args))
+                if *selector == function_selector(REGISTER_CONTINUOUS_MEMORY_PAGE) =>
+            {
+                let decoder = Decoder(args);
+                Ok(ContinuousMemoryPage {
+                    start_address: decoder.uint(0)?,
+                    values: decoder.uint_array(decoder.offset(WORD_LEN)?)?,
+                    z: decoder.uint(2 * WORD_LEN)?,
+                    alpha: decoder.uint(3 * WORD_LEN)?,
+                    prime: decoder.uint(4 * WORD_LEN)?,
+                })
+            }
+            _ => Err(AbiError::UnknownSelector(REGISTER_CONTINUOUS_MEMORY_PAGE)),
+        }
+    }
+
+    /// Encodes the call, as the input of a transaction.
+    ///
+    /// # Panics
+    ///
+    /// If a value does not fit in 32 bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = function_selector(REGISTER_CONTINUOUS_MEMORY_PAGE).to_vec();
+        out.extend(encode_uint(&self.start_address));
+        out.extend(encode_uint(&BigUint::from(5 * WORD_LEN)));
+        out.extend(encode_uint(&self.z));
+        out.extend(encode_uint(&self.alpha));
+        out.extend(encode_uint(&self.prime));
+        out.extend(encode_uint_array(&self.values));
+        out
+    }
+
+    /// Returns the memory hash of the page, as in the `LogMemoryPageFactContinuous` event
+    /// registering it.
+    ///
+    /// # Panics
+    ///
+    /// If a value does not fit in 32 bytes.
+    pub fn memory_hash(&self) -> [u8; 32] {
+        let data: Vec<u8> = self.values.iter().flat_map(encode_uint).collect();
+        keccak256(&data)
+    }
+}
+
+/// A `LogMemoryPageFactContinuous` event, registering a continuous memory page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryPageFact {
+    pub fact_hash: [u8; 32],
+    pub memory_hash: [u8; 32],
+    pub prod: BigUint,
+}
+
+impl MemoryPageFact {
+    /// Decodes a log of the memory page fact registry.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the log is not a `LogMemoryPageFactContinuous` event.
+    pub fn decode(log: &Log) -> Result<Option<Self>, AbiError> {
+        if log.topics.first() != Some(&event_topic(LOG_MEMORY_PAGE_FACT_CONTINUOUS)) {
+            return Ok(None);
+        }
+        check_topics(log, "LogMemoryPageFactContinuous", 1)?;
+        let data = Decoder(&log.data);
+        Ok(Some(MemoryPageFact {
+            fact_hash: data.word(0)?.try_into().unwrap(),
+            memory_hash: data.word(WORD_LEN)?.try_into().unwrap(),
+            prod: data.uint(2 * WORD_LEN)?,
+        }))
+    }
+}
+
+/// A log emitted by a contract, as found in transaction receipts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub address: [u8; 20],
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+/// Parses the logs of a receipt, given as an `eth_getTransactionReceipt` response, the
+/// receipt itself or its `logs` array.
+pub fn parse_logs_json(data: &str) -> Result<Vec<Log>, AbiError> {
+    let value: Value =
+        serde_json::from_str(data).map_err(|e| AbiError::InvalidLog(e.to_string()))?;
+    let receipt = value.get("result").unwrap_or(&value);
+    let logs = match receipt.get("logs").unwrap_or(receipt) {
+        Value::Array(logs) => logs,
+        _ => return Err(AbiError::InvalidLog("missing logs".to_string())),
+    };
+    logs.iter()
+        .map(|log| {
+            Ok(Log {
+                address: parse_hex(&log["address"], "address")?,
+                topics: match &log["topics"] {
+                    Value::Array(topics) => topics
+                        .iter()
+                        .map(|topic| parse_hex(topic, "topics"))
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err(AbiError::InvalidLog("missing topics".to_string())),
+                },
+                data: parse_hex(&log["data"], "data")?,
+            })
+        })
+        .collect()
+}
+
+/// An event of the Starknet core contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoreContractEvent {
+    LogStateUpdate {
+        #[serde(serialize_with = "serialize_biguint")]
+        global_root: BigUint,
+        block_number: i64,
+        #[serde(serialize_with = "serialize_biguint")]
+        block_hash: BigUint,
+    },
+    LogMessageToL1 {
+        #[serde(serialize_with = "serialize_biguint")]
+        from_address: BigUint,
+        #[serde(serialize_with = "serialize_address")]
+        to_address: [u8; 20],
+        #[serde(serialize_with = "serialize_biguints")]
+        payload: Vec<BigUint>,
+    },
+    LogMessageToL2 {
+        #[serde(serialize_with = "serialize_address")]
+        from_address: [u8; 20],
+        #[serde(serialize_with = "serialize_biguint")]
+        to_address: BigUint,
+        #[serde(serialize_with = "serialize_biguint")]
+        selector: BigUint,
+        #[serde(serialize_with = "serialize_biguints")]
+        payload: Vec<BigUint>,
+        #[serde(serialize_with = "serialize_biguint")]
+        nonce: BigUint,
+        #[serde(serialize_with = "serialize_biguint")]
+        fee: BigUint,
+    },
+}
+
+impl CoreContractEvent {
+    /// Decodes a log of the Starknet core contract.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the log is not one of the events of [`CoreContractEvent`].
+    pub fn decode(log: &Log) -> Result<Option<Self>, AbiError> {
+        let Some(topic) = log.topics.first() else {
+            return Ok(None);
+        };
+        let data = Decoder(&log.data);
+        let event = if *topic == event_topic(LOG_STATE_UPDATE) {
+            check_topics(log, "LogStateUpdate", 1)?;
+            CoreContractEvent::LogStateUpdate {
+                global_root: data.uint(0)?,
+                block_number: data.int64(WORD_LEN)?,
+                block_hash: data.uint(2 * WORD_LEN)?,
+            }
+        } else if *topic == event_topic(LOG_MESSAGE_TO_L1) {
+            check_topics(log, "LogMessageToL1", 3)?;
+            CoreContractEvent::LogMessageToL1 {
+                from_address: BigUint::from_bytes_be(&log.topics[1]),
+                to_address: topic_address(&log.topics[2])?,
+                payload: data.uint_array(data.offset(0)?)?,
+            }
+        } else if *topic == event_topic(LOG_MESSAGE_TO_L2) {
+            check_topics(log, "LogMessageToL2", 4)?;
+            CoreContractEvent::LogMessageToL2 {
+                from_address: topic_address(&log.topics[1])?,
+                to_address: BigUint::from_bytes_be(&log.topics[2]),
+                selector: BigUint::from_bytes_be(&log.topics[3]),
+                payload: data.uint_array(data.offset(0)?)?,
+                nonce: data.uint(WORD_LEN)?,
+                fee: data.uint(2 * WORD_LEN)?,
+            }
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(event))
+    }
+}
+
+/// Decodes the events of the Starknet core contract among logs, skipping the logs of
+/// other contracts and the other events.
+pub fn decode_core_contract_events(
+    logs: &[Log],
+    contract: &[u8; 20],
+) -> Result<Vec<CoreContractEvent>, AbiError> {
+    logs.iter()
+        .filter(|log| log.address == *contract)
+        .filter_map(|log| CoreContractEvent::decode(log).transpose())
+        .collect()
+}
+
+/// Reads the head of ABI-encoded data. Positions are relative to the start of the data.
+struct Decoder<'a>(&'a [u8]);
+
+impl Decoder<'_> {
+    fn word(&self, position: usize) -> Result<&[u8], AbiError> {
+        let end = position
+            .checked_add(WORD_LEN)
+            .ok_or(AbiError::InvalidValue(position))?;
+        self.0.get(position..end).ok_or(AbiError::UnexpectedEnd)
+    }
+
+    fn uint(&self, position: usize) -> Result<BigUint, AbiError> {
+        Ok(BigUint::from_bytes_be(self.word(position)?))
+    }
+
+    fn usize(&self, position: usize) -> Result<usize, AbiError> {
+        let word = self.word(position)?;
+        let (high, low) = word.split_at(WORD_LEN - 8);
+        if high.iter().any(|b| *b != 0) {
+            return Err(AbiError::InvalidValue(position));
+        }
+        usize::try_from(u64::from_be_bytes(low.try_into().unwrap()))
+            .map_err(|_| AbiError::InvalidValue(position))
+    }
+
+    fn int64(&self, position: usize) -> Result<i64, AbiError> {
+        let word = self.word(position)?;
+        let (high, low) = word.split_at(WORD_LEN - 8);
+        let value = i64::from_be_bytes(low.try_into().unwrap());
+        let sign = if value < 0 { 0xff } else { 0 };
+        if high.iter().any(|b| *b != sign) {
+            return Err(AbiError::InvalidValue(position));
+        }
+        Ok(value)
+    }
+
+    /// Reads the offset of a dynamic value stored at `position`.
+    fn offset(&self, position: usize) -> Result<usize, AbiError> {
+        self.usize(position)
+    }
+
+    fn uint_array(&self, position: usize) -> Result<Vec<BigUint>, AbiError> {
+        let len = self.usize(position)?;
+        (0..len)
+            .map(|i| {
+                let element = WORD_LEN
+                    .checked_mul(i + 1)
+                    .and_then(|offset| position.checked_add(offset))
+                    .ok_or(AbiError::InvalidValue(position))?;
+                self.uint(element)
+            })
+            .collect()
+    }
+
+    fn bytes(&self, position: usize) -> Result<Vec<u8>, AbiError> {
+        let len = self.usize(position)?;
+        let start = position
+            .checked_add(WORD_LEN)
+            .ok_or(AbiError::InvalidValue(position))?;
+        self.0
+            .get(start..start.saturating_add(len))
+            .map(<[u8]>::to_vec)
+            .ok_or(AbiError::UnexpectedEnd)
+    }
+
+    fn bytes_array(&self, position: usize) -> Result<Vec<Vec<u8>>, AbiError> {
+        let len = self.usize(position)?;
+        let start = position
+            .checked_add(WORD_LEN)
+            .ok_or(AbiError::InvalidValue(position))?;
+        // The offsets of the elements are relative to the start of the element heads
+        let elements = Decoder(self.0.get(start..).unwrap_or_default());
+        (0..len)
+            .map(|i| {
+                let head = WORD_LEN
+                    .checked_mul(i)
+                    .ok_or(AbiError::InvalidValue(position))?;
+                elements.bytes(elements.offset(head)?)
+            })
+            .collect()
+    }
+}
+
+fn check_topics(log: &Log, event: &'static str, expected: usize) -> Result<(), AbiError> {
+    if log.topics.len() != expected {
+        return Err(AbiError::TopicCount {
+            event,
+            expected,
+            actual: log.topics.len(),
+        });
+    }
+    Ok(())
+}
+
+fn topic_address(topic: &[u8; 32]) -> Result<[u8; 20], AbiError> {
+    if topic[..12].iter().any(|b| *b != 0) {
+        return Err(AbiError::InvalidLog("invalid address topic".to_string()));
+    }
+    Ok(topic[12..].try_into().unwrap())
+}
+
+fn parse_hex<T: TryFrom<Vec<u8>>>(value: &Value, field: &str) -> Result<T, AbiError> {
+    value
+        .as_str()
+        .and_then(|value| hex::decode(value.trim_start_matches("0x")).ok())
+        .and_then(|bytes| T::try_from(bytes).ok())
+        .ok_or_else(|| AbiError::InvalidLog(format!("invalid or missing field {}", field)))
+}
+
+/// Encodes a value as an ABI word.
+///
+/// # Panics
+///
+/// If the value does not fit in 32 bytes.
+fn encode_uint(value: &BigUint) -> [u8; WORD_LEN] {
+    let bytes = value.to_bytes_be();
+    assert!(
+        bytes.len() <= WORD_LEN,
+        "ABI words hold at most {} bytes, got {}",
+        WORD_LEN,
+        bytes.len()
+    );
+    let mut word = [0u8; WORD_LEN];
+    word[WORD_LEN - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
+
+fn encode_uint_array(values: &[BigUint]) -> Vec<u8> {
+    let mut out = encode_uint(&BigUint::from(values.len())).to_vec();
+    values
+        .iter()
+        .for_each(|value| out.extend(encode_uint(value)));
+    out
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = encode_uint(&BigUint::from(bytes.len())).to_vec();
+    out.extend(bytes);
+    out.resize(
+        out.len() + (WORD_LEN - bytes.len() % WORD_LEN) % WORD_LEN,
+        0,
+    );
+    out
+}
+
+fn encode_bytes_array(values: &[Vec<u8>]) -> Vec<u8> {
+    let tails: Vec<Vec<u8>> = values.iter().map(|value| encode_bytes(value)).collect();
+    let mut out = encode_uint(&BigUint::from(values.len())).to_vec();
+    let mut offset = WORD_LEN * values.len();
+    for tail in &tails {
+        out.extend(encode_uint(&BigUint::from(offset)));
+        offset += tail.len();
+    }
+    tails.iter().for_each(|tail| out.extend(tail));
+    out
+}
+
+fn serialize_address<S>(address: &[u8; 20], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format!("0x{}", hex::encode(address)))
+}
+
+fn serialize_biguints<S>(values: &[BigUint], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(values.iter().map(|value| value.to_str_radix(10)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn word(value: u64) -> [u8; 32] {
+        encode_uint(&BigUint::from(value))
+    }
+
+    #[test]
+    fn test_update_state_kzg_da_round_trip() {
+        let call = UpdateStateKzgDa {
+            program_output: (0..12u32).map(BigUint::from).collect(),
+            kzg_proofs: vec![vec![0xaa; 48], vec![0xbb; 48]],
+        };
+        let input = call.encode();
+        assert_eq!(input[..4], function_selector(UPDATE_STATE_KZG_DA));
+        assert_eq!(UpdateStateKzgDa::decode(&input), Ok(call));
+
+        // The last proof is followed by 16 bytes of padding
+        assert_eq!(
+            UpdateStateKzgDa::decode(&input[..input.len() - 17]),
+            Err(AbiError::UnexpectedEnd)
+        );
+        let mut other_call = input.clone();
+        other_call[0] ^= 1;
+        assert_eq!(
+            UpdateStateKzgDa::decode(&other_call),
+            Err(AbiError::UnknownSelector(UPDATE_STATE_KZG_DA))
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_overflowing_offsets() {
+        let mut input = function_selector(UPDATE_STATE_KZG_DA).to_vec();
+        input.extend(word(u64::MAX));
+        input.extend(word(2 * WORD_LEN as u64));
+        assert!(matches!(
+            UpdateStateKzgDa::decode(&input),
+            Err(AbiError::InvalidValue(_))
+        ));
+
+        // The length of the proofs would end past the last address
+        let mut input = function_selector(UPDATE_STATE_KZG_DA).to_vec();
+        input.extend(word(2 * WORD_LEN as u64));
+        input.extend(word(u64::MAX - WORD_LEN as u64 + 1));
+        input.extend(word(0));
+        assert!(matches!(
+            UpdateStateKzgDa::decode(&input),
+            Err(AbiError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "ABI words hold at most 32 bytes")]
+    fn test_encode_uint_rejects_wide_values() {
+        encode_uint(&(BigUint::from(1u32) << 256u32));
+    }
+
+    #[test]
+    fn test_legacy_calls_round_trip() {
+        let call = UpdateState {
+            program_output: (0..7u32).map(BigUint::from).collect(),
+            onchain_data_hash: BigUint::from(0xabcdu32),
+            onchain_data_size: BigUint::from(12u32),
+        };
+        let input = call.encode();
+        assert_eq!(input[..4], function_selector(UPDATE_STATE));
+        assert_eq!(UpdateState::decode(&input), Ok(call));
+
+        let page = ContinuousMemoryPage {
+            start_address: BigUint::from(1000u32),
+            values: (0..3u32).map(BigUint::from).collect(),
+            z: BigUint::from(5u32),
+            alpha: BigUint::from(6u32),
+            prime: BigUint::from(7u32),
+        };
+        let input = page.encode();
+        assert_eq!(ContinuousMemoryPage::decode(&input), Ok(page.clone()));
+        assert_eq!(
+            UpdateState::decode(&input),
+            Err(AbiError::UnknownSelector(UPDATE_STATE))
+        );
+
+        let log = Log {
+            address: [0xfe; 20],
+            topics: vec![event_topic(LOG_MEMORY_PAGE_FACT_CONTINUOUS)],
+            data: [[0x11; 32], page.memory_hash(), word(9)].concat(),
+        };
+        let fact = MemoryPageFact::decode(&log).unwrap().unwrap();
+        assert_eq!(fact.memory_hash, page.memory_hash());
+        assert_eq!(fact.prod, BigUint::from(9u32));
+        assert_eq!(
+            MemoryPageFact::decode(&Log {
+                topics: vec![event_topic(LOG_STATE_UPDATE)],
+                ..log
+            }),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_selector() {
+        // The selector of the ERC-20 `transfer(address,uint256)` function
+        assert_eq!(
+            function_selector("transfer(address,uint256)"),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+    }
+
+    #[test]
+    fn test_decode_events() {
+        let contract = [0xc6; 20];
+        let mut to_address = [0u8; 32];
+        to_address[12..].copy_from_slice(&[0x11; 20]);
+        let mut message_data = word(32).to_vec();
+        message_data.extend(encode_uint_array(&[BigUint::from(7u32)]));
+
+        let logs = vec![
+            Log {
+                address: contract,
+                topics: vec![event_topic(LOG_STATE_UPDATE)],
+                data: [
+                    word(1),
+                    encode_uint(&(BigUint::from(1u32) << 255u32)),
+                    word(3),
+                ]
+                .concat(),
+            },
+            Log {
+                address: contract,
+                topics: vec![event_topic(LOG_MESSAGE_TO_L1), word(5), to_address],
+                data: message_data,
+            },
+            Log {
+                address: [0x01; 20],
+                topics: vec![event_topic(LOG_STATE_UPDATE)],
+                data: Vec::new(),
+            },
+            Log {
+                address: contract,
+                topics: vec![[0xee; 32]],
+                data: Vec::new(),
+            },
+        ];
+        // An int256 of 2^255 does not fit a block number
+        assert_eq!(
+            decode_core_contract_events(&logs, &contract),
+            Err(AbiError::InvalidValue(32))
+        );
+
+        let mut logs = logs;
+        logs[0].data = [word(1), [0xff; 32], word(3)].concat();
+        let events = decode_core_contract_events(&logs, &contract).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                CoreContractEvent::LogStateUpdate {
+                    global_root: BigUint::from(1u32),
+                    block_number: -1,
+                    block_hash: BigUint::from(3u32),
+                },
+                CoreContractEvent::LogMessageToL1 {
+                    from_address: BigUint::from(5u32),
+                    to_address: [0x11; 20],
+                    payload: vec![BigUint::from(7u32)],
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::response(r#"{"jsonrpc":"2.0","id":1,"result":{"logs":[LOG]}}"#)]
+    #[case::receipt(r#"{"status":"0x1","logs":[LOG]}"#)]
+    #[case::logs(r#"[LOG]"#)]
+    fn test_parse_logs_json(#[case] json: &str) {
+        let log = format!(
+            r#"{{"address":"0x{}","topics":["0x{}"],"data":"0x0102"}}"#,
+            "c6".repeat(20),
+            "ab".repeat(32)
+        );
+        let logs = parse_logs_json(&json.replace("LOG", &log)).unwrap();
+        assert_eq!(
+            logs,
+            vec![Log {
+                address: [0xc6; 20],
+                topics: vec![[0xab; 32]],
+                data: vec![1, 2],
+            }]
+        );
+    }
+}
@@ -1,2 +1,7 @@
+pub mod abi;
+pub mod messages;
+pub mod os_output;
 pub mod serde;
+pub mod sidecar;
+pub mod ssz;
 pub mod state_diffs;
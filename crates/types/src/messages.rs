@@ -0,0 +1,243 @@
+//! Messages between L1 and L2, as published in the output of the Starknet OS.
+
+use majin_blob_eip_4844::transaction::keccak256;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::{Serialize, Serializer};
+
+use crate::os_output::OsOutputError;
+use crate::state_diffs::{serialize_biguint, serialize_option_biguint};
+
+/// The direction of a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageDirection {
+    /// A message sent by an L2 contract, to be consumed on L1.
+    L2ToL1,
+    /// A message sent by an L1 contract and handled by L2.
+    L1ToL2,
+}
+
+/// A message between L1 and L2.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Message {
+    pub direction: MessageDirection,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub from_address: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub to_address: BigUint,
+    /// The L1 handler called on L2, for L1 to L2 messages.
+    #[serde(serialize_with = "serialize_option_biguint")]
+    pub selector: Option<BigUint>,
+    /// The nonce given by the core contract, for L1 to L2 messages.
+    #[serde(serialize_with = "serialize_option_biguint")]
+    pub nonce: Option<BigUint>,
+    #[serde(serialize_with = "serialize_payload")]
+    pub payload: Vec<BigUint>,
+    /// The hash of the message, as the core contract computes it.
+    #[serde(serialize_with = "serialize_hash")]
+    pub hash: [u8; 32],
+}
+
+impl Message {
+    /// Builds a message sent from L2 to L1.
+    pub fn l2_to_l1(from_address: BigUint, to_address: BigUint, payload: Vec<BigUint>) -> Self {
+        let mut words = vec![from_address.clone(), to_address.clone()];
+        words.push(BigUint::from(payload.len()));
+        words.extend(payload.iter().cloned());
+        Message {
+            direction: MessageDirection::L2ToL1,
+            from_address,
+            to_address,
+            selector: None,
+            nonce: None,
+            payload,
+            hash: hash_words(&words),
+        }
+    }
+
+    /// Builds a message sent from L1 to L2.
+    pub fn l1_to_l2(
+        from_address: BigUint,
+        to_address: BigUint,
+        nonce: BigUint,
+        selector: BigUint,
+        payload: Vec<BigUint>,
+    ) -> Self {
+        let mut words = vec![
+            from_address.clone(),
+            to_address.clone(),
+            nonce.clone(),
+            selector.clone(),
+        ];
+        words.push(BigUint::from(payload.len()));
+        words.extend(payload.iter().cloned());
+        Message {
+            direction: MessageDirection::L1ToL2,
+            from_address,
+            to_address,
+            selector: Some(selector),
+            nonce: Some(nonce),
+            payload,
+            hash: hash_words(&words),
+        }
+    }
+}
+
+/// The messages of an OS output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Messages {
+    pub to_l1: Vec<Message>,
+    pub to_l2: Vec<Message>,
+}
+
+impl Messages {
+    /// Parses the message segments of an OS output: the messages to L1, then the
+    /// messages to L2, each segment prefixed by its size in felts.
+    ///
+    /// # Returns
+    ///
+    /// The messages and the number of felts read.
+    pub fn parse(output: &[BigUint]) -> Result<(Self, usize), OsOutputError> {
+        let (to_l1, to_l1_len) = parse_segment(output, "messages to L1", |message| {
+            let (header, rest) = split(message, 3)?;
+            let (payload, rest) = split(rest, header[2].to_usize()?)?;
+            let message = Message::l2_to_l1(header[0].clone(), header[1].clone(), payload.to_vec());
+            Some((message, rest))
+        })?;
+        let (to_l2, to_l2_len) =
+            parse_segment(&output[to_l1_len..], "messages to L2", |message| {
+                let (header, rest) = split(message, 5)?;
+                let (payload, rest) = split(rest, header[4].to_usize()?)?;
+                let message = Message::l1_to_l2(
+                    header[0].clone(),
+                    header[1].clone(),
+                    header[2].clone(),
+                    header[3].clone(),
+                    payload.to_vec(),
+                );
+                Some((message, rest))
+            })?;
+        Ok((Messages { to_l1, to_l2 }, to_l1_len + to_l2_len))
+    }
+}
+
+/// Parses a segment of messages prefixed by its size, with `parse_message` returning a
+/// message and the felts following it, or `None` if the segment ends within the message.
+fn parse_segment<F>(
+    output: &[BigUint],
+    segment_name: &'static str,
+    parse_message: F,
+) -> Result<(Vec<Message>, usize), OsOutputError>
+where
+    F: Fn(&[BigUint]) -> Option<(Message, &[BigUint])>,
+{
+    let size = output
+        .first()
+        .ok_or(OsOutputError::Truncated(segment_name))?;
+    let mut segment = size
+        .to_usize()
+        .and_then(|size| output.get(1..size.checked_add(1)?))
+        .ok_or(OsOutputError::Truncated(segment_name))?;
+    let len = 1 + segment.len();
+    let mut messages = Vec::new();
+    while !segment.is_empty() {
+        let (message, rest) =
+            parse_message(segment).ok_or(OsOutputError::InvalidValue(segment_name))?;
+        messages.push(message);
+        segment = rest;
+    }
+    Ok((messages, len))
+}
+
+fn split(felts: &[BigUint], len: usize) -> Option<(&[BigUint], &[BigUint])> {
+    (len <= felts.len()).then(|| felts.split_at(len))
+}
+
+/// Hashes felts as `keccak256(abi.encodePacked(...))` of `uint256` values does.
+fn hash_words(words: &[BigUint]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(32 * words.len());
+    for word in words {
+        let bytes = word.to_bytes_be();
+        data.extend(std::iter::repeat_n(0u8, 32 - bytes.len()));
+        data.extend(bytes);
+    }
+    keccak256(&data)
+}
+
+fn serialize_payload<S>(payload: &[BigUint], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(payload.iter().map(|felt| felt.to_str_radix(10)))
+}
+
+fn serialize_hash<S>(hash: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format!("0x{}", hex::encode(hash)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felts(values: &[u64]) -> Vec<BigUint> {
+        values.iter().map(|value| BigUint::from(*value)).collect()
+    }
+
+    #[test]
+    fn test_parse_messages() {
+        // 2 messages to L1, then 1 message to L2
+        let output = felts(&[7, 1, 2, 0, 3, 4, 1, 5, 7, 10, 11, 12, 13, 2, 14, 15, 99]);
+        let (messages, len) = Messages::parse(&output).unwrap();
+        assert_eq!(len, 16);
+        assert_eq!(messages.to_l1.len(), 2);
+        assert_eq!(messages.to_l1[0].payload, Vec::new());
+        assert_eq!(messages.to_l1[1].from_address, BigUint::from(3u32));
+        assert_eq!(messages.to_l1[1].payload, felts(&[5]));
+        assert_eq!(
+            messages.to_l2,
+            vec![Message::l1_to_l2(
+                BigUint::from(10u32),
+                BigUint::from(11u32),
+                BigUint::from(12u32),
+                BigUint::from(13u32),
+                felts(&[14, 15]),
+            )]
+        );
+
+        // The size of the segment cuts the last message
+        let mut truncated = output.clone();
+        truncated[0] = BigUint::from(6u32);
+        assert_eq!(
+            Messages::parse(&truncated),
+            Err(OsOutputError::InvalidValue("messages to L1"))
+        );
+        assert_eq!(
+            Messages::parse(&output[..10]),
+            Err(OsOutputError::Truncated("messages to L2"))
+        );
+    }
+
+    #[test]
+    fn test_message_hash() {
+        // keccak256(abi.encodePacked(uint256(1), uint256(2), uint256(1), uint256(3)))
+        let message = Message::l2_to_l1(
+            BigUint::from(1u32),
+            BigUint::from(2u32),
+            vec![BigUint::from(3u32)],
+        );
+        let mut data = [0u8; 128];
+        data[31] = 1;
+        data[63] = 2;
+        data[95] = 1;
+        data[127] = 3;
+        assert_eq!(message.hash, keccak256(&data));
+        assert_ne!(
+            message.hash,
+            Message::l2_to_l1(BigUint::from(1u32), BigUint::from(2u32), Vec::new()).hash
+        );
+    }
+}
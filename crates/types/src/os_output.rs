@@ -0,0 +1,374 @@
+//! The output of the Starknet OS, as posted to the core contract with each state update.
+
+use std::fmt;
+use std::str::FromStr;
+
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+use crate::messages::Messages;
+use crate::state_diffs::{serialize_biguint, serialize_option_biguint, DataJson};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum OsOutputError {
+    #[error("the OS output ends within its {0}")]
+    Truncated(&'static str),
+    #[error("the {0} of the OS output is not a valid value")]
+    InvalidValue(&'static str),
+    #[error("unknown Starknet version {0}")]
+    UnknownVersion(String),
+}
+
+/// The layouts of the OS output, named after the Starknet version introducing them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum OsOutputVersion {
+    /// Up to Starknet 0.13.0: the state diff is in the output, there is no KZG segment.
+    V0_13_0,
+    /// Starknet 0.13.1: the `use_kzg_da` flag and the KZG segment are introduced.
+    V0_13_1,
+    /// Starknet 0.13.2 and later: the output covers a range of blocks.
+    V0_13_2,
+}
+
+impl OsOutputVersion {
+    /// Number of felts of the header of the output.
+    pub fn header_size(&self) -> usize {
+        match self {
+            OsOutputVersion::V0_13_0 => 5,
+            OsOutputVersion::V0_13_1 => 6,
+            OsOutputVersion::V0_13_2 => 10,
+        }
+    }
+}
+
+impl FromStr for OsOutputVersion {
+    type Err = OsOutputError;
+
+    /// Parses a Starknet version such as `0.13.1`, returning the layout of its output.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || OsOutputError::UnknownVersion(s.to_string());
+        let numbers = s
+            .trim_start_matches('v')
+            .split('.')
+            .map(|n| n.parse::<u64>().map_err(|_| unknown()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let version = match numbers.as_slice() {
+            [major, minor] => (*major, *minor, 0),
+            [major, minor, patch, ..] => (*major, *minor, *patch),
+            _ => return Err(unknown()),
+        };
+        Ok(match version {
+            v if v < (0, 13, 1) => OsOutputVersion::V0_13_0,
+            (0, 13, 1) => OsOutputVersion::V0_13_1,
+            _ => OsOutputVersion::V0_13_2,
+        })
+    }
+}
+
+impl fmt::Display for OsOutputVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OsOutputVersion::V0_13_0 => write!(f, "0.13.0"),
+            OsOutputVersion::V0_13_1 => write!(f, "0.13.1"),
+            OsOutputVersion::V0_13_2 => write!(f, "0.13.2"),
+        }
+    }
+}
+
+/// The header of the OS output. Fields missing from the layout of older versions are
+/// `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OsOutputHeader {
+    #[serde(serialize_with = "serialize_biguint")]
+    pub previous_state_root: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub new_state_root: BigUint,
+    /// The block before the first block of the output, from Starknet 0.13.2.
+    pub previous_block_number: Option<u64>,
+    /// The last block of the output.
+    pub new_block_number: u64,
+    #[serde(serialize_with = "serialize_option_biguint")]
+    pub previous_block_hash: Option<BigUint>,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub new_block_hash: BigUint,
+    #[serde(serialize_with = "serialize_option_biguint")]
+    pub os_program_hash: Option<BigUint>,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub config_hash: BigUint,
+    /// Whether the state diff is published in blobs, from Starknet 0.13.1.
+    pub use_kzg_da: bool,
+    /// Whether the state diff holds the previous values too, from Starknet 0.13.2.
+    pub full_output: bool,
+}
+
+/// The KZG segment of the OS output, following the header when `use_kzg_da` is set.
+///
+/// Commitments and evaluations are split into two felts, low half first, see
+/// `majin_blob_core::kzg::StarknetKzgClaim`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KzgSegment {
+    /// The point the blobs are evaluated at.
+    #[serde(serialize_with = "serialize_biguint")]
+    pub z: BigUint,
+    #[serde(serialize_with = "serialize_felt_pairs")]
+    pub commitments: Vec<[BigUint; 2]>,
+    #[serde(serialize_with = "serialize_felt_pairs")]
+    pub evaluations: Vec<[BigUint; 2]>,
+}
+
+/// The header, the KZG segment and the messages of the OS output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OsOutput {
+    pub version: OsOutputVersion,
+    pub header: OsOutputHeader,
+    pub kzg: Option<KzgSegment>,
+    pub messages: Messages,
+    /// Number of felts of the header, the KZG segment and the message segments. The state
+    /// diff starts there when the output does not use KZG data availability.
+    pub len: usize,
+}
+
+impl OsOutput {
+    /// Parses the header, the KZG segment and the messages of an OS output.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The OS output, as the `programOutput` of an `updateState` call.
+    /// * `version` - The layout of the output.
+    pub fn parse(output: &[BigUint], version: OsOutputVersion) -> Result<Self, OsOutputError> {
+        let header = output
+            .get(..version.header_size())
+            .ok_or(OsOutputError::Truncated("header"))?;
+        let header = match version {
+            OsOutputVersion::V0_13_0 | OsOutputVersion::V0_13_1 => OsOutputHeader {
+                previous_state_root: header[0].clone(),
+                new_state_root: header[1].clone(),
+                previous_block_number: None,
+                new_block_number: to_u64(&header[2], "block number")?,
+                previous_block_hash: None,
+                new_block_hash: header[3].clone(),
+                os_program_hash: None,
+                config_hash: header[4].clone(),
+                use_kzg_da: match header.get(5) {
+                    Some(use_kzg_da) => to_bool(use_kzg_da, "use_kzg_da flag")?,
+                    None => false,
+                },
+                full_output: false,
+            },
+            OsOutputVersion::V0_13_2 => OsOutputHeader {
+                previous_state_root: header[0].clone(),
+                new_state_root: header[1].clone(),
+                previous_block_number: Some(to_u64(&header[2], "previous block number")?),
+                new_block_number: to_u64(&header[3], "new block number")?,
+                previous_block_hash: Some(header[4].clone()),
+                new_block_hash: header[5].clone(),
+                os_program_hash: Some(header[6].clone()),
+                config_hash: header[7].clone(),
+                use_kzg_da: to_bool(&header[8], "use_kzg_da flag")?,
+                full_output: to_bool(&header[9], "full_output flag")?,
+            },
+        };
+
+        let mut len = version.header_size();
+        let kzg = match header.use_kzg_da {
+            true => {
+                let segment = &output[len..];
+                let (z, n_blobs) = match segment {
+                    [z, n_blobs, ..] => (z.clone(), to_u64(n_blobs, "number of blobs")? as usize),
+                    _ => return Err(OsOutputError::Truncated("KZG segment")),
+                };
+                if n_blobs > segment.len() / 4 {
+                    return Err(OsOutputError::Truncated("KZG segment"));
+                }
+                let pairs = |start: usize| {
+                    segment
+                        .get(start..start + 2 * n_blobs)
+                        .map(|felts| {
+                            felts
+                                .chunks(2)
+                                .map(|pair| [pair[0].clone(), pair[1].clone()])
+                                .collect::<Vec<_>>()
+                        })
+                        .ok_or(OsOutputError::Truncated("KZG segment"))
+                };
+                let commitments = pairs(2)?;
+                let evaluations = pairs(2 + 2 * n_blobs)?;
+                len += 2 + 4 * n_blobs;
+                Some(KzgSegment {
+                    z,
+                    commitments,
+                    evaluations,
+                })
+            }
+            false => None,
+        };
+        let (messages, messages_len) = Messages::parse(&output[len..])?;
+        Ok(OsOutput {
+            version,
+            header,
+            kzg,
+            messages,
+            len: len + messages_len,
+        })
+    }
+
+    /// Attaches the Starknet blocks, the state roots and the messages of the output to the
+    /// state diffs it published.
+    pub fn join(&self, state_diffs: DataJson) -> StateTransition {
+        let header = &self.header;
+        StateTransition {
+            first_block_number: header
+                .previous_block_number
+                .map_or(header.new_block_number, |previous| previous.wrapping_add(1)),
+            last_block_number: header.new_block_number,
+            previous_state_root: header.previous_state_root.clone(),
+            new_state_root: header.new_state_root.clone(),
+            new_block_hash: header.new_block_hash.clone(),
+            messages: self.messages.clone(),
+            state_diffs,
+        }
+    }
+}
+
+/// State diffs, with the Starknet blocks they cover, the state roots they move between
+/// and the messages sent in these blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateTransition {
+    pub first_block_number: u64,
+    pub last_block_number: u64,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub previous_state_root: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub new_state_root: BigUint,
+    #[serde(serialize_with = "serialize_biguint")]
+    pub new_block_hash: BigUint,
+    pub messages: Messages,
+    pub state_diffs: DataJson,
+}
+
+fn to_u64(felt: &BigUint, field: &'static str) -> Result<u64, OsOutputError> {
+    felt.to_u64().ok_or(OsOutputError::InvalidValue(field))
+}
+
+fn to_bool(felt: &BigUint, field: &'static str) -> Result<bool, OsOutputError> {
+    match felt {
+        felt if felt.is_zero() => Ok(false),
+        felt if felt.is_one() => Ok(true),
+        _ => Err(OsOutputError::InvalidValue(field)),
+    }
+}
+
+fn serialize_felt_pairs<S>(pairs: &[[BigUint; 2]], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(
+        pairs
+            .iter()
+            .map(|[low, high]| [low.to_str_radix(10), high.to_str_radix(10)]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn felts(values: &[u64]) -> Vec<BigUint> {
+        values.iter().map(|value| BigUint::from(*value)).collect()
+    }
+
+    fn pair(low: u64, high: u64) -> [BigUint; 2] {
+        [BigUint::from(low), BigUint::from(high)]
+    }
+
+    #[test]
+    fn test_parse_v0_13_2() {
+        // Header, then a KZG segment of 2 blobs, then the message segments
+        let output = felts(&[
+            1, 2, 99, 100, 3, 4, 5, 6, 1, 0, 7, 2, 10, 11, 12, 13, 20, 21, 22, 23, 0, 0,
+        ]);
+        let os_output = OsOutput::parse(&output, OsOutputVersion::V0_13_2).unwrap();
+        assert_eq!(
+            os_output.header,
+            OsOutputHeader {
+                previous_state_root: BigUint::from(1u32),
+                new_state_root: BigUint::from(2u32),
+                previous_block_number: Some(99),
+                new_block_number: 100,
+                previous_block_hash: Some(BigUint::from(3u32)),
+                new_block_hash: BigUint::from(4u32),
+                os_program_hash: Some(BigUint::from(5u32)),
+                config_hash: BigUint::from(6u32),
+                use_kzg_da: true,
+                full_output: false,
+            }
+        );
+        let kzg = os_output.kzg.unwrap();
+        assert_eq!(kzg.z, BigUint::from(7u32));
+        assert_eq!(kzg.commitments, vec![pair(10, 11), pair(12, 13)]);
+        assert_eq!(kzg.evaluations[1], pair(22, 23));
+        assert_eq!(os_output.len, 22);
+        assert_eq!(os_output.messages, Messages::default());
+
+        assert_eq!(
+            OsOutput::parse(&output[..19], OsOutputVersion::V0_13_2),
+            Err(OsOutputError::Truncated("KZG segment"))
+        );
+    }
+
+    #[rstest]
+    #[case::v0_13_0(OsOutputVersion::V0_13_0, &[1, 2, 100, 4, 6, 3, 8, 9, 0, 0], false, 10)]
+    #[case::v0_13_1(OsOutputVersion::V0_13_1, &[1, 2, 100, 4, 6, 1, 7, 0, 3, 8, 9, 0, 0], true, 13)]
+    fn test_parse_previous_versions(
+        #[case] version: OsOutputVersion,
+        #[case] output: &[u64],
+        #[case] use_kzg_da: bool,
+        #[case] len: usize,
+    ) {
+        let os_output = OsOutput::parse(&felts(output), version).unwrap();
+        assert_eq!(os_output.header.new_block_number, 100);
+        assert_eq!(os_output.header.new_block_hash, BigUint::from(4u32));
+        assert_eq!(os_output.header.config_hash, BigUint::from(6u32));
+        assert_eq!(os_output.header.previous_block_number, None);
+        assert_eq!(os_output.header.use_kzg_da, use_kzg_da);
+        assert_eq!(os_output.kzg.is_some(), use_kzg_da);
+        assert_eq!(os_output.len, len);
+        assert_eq!(os_output.messages.to_l1.len(), 1);
+
+        let transition = os_output.join(DataJson {
+            state_update_size: 0,
+            state_update: Vec::new(),
+            class_declaration_size: 0,
+            class_declaration: Vec::new(),
+        });
+        assert_eq!(transition.first_block_number, 100);
+        assert_eq!(transition.last_block_number, 100);
+        assert_eq!(transition.messages, os_output.messages);
+    }
+
+    #[test]
+    fn test_invalid_output() {
+        assert_eq!(
+            OsOutput::parse(&felts(&[1, 2, 3]), OsOutputVersion::V0_13_0),
+            Err(OsOutputError::Truncated("header"))
+        );
+        assert_eq!(
+            OsOutput::parse(&felts(&[1, 2, 100, 4, 6, 2]), OsOutputVersion::V0_13_1),
+            Err(OsOutputError::InvalidValue("use_kzg_da flag"))
+        );
+    }
+
+    #[rstest]
+    #[case("0.12.3", OsOutputVersion::V0_13_0)]
+    #[case("0.13.0", OsOutputVersion::V0_13_0)]
+    #[case("0.13.1", OsOutputVersion::V0_13_1)]
+    #[case("0.13.2", OsOutputVersion::V0_13_2)]
+    #[case("v0.13.4", OsOutputVersion::V0_13_2)]
+    #[case("0.14", OsOutputVersion::V0_13_2)]
+    fn test_version_from_str(#[case] version: &str, #[case] expected: OsOutputVersion) {
+        assert_eq!(version.parse(), Ok(expected));
+    }
+}
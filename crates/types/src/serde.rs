@@ -5,37 +5,67 @@ use majin_blob_eip_4844::BLOB_LEN;
 use num_bigint::BigUint;
 use num_traits::{Num, ToPrimitive, Zero};
 use serde_json;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EncodeError {
+    #[error("contract {0:#x} writes 0 to storage key 0, which the parser reads as the end of its updates")]
+    ZeroStorageUpdate(BigUint),
+}
 
 /// Function to parse the encoded data into a vector of StateDiff structs.
+/// Parsing stops at the end of the data, so truncated data gives the state diffs read so far.
 /// # Arguments
 /// * `data` - A vector of `BigUint` representing the encoded data.
 /// # Returns
 /// A `DataJson` structs.
 pub fn parse_state_diffs(data: &[BigUint]) -> DataJson {
+    parse_state_diffs_with_len(data).0
+}
+
+/// Function to compute how many elements of the encoded data the state diffs span.
+/// The elements after them are the zero padding of the blob.
+/// # Arguments
+/// * `data` - A vector of `BigUint` representing the encoded data.
+/// # Returns
+/// The number of elements read by `parse_state_diffs`.
+pub fn state_diffs_len(data: &[BigUint]) -> usize {
+    parse_state_diffs_with_len(data).1
+}
+
+fn parse_state_diffs_with_len(data: &[BigUint]) -> (DataJson, usize) {
     let mut updates = Vec::new();
     let mut i = 0;
-    let contract_updated_num = data[i].to_usize().unwrap();
+    // Out of range lengths read as the end of the data
+    let contract_updated_num = data.first().and_then(|n| n.to_usize()).unwrap_or(0);
     i += 5;
     // iterate only on len-1 because (len-1)th element contains the length
     // of declared classes.
-    for _ in 0..contract_updated_num - 1 {
-        let address = data[i].clone();
+    for _ in 0..contract_updated_num.saturating_sub(1) {
+        // Break after the end of the data
+        let Some(address) = data.get(i).cloned() else {
+            break;
+        };
         // Break if address undefined
         if address == BigUint::zero() {
             break;
         }
         i += 1;
-        // Break after blob data len
-        if i >= BLOB_LEN - 1 {
+        // Break after the end of the data
+        if i >= data.len() {
             break;
         }
         let info_word = &data[i];
 
-        let (class_flag, nonce, number_of_storage_updates) = extract_bits(&info_word);
+        let (class_flag, nonce, number_of_storage_updates) = extract_bits(info_word);
         
         let new_class_hash = if class_flag {
             i += 1;
-            Some(data[i].clone())
+            // Break after the end of the data
+            match data.get(i) {
+                Some(class_hash) => Some(class_hash.clone()),
+                None => break,
+            }
         } else {
             None
         };
@@ -44,8 +74,8 @@ pub fn parse_state_diffs(data: &[BigUint]) -> DataJson {
 
         let mut storage_updates = Vec::new();
         for _ in 0..number_of_storage_updates {
-            // Break after blob data len
-            if i >= BLOB_LEN - 1 {
+            // Break after the end of the data
+            if i + 1 >= data.len() {
                 break;
             }
             let key = data[i].clone();
@@ -68,19 +98,21 @@ pub fn parse_state_diffs(data: &[BigUint]) -> DataJson {
         });
     }
 
-    let declared_classes_len = data[i].to_usize().unwrap();
+    let declared_classes_len = data.get(i).and_then(|n| n.to_usize()).unwrap_or(0);
     let mut class_declaration_updates = Vec::new();
     i += 1;
     for _ in 0..declared_classes_len {
-        let class_hash = data[i].clone();
-        // Break if address undefined
+        // Break after the end of the data
+        let Some(class_hash) = data.get(i).cloned() else {
+            break;
+        };
+        // Break if class hash undefined, it can't be zero when the len of declared_classes is non-zero
         if class_hash == BigUint::zero() {
-            panic!("class hash can't be zero when the len of declared_classes is non-zero");
             break;
         }
         i += 1;
-        // Break after blob data len
-        if i >= BLOB_LEN - 1 {
+        // Break after the end of the data
+        if i >= data.len() {
             break;
         }
         let compiled_class_hash = data[i].clone();
@@ -93,13 +125,54 @@ pub fn parse_state_diffs(data: &[BigUint]) -> DataJson {
     }
 
     let final_result = DataJson {
-        state_update_size: (contract_updated_num - 1).to_u64().unwrap(),
+        state_update_size: contract_updated_num.saturating_sub(1) as u64,
         state_update: updates,
         class_declaration_size: declared_classes_len.to_u64().unwrap(),
         class_declaration: class_declaration_updates,
     };
 
-    final_result
+    (final_result, i.min(data.len()))
+}
+
+/// Function to encode state diffs into the data format read by `parse_state_diffs`.
+/// The entry of the `0x1` contract, skipped by the parser, is written as a placeholder.
+/// # Arguments
+/// * `state_diffs` - The `DataJson` to encode.
+/// # Returns
+/// A vector of `BigUint` representing the encoded data, or an error for a storage update
+/// writing 0 to key 0, which would not survive `parse_state_diffs`.
+pub fn encode_state_diffs(state_diffs: &DataJson) -> Result<Vec<BigUint>, EncodeError> {
+    let mut data = vec![
+        BigUint::from(state_diffs.state_update.len() + 1),
+        BigUint::from(1u32),
+        BigUint::from(1u32),
+        BigUint::zero(),
+        BigUint::zero(),
+    ];
+    for update in &state_diffs.state_update {
+        data.push(update.address.clone());
+        data.push(build_info_word(
+            update.new_class_hash.is_some(),
+            update.nonce,
+            update.storage_updates.len() as u64,
+        ));
+        if let Some(class_hash) = &update.new_class_hash {
+            data.push(class_hash.clone());
+        }
+        for storage_update in &update.storage_updates {
+            if storage_update.key.is_zero() && storage_update.value.is_zero() {
+                return Err(EncodeError::ZeroStorageUpdate(update.address.clone()));
+            }
+            data.push(storage_update.key.clone());
+            data.push(storage_update.value.clone());
+        }
+    }
+    data.push(BigUint::from(state_diffs.class_declaration.len()));
+    for declaration in &state_diffs.class_declaration {
+        data.push(declaration.class_hash.clone());
+        data.push(declaration.compiled_class_hash.clone());
+    }
+    Ok(data)
 }
 
 /// Function to convert a vector of StateDiff structs into a JSON string.
@@ -165,13 +238,21 @@ fn extract_bits(info_word: &BigUint) -> (bool, u64, u64) {
     (class_flag, new_nonce, num_changes)
 }
 
+/// Function to build an info word from the class flag, nonce and state_diff length,
+/// the inverse of `extract_bits`.
+fn build_info_word(class_flag: bool, nonce: u64, num_changes: u64) -> BigUint {
+    (BigUint::from(class_flag as u8) << 128u32)
+        | (BigUint::from(nonce) << 64u32)
+        | BigUint::from(num_changes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
     use std::fs;
     use num_bigint::BigUint;
-    use std::{result, str::FromStr};
+    use std::str::FromStr;
     use rstest::rstest;
     use crate::state_diffs::{ClassDeclaration, ContractUpdate, DataJson, StorageUpdate};
 
@@ -285,6 +366,29 @@ mod tests {
         assert_eq!(result, expected_result);
     }
 
+    #[test]
+    fn test_encode_state_diffs_round_trip() {
+        let state_diffs = DataJson {
+            state_update_size: 2,
+            state_update: vec![
+                ContractUpdate {address: BigUint::from(1234u64), nonce: 5678, number_of_storage_updates: 2, new_class_hash: Some(BigUint::from(5432u64)), storage_updates: vec![StorageUpdate{key: BigUint::from(12u64), value: BigUint::from(34u64)}, StorageUpdate{key: BigUint::from(56u64), value: BigUint::from(78u64)}]},
+                ContractUpdate {address: BigUint::from(4321u64), nonce: 0, number_of_storage_updates: 1, new_class_hash: None, storage_updates: vec![StorageUpdate{key: BigUint::from(90u64), value: BigUint::from(1u64)}]},
+            ],
+            class_declaration_size: 1,
+            class_declaration: vec![ClassDeclaration {class_hash:BigUint::from(56u64),compiled_class_hash:BigUint::from(78u64)}]
+        };
+        let encoded = encode_state_diffs(&state_diffs).unwrap();
+        assert_eq!(parse_state_diffs(&encoded), state_diffs);
+
+        // Writing 0 to key 0 reads back as the end of the updates, so it is rejected
+        let mut zero_update = state_diffs.clone();
+        zero_update.state_update[1].storage_updates[0] = StorageUpdate{key: BigUint::zero(), value: BigUint::zero()};
+        assert_eq!(encode_state_diffs(&zero_update), Err(EncodeError::ZeroStorageUpdate(BigUint::from(4321u64))));
+
+        let json = to_json(state_diffs.clone());
+        assert_eq!(serde_json::from_str::<DataJson>(&json).unwrap(), state_diffs);
+    }
+
     #[rstest]
     #[case(
         &"0000000000000000000000000000000100000000000000010000000000000000".repeat(4096), 
@@ -321,9 +425,9 @@ mod tests {
         ].repeat(4096/2))
 
     )]
-    fn test_parse_str_to_blob_data(#[case] data: &str, #[case] expected_result: &Vec<BigUint>) {
+    fn test_parse_str_to_blob_data(#[case] data: &str, #[case] expected_result: &[BigUint]) {
         let result = parse_str_to_blob_data(data);
-        assert_eq!(result, expected_result.clone());
+        assert_eq!(result, expected_result);
     }
 
     #[rstest]
@@ -334,9 +438,64 @@ mod tests {
     #[case("src/testutils/blob_639404.txt", "src/testutils/blob_639404_output.txt")]
     fn test_parse_file_to_blob_data(#[case] file_path: &str, #[case] expected_output_file_path: &str) {
         let result = parse_file_to_blob_data(file_path);
-        let expected_output: Vec<BigUint> = fs::read_to_string(expected_output_file_path).expect("Failed to read file").lines().into_iter()
-                                                .map(|s| BigUint::from_str(&s).expect("Failed to parse BigUint"))
+        let expected_output: Vec<BigUint> = fs::read_to_string(expected_output_file_path).expect("Failed to read file").lines()
+                                                .map(|s| BigUint::from_str(s).expect("Failed to parse BigUint"))
                                                 .collect();
         assert_eq!(result,expected_output);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_parse_state_diffs_over_several_blobs() {
+        // A contract writing 2100 slots spans more than a blob
+        let storage_updates: Vec<StorageUpdate> = (1..=2100u64)
+            .map(|key| StorageUpdate {
+                key: BigUint::from(key),
+                value: BigUint::from(2 * key),
+            })
+            .collect();
+        let mut data = biguints_from_strings(&["2", "0", "0", "0", "0", "1234", "2100"]);
+        for update in &storage_updates {
+            data.extend([update.key.clone(), update.value.clone()]);
+        }
+        data.push(BigUint::zero());
+        assert!(data.len() > BLOB_LEN);
+
+        let expected = DataJson {
+            state_update_size: 1,
+            state_update: vec![ContractUpdate {
+                address: BigUint::from(1234u64),
+                nonce: 0,
+                number_of_storage_updates: 2100,
+                new_class_hash: None,
+                storage_updates,
+            }],
+            class_declaration_size: 0,
+            class_declaration: vec![],
+        };
+        assert_eq!(parse_state_diffs(&data), expected);
+        assert_eq!(state_diffs_len(&data), data.len());
+    }
+
+    #[test]
+    fn test_parse_truncated_state_diffs() {
+        // A contract with a new class hash and two storage updates, then a declared class
+        let data = biguints_from_strings(&[
+            "2", "0", "0", "0", "0", "1234", "340282366920938463463374607431768211458", "5432",
+            "12", "34", "56", "78", "1", "90", "12",
+        ]);
+        let full = parse_state_diffs(&data);
+        assert_eq!(full.state_update[0].storage_updates.len(), 2);
+        assert_eq!(full.class_declaration.len(), 1);
+        // Every prefix parses to the state diffs it holds, without reading past its end
+        for len in 0..data.len() {
+            let truncated = parse_state_diffs(&data[..len]);
+            assert!(truncated.class_declaration.len() <= full.class_declaration.len());
+            assert!(state_diffs_len(&data[..len]) <= len);
+        }
+        assert_eq!(parse_state_diffs(&[]).state_update_size, 0);
+
+        // A zero class hash ends the declared classes
+        let data = biguints_from_strings(&["1", "0", "0", "0", "0", "2", "0", "0"]);
+        assert_eq!(parse_state_diffs(&data).class_declaration, vec![]);
+    }
+}
@@ -0,0 +1,627 @@
+use majin_blob_eip_4844::blob::{blob_from_bytes, BlobError};
+use majin_blob_eip_4844::{BYTES_PER_BLOB, BYTES_PER_COMMITMENT, BYTES_PER_PROOF};
+use num_bigint::BigUint;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::serde::parse_str_to_blob_data;
+use crate::ssz::{bytes_root, is_valid_merkle_branch, merkleize, uint64_chunk, Chunk};
+
+/// Depth of the inclusion proof of a KZG commitment in the beacon block body (Deneb).
+pub const KZG_COMMITMENT_INCLUSION_PROOF_DEPTH: usize = 17;
+/// Index at depth `KZG_COMMITMENT_INCLUSION_PROOF_DEPTH` of the first element of
+/// `blob_kzg_commitments` in the Deneb `BeaconBlockBody`, `get_subtree_index` of its
+/// generalized index.
+pub const KZG_COMMITMENTS_SUBTREE_INDEX: u64 = 90112;
+/// Size in bytes of a BLS signature.
+pub const BYTES_PER_SIGNATURE: usize = 96;
+/// Size in bytes of an SSZ-encoded `BeaconBlockHeader`.
+pub const BEACON_BLOCK_HEADER_SSZ_LEN: usize = 8 + 8 + 32 * 3;
+/// Size in bytes of an SSZ-encoded `BlobSidecar`.
+pub const BLOB_SIDECAR_SSZ_LEN: usize = 8
+    + BYTES_PER_BLOB
+    + BYTES_PER_COMMITMENT
+    + BYTES_PER_PROOF
+    + BEACON_BLOCK_HEADER_SSZ_LEN
+    + BYTES_PER_SIGNATURE
+    + 32 * KZG_COMMITMENT_INCLUSION_PROOF_DEPTH;
+
+#[derive(Debug, Error)]
+pub enum SidecarError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("SSZ input of {0} bytes is not a list of blob sidecars")]
+    InvalidSszLength(usize),
+    #[error(transparent)]
+    Blob(#[from] BlobError),
+    #[error("expected a blob of {expected} hex characters, got {actual}")]
+    InvalidHexBlobLength { expected: usize, actual: usize },
+    #[error("the commitment of sidecar {0} is not included in its block body")]
+    InvalidInclusionProof(u64),
+    #[error("sidecar {0} belongs to another block")]
+    BlockRootMismatch(u64),
+    #[error("the KZG proof of sidecar {0} does not bind its blob to its commitment")]
+    InvalidKzgProof(u64),
+    #[cfg(feature = "kzg")]
+    #[error(transparent)]
+    Kzg(#[from] majin_blob_eip_4844::kzg::KzgError),
+}
+
+/// The header of a beacon block, as defined in the consensus specs.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BeaconBlockHeader {
+    #[serde(deserialize_with = "deserialize_quoted_u64")]
+    pub slot: u64,
+    #[serde(deserialize_with = "deserialize_quoted_u64")]
+    pub proposer_index: u64,
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub parent_root: [u8; 32],
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub state_root: [u8; 32],
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub body_root: [u8; 32],
+}
+
+/// A beacon block header and the signature of its proposer.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedBeaconBlockHeader {
+    pub message: BeaconBlockHeader,
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub signature: [u8; BYTES_PER_SIGNATURE],
+}
+
+/// A blob and the data tying it to a beacon block, as served by
+/// `/eth/v1/beacon/blob_sidecars/{block_id}`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlobSidecar {
+    #[serde(deserialize_with = "deserialize_quoted_u64")]
+    pub index: u64,
+    /// The blob field elements, in the same form as `parse_str_to_blob_data` returns.
+    #[serde(deserialize_with = "deserialize_blob")]
+    pub blob: Vec<BigUint>,
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub kzg_commitment: [u8; BYTES_PER_COMMITMENT],
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub kzg_proof: [u8; BYTES_PER_PROOF],
+    pub signed_block_header: SignedBeaconBlockHeader,
+    #[serde(deserialize_with = "deserialize_hex_list")]
+    pub kzg_commitment_inclusion_proof: Vec<[u8; 32]>,
+}
+
+impl BeaconBlockHeader {
+    /// Compute the SSZ `hash_tree_root` of the header, the root of its block.
+    pub fn hash_tree_root(&self) -> Chunk {
+        merkleize(
+            &[
+                uint64_chunk(self.slot),
+                uint64_chunk(self.proposer_index),
+                self.parent_root,
+                self.state_root,
+                self.body_root,
+            ],
+            None,
+        )
+    }
+}
+
+impl BlobSidecar {
+    /// Check the Merkle proof that the commitment is in the block body of the header.
+    pub fn verify_inclusion_proof(&self) -> bool {
+        is_valid_merkle_branch(
+            &bytes_root(&self.kzg_commitment),
+            &self.kzg_commitment_inclusion_proof,
+            KZG_COMMITMENT_INCLUSION_PROOF_DEPTH,
+            KZG_COMMITMENTS_SUBTREE_INDEX + self.index,
+            &self.signed_block_header.message.body_root,
+        )
+    }
+
+    /// Check the KZG proof binding the blob to the commitment.
+    #[cfg(feature = "kzg")]
+    pub fn verify_kzg_proof(&self) -> Result<bool, SidecarError> {
+        Ok(majin_blob_eip_4844::kzg::verify_blob_proof(
+            &self.blob,
+            &self.kzg_commitment,
+            &self.kzg_proof,
+        )?)
+    }
+
+    /// Authenticate the sidecar: its commitment must be in the block body and, with the
+    /// `kzg` feature, its blob must match the commitment.
+    /// # Arguments
+    /// * `block_root` - The root of the block the sidecar must belong to, if known from a
+    ///   trusted source.
+    pub fn verify(&self, block_root: Option<&Chunk>) -> Result<(), SidecarError> {
+        if let Some(block_root) = block_root {
+            if self.signed_block_header.message.hash_tree_root() != *block_root {
+                return Err(SidecarError::BlockRootMismatch(self.index));
+            }
+        }
+        if !self.verify_inclusion_proof() {
+            return Err(SidecarError::InvalidInclusionProof(self.index));
+        }
+        #[cfg(feature = "kzg")]
+        if !self.verify_kzg_proof()? {
+            return Err(SidecarError::InvalidKzgProof(self.index));
+        }
+        Ok(())
+    }
+}
+
+/// How far a list of sidecars could be authenticated by [`verify_sidecars`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authentication {
+    /// The sidecars belong to the trusted block root.
+    Authenticated,
+    /// The sidecars are consistent with a single block header, but nothing ties that
+    /// header to the chain: a forged header with forged sidecars passes these checks.
+    Unauthenticated,
+}
+
+/// Authenticate a list of sidecars, see [`BlobSidecar::verify`].
+///
+/// The signature of the proposer over the block header is not verified: the sidecars are
+/// only authenticated by a block root from a trusted source, such as the
+/// `parentBeaconBlockRoot` of the next execution block or a finalized checkpoint.
+/// # Arguments
+/// * `block_root` - The root of the block the sidecars must belong to. Without it, the
+///   sidecars must all belong to the same block, which does not authenticate them.
+/// # Returns
+/// Whether the sidecars were checked against a trusted block root, or an error if any of
+/// them fails its checks.
+pub fn verify_sidecars(
+    sidecars: &[BlobSidecar],
+    block_root: Option<&Chunk>,
+) -> Result<Authentication, SidecarError> {
+    let (block_root, authentication) = match (block_root, sidecars.first()) {
+        (Some(block_root), _) => (*block_root, Authentication::Authenticated),
+        (None, Some(first)) => (
+            first.signed_block_header.message.hash_tree_root(),
+            Authentication::Unauthenticated,
+        ),
+        (None, None) => return Ok(Authentication::Unauthenticated),
+    };
+    sidecars
+        .iter()
+        .try_for_each(|sidecar| sidecar.verify(Some(&block_root)))?;
+    Ok(authentication)
+}
+
+/// Parse the JSON body of a `blob_sidecars` response.
+/// # Arguments
+/// * `input` - The JSON document, either the response object or the bare list of sidecars.
+/// # Returns
+/// The blob sidecars, in the order of the document.
+pub fn parse_sidecars_json(input: &str) -> Result<Vec<BlobSidecar>, SidecarError> {
+    let mut document: serde_json::Value = serde_json::from_str(input)?;
+    let sidecars = match document.get_mut("data") {
+        Some(data) => data.take(),
+        None => document,
+    };
+    Ok(serde_json::from_value(sidecars)?)
+}
+
+/// Parse the SSZ body of a `blob_sidecars` response, a `List[BlobSidecar]`.
+/// # Arguments
+/// * `input` - The SSZ bytes.
+/// # Returns
+/// The blob sidecars, in the order of the list.
+pub fn parse_sidecars_ssz(input: &[u8]) -> Result<Vec<BlobSidecar>, SidecarError> {
+    if !input.len().is_multiple_of(BLOB_SIDECAR_SSZ_LEN) {
+        return Err(SidecarError::InvalidSszLength(input.len()));
+    }
+    input
+        .chunks(BLOB_SIDECAR_SSZ_LEN)
+        .map(decode_sidecar_ssz)
+        .collect()
+}
+
+fn decode_sidecar_ssz(bytes: &[u8]) -> Result<BlobSidecar, SidecarError> {
+    let mut reader = SszReader { bytes };
+    let index = reader.u64();
+    let blob = blob_from_bytes(reader.take(BYTES_PER_BLOB))?;
+    let kzg_commitment = reader.array();
+    let kzg_proof = reader.array();
+    let message = BeaconBlockHeader {
+        slot: reader.u64(),
+        proposer_index: reader.u64(),
+        parent_root: reader.array(),
+        state_root: reader.array(),
+        body_root: reader.array(),
+    };
+    let signature = reader.array();
+    let kzg_commitment_inclusion_proof = (0..KZG_COMMITMENT_INCLUSION_PROOF_DEPTH)
+        .map(|_| reader.array())
+        .collect();
+    Ok(BlobSidecar {
+        index,
+        blob,
+        kzg_commitment,
+        kzg_proof,
+        signed_block_header: SignedBeaconBlockHeader { message, signature },
+        kzg_commitment_inclusion_proof,
+    })
+}
+
+/// Reads the fields of a fixed-size SSZ container, whose length was checked beforehand.
+struct SszReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SszReader<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        let (field, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        field
+    }
+
+    fn array<const N: usize>(&mut self) -> [u8; N] {
+        self.take(N).try_into().unwrap()
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.array())
+    }
+}
+
+/// Blobs as read from an input document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlobInput {
+    /// A single bare blob, which cannot be authenticated.
+    Blob(Vec<BigUint>),
+    /// Blob sidecars, see [`verify_sidecars`].
+    Sidecars(Vec<BlobSidecar>),
+}
+
+impl BlobInput {
+    /// Extract the blobs, ordered by sidecar index.
+    pub fn into_blobs(self) -> Vec<Vec<BigUint>> {
+        match self {
+            BlobInput::Blob(blob) => vec![blob],
+            BlobInput::Sidecars(sidecars) => sidecars_to_blobs(sidecars),
+        }
+    }
+}
+
+/// Parse an input document in any of the supported formats: a `blob_sidecars` response as
+/// JSON or SSZ, or a single blob as hex.
+/// # Arguments
+/// * `input` - The raw document.
+/// # Returns
+/// The blob or the sidecars of the document.
+pub fn parse_blob_input(input: &[u8]) -> Result<BlobInput, SidecarError> {
+    Ok(match input.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') | Some(b'[') => {
+            BlobInput::Sidecars(parse_sidecars_json(&String::from_utf8_lossy(input))?)
+        }
+        _ => match std::str::from_utf8(input) {
+            Ok(text) if is_hex(text) => BlobInput::Blob(parse_hex_blob(text)?),
+            _ => BlobInput::Sidecars(parse_sidecars_ssz(input)?),
+        },
+    })
+}
+
+/// Parse blobs given in any of the supported formats, see [`parse_blob_input`]. Sidecars
+/// are not authenticated.
+/// # Arguments
+/// * `input` - The raw document.
+/// # Returns
+/// The field elements of each blob, ordered by sidecar index.
+pub fn parse_blobs(input: &[u8]) -> Result<Vec<Vec<BigUint>>, SidecarError> {
+    Ok(parse_blob_input(input)?.into_blobs())
+}
+
+/// Extract the blobs of a list of sidecars, ordered by sidecar index.
+pub fn sidecars_to_blobs(mut sidecars: Vec<BlobSidecar>) -> Vec<Vec<BigUint>> {
+    sidecars.sort_by_key(|sidecar| sidecar.index);
+    sidecars.into_iter().map(|sidecar| sidecar.blob).collect()
+}
+
+fn is_hex(text: &str) -> bool {
+    let text = text.trim();
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    !text.is_empty() && text.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn parse_hex_blob(text: &str) -> Result<Vec<BigUint>, SidecarError> {
+    let text = text.trim();
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    if text.len() != 2 * BYTES_PER_BLOB {
+        return Err(SidecarError::InvalidHexBlobLength {
+            expected: 2 * BYTES_PER_BLOB,
+            actual: text.len(),
+        });
+    }
+    Ok(parse_str_to_blob_data(text))
+}
+
+fn deserialize_quoted_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+fn decode_hex<E: serde::de::Error>(value: &str) -> Result<Vec<u8>, E> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value)).map_err(E::custom)
+}
+
+fn deserialize_hex<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes = decode_hex::<D::Error>(&String::deserialize(deserializer)?)?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| serde::de::Error::invalid_length(len, &format!("{} bytes", N).as_str()))
+}
+
+fn deserialize_hex_list<'de, D>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| {
+            decode_hex::<D::Error>(value)?
+                .try_into()
+                .map_err(|_| serde::de::Error::custom("expected 32 bytes"))
+        })
+        .collect()
+}
+
+fn deserialize_blob<'de, D>(deserializer: D) -> Result<Vec<BigUint>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes = decode_hex::<D::Error>(&String::deserialize(deserializer)?)?;
+    blob_from_bytes(&bytes).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::parse_file_to_blob_data;
+    use majin_blob_eip_4844::blob::blob_to_bytes;
+
+    const EXAMPLE_BLOB: &str = "../../examples/blob/mainnet.txt";
+
+    fn example_sidecar_json(index: u64, blob_hex: &str) -> String {
+        let root = format!("0x{}", "11".repeat(32));
+        format!(
+            r#"{{
+                "index": "{index}",
+                "blob": "0x{blob_hex}",
+                "kzg_commitment": "0x{commitment}",
+                "kzg_proof": "0x{proof}",
+                "signed_block_header": {{
+                    "message": {{
+                        "slot": "8626176",
+                        "proposer_index": "1234",
+                        "parent_root": "{root}",
+                        "state_root": "{root}",
+                        "body_root": "{root}"
+                    }},
+                    "signature": "0x{signature}"
+                }},
+                "kzg_commitment_inclusion_proof": [{proof_list}]
+            }}"#,
+            commitment = "a0".repeat(BYTES_PER_COMMITMENT),
+            proof = "b0".repeat(BYTES_PER_PROOF),
+            signature = "c0".repeat(BYTES_PER_SIGNATURE),
+            proof_list =
+                vec![format!("\"{}\"", root); KZG_COMMITMENT_INCLUSION_PROOF_DEPTH].join(","),
+        )
+    }
+
+    fn example_sidecar_ssz(sidecar: &BlobSidecar) -> Vec<u8> {
+        let header = &sidecar.signed_block_header.message;
+        let mut out = sidecar.index.to_le_bytes().to_vec();
+        out.extend(blob_to_bytes(&sidecar.blob).unwrap());
+        out.extend(sidecar.kzg_commitment);
+        out.extend(sidecar.kzg_proof);
+        out.extend(header.slot.to_le_bytes());
+        out.extend(header.proposer_index.to_le_bytes());
+        out.extend(header.parent_root);
+        out.extend(header.state_root);
+        out.extend(header.body_root);
+        out.extend(sidecar.signed_block_header.signature);
+        for node in &sidecar.kzg_commitment_inclusion_proof {
+            out.extend(node);
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_sidecars_json() {
+        let blob_hex = std::fs::read_to_string(EXAMPLE_BLOB).unwrap();
+        let response = format!(
+            r#"{{"data": [{}]}}"#,
+            example_sidecar_json(3, blob_hex.trim())
+        );
+        let sidecars = parse_sidecars_json(&response).unwrap();
+        assert_eq!(sidecars.len(), 1);
+        let sidecar = &sidecars[0];
+        assert_eq!(sidecar.index, 3);
+        assert_eq!(sidecar.blob, parse_file_to_blob_data(EXAMPLE_BLOB));
+        assert_eq!(sidecar.kzg_commitment, [0xa0; BYTES_PER_COMMITMENT]);
+        assert_eq!(sidecar.signed_block_header.message.slot, 8626176);
+        assert_eq!(sidecar.signed_block_header.message.proposer_index, 1234);
+        assert_eq!(
+            sidecar.kzg_commitment_inclusion_proof,
+            vec![[0x11; 32]; KZG_COMMITMENT_INCLUSION_PROOF_DEPTH]
+        );
+
+        let list = format!("[{}]", example_sidecar_json(3, blob_hex.trim()));
+        assert_eq!(parse_sidecars_json(&list).unwrap(), sidecars);
+    }
+
+    #[test]
+    fn test_parse_sidecars_ssz() {
+        let blob_hex = std::fs::read_to_string(EXAMPLE_BLOB).unwrap();
+        let list = format!(
+            "[{}, {}]",
+            example_sidecar_json(1, blob_hex.trim()),
+            example_sidecar_json(0, &"00".repeat(BYTES_PER_BLOB))
+        );
+        let mut sidecars = parse_sidecars_json(&list).unwrap();
+        let ssz: Vec<u8> = sidecars.iter().flat_map(example_sidecar_ssz).collect();
+        assert_eq!(ssz.len(), 2 * BLOB_SIDECAR_SSZ_LEN);
+        assert_eq!(parse_sidecars_ssz(&ssz).unwrap(), sidecars);
+        assert!(matches!(
+            parse_sidecars_ssz(&ssz[1..]),
+            Err(SidecarError::InvalidSszLength(_))
+        ));
+
+        // Blobs come out ordered by index, whatever the encoding
+        let blobs = parse_blobs(&ssz).unwrap();
+        sidecars.sort_by_key(|sidecar| sidecar.index);
+        assert_eq!(blobs[0], sidecars[0].blob);
+        assert_eq!(blobs[1], parse_file_to_blob_data(EXAMPLE_BLOB));
+    }
+
+    #[test]
+    fn test_parse_blobs_detects_format() {
+        let blob_hex = std::fs::read_to_string(EXAMPLE_BLOB).unwrap();
+        let expected = vec![parse_file_to_blob_data(EXAMPLE_BLOB)];
+        assert_eq!(parse_blobs(blob_hex.as_bytes()).unwrap(), expected);
+
+        let response = format!(
+            r#"{{"data": [{}]}}"#,
+            example_sidecar_json(0, blob_hex.trim())
+        );
+        assert_eq!(parse_blobs(response.as_bytes()).unwrap(), expected);
+
+        assert!(matches!(
+            parse_blobs(b"0x1234"),
+            Err(SidecarError::InvalidHexBlobLength { .. })
+        ));
+    }
+
+    /// Collect the siblings of a leaf in a full tree, bottom-up.
+    fn merkle_branch(leaves: &[Chunk], mut index: usize) -> Vec<Chunk> {
+        let mut layer = leaves.to_vec();
+        let mut branch = Vec::new();
+        while layer.len() > 1 {
+            branch.push(layer[index ^ 1]);
+            layer = layer
+                .chunks(2)
+                .map(|pair| crate::ssz::hash_pair(&pair[0], &pair[1]))
+                .collect();
+            index /= 2;
+        }
+        branch
+    }
+
+    /// Build a block body holding the commitments of the sidecars, and set their header
+    /// and inclusion proof accordingly.
+    fn include_in_block(sidecars: &mut [BlobSidecar]) {
+        let mut commitment_leaves = vec![[0u8; 32]; 4096];
+        for sidecar in sidecars.iter() {
+            commitment_leaves[sidecar.index as usize] = bytes_root(&sidecar.kzg_commitment);
+        }
+        let count = sidecars.len();
+        let commitments_root =
+            crate::ssz::mix_in_length(&merkleize(&commitment_leaves, None), count);
+        let mut body_fields: Vec<Chunk> = (0..16u8).map(|i| [i; 32]).collect();
+        body_fields[11] = commitments_root;
+        let body_root = merkleize(&body_fields, None);
+
+        for sidecar in sidecars.iter_mut() {
+            let mut proof = merkle_branch(&commitment_leaves, sidecar.index as usize);
+            proof.push(uint64_chunk(count as u64));
+            proof.extend(merkle_branch(&body_fields, 11));
+            sidecar.kzg_commitment_inclusion_proof = proof;
+            sidecar.signed_block_header.message.body_root = body_root;
+        }
+    }
+
+    fn authentic_sidecars() -> Vec<BlobSidecar> {
+        let blob_hex = std::fs::read_to_string(EXAMPLE_BLOB).unwrap();
+        let list = format!(
+            "[{}, {}]",
+            example_sidecar_json(0, blob_hex.trim()),
+            example_sidecar_json(1, &"00".repeat(BYTES_PER_BLOB))
+        );
+        let mut sidecars = parse_sidecars_json(&list).unwrap();
+        #[cfg(feature = "kzg")]
+        for sidecar in sidecars.iter_mut() {
+            use majin_blob_eip_4844::kzg::{blob_to_commitment, compute_blob_proof};
+            sidecar.kzg_commitment = blob_to_commitment(&sidecar.blob).unwrap();
+            sidecar.kzg_proof = compute_blob_proof(&sidecar.blob, &sidecar.kzg_commitment).unwrap();
+        }
+        include_in_block(&mut sidecars);
+        sidecars
+    }
+
+    #[test]
+    fn test_verify_sidecars() {
+        let sidecars = authentic_sidecars();
+        assert_eq!(
+            verify_sidecars(&sidecars, None).unwrap(),
+            Authentication::Unauthenticated
+        );
+        let block_root = sidecars[0].signed_block_header.message.hash_tree_root();
+        assert_eq!(
+            verify_sidecars(&sidecars, Some(&block_root)).unwrap(),
+            Authentication::Authenticated
+        );
+
+        assert!(matches!(
+            verify_sidecars(&sidecars, Some(&[0u8; 32])),
+            Err(SidecarError::BlockRootMismatch(0))
+        ));
+
+        let mut tampered = sidecars.clone();
+        tampered[1].kzg_commitment[47] ^= 1;
+        assert!(matches!(
+            verify_sidecars(&tampered, None),
+            Err(SidecarError::InvalidInclusionProof(1))
+        ));
+
+        let mut moved = sidecars.clone();
+        moved[1].index = 2;
+        assert!(!moved[1].verify_inclusion_proof());
+
+        let mut other_block = sidecars;
+        other_block[1].signed_block_header.message.slot += 1;
+        assert!(matches!(
+            verify_sidecars(&other_block, None),
+            Err(SidecarError::BlockRootMismatch(1))
+        ));
+    }
+
+    #[test]
+    fn test_forged_sidecars_are_not_authenticated() {
+        let sidecars = authentic_sidecars();
+        let block_root = sidecars[0].signed_block_header.message.hash_tree_root();
+
+        // A consistent set of sidecars under a forged header is only unauthenticated
+        let mut forged = sidecars;
+        for sidecar in forged.iter_mut() {
+            sidecar.signed_block_header.message.proposer_index += 1;
+        }
+        assert_eq!(
+            verify_sidecars(&forged, None).unwrap(),
+            Authentication::Unauthenticated
+        );
+        assert!(matches!(
+            verify_sidecars(&forged, Some(&block_root)),
+            Err(SidecarError::BlockRootMismatch(0))
+        ));
+    }
+
+    #[cfg(feature = "kzg")]
+    #[test]
+    fn test_verify_sidecars_rejects_tampered_blob() {
+        let mut sidecars = authentic_sidecars();
+        sidecars[0].blob[0] += 1u32;
+        assert!(matches!(
+            verify_sidecars(&sidecars, None),
+            Err(SidecarError::InvalidKzgProof(0))
+        ));
+    }
+}
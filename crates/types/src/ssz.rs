@@ -0,0 +1,130 @@
+use sha2::{Digest, Sha256};
+
+/// A 32-byte SSZ chunk, also the type of every node of a Merkle tree.
+pub type Chunk = [u8; 32];
+
+/// Hash two nodes of a Merkle tree into their parent.
+pub fn hash_pair(left: &Chunk, right: &Chunk) -> Chunk {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Compute the root of a zero-padded tree of `depth` levels whose leaves are all zero.
+pub fn zero_hash(depth: usize) -> Chunk {
+    (0..depth).fold([0u8; 32], |node, _| hash_pair(&node, &node))
+}
+
+/// Merkleize chunks, as `merkleize` in the SSZ specification.
+/// # Arguments
+/// * `chunks` - The leaves of the tree.
+/// * `limit` - The number of leaves the tree is padded to, `None` to pad to the next power
+///   of two.
+/// # Returns
+/// The root of the tree.
+pub fn merkleize(chunks: &[Chunk], limit: Option<usize>) -> Chunk {
+    let size = limit.unwrap_or(chunks.len()).max(chunks.len()).max(1);
+    let depth = size.next_power_of_two().trailing_zeros() as usize;
+
+    let mut layer = chunks.to_vec();
+    for level in 0..depth {
+        if layer.len() % 2 == 1 {
+            layer.push(zero_hash(level));
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+    layer.pop().unwrap_or_else(|| zero_hash(depth))
+}
+
+/// Mix the length of a list into the root of its data, as `mix_in_length` in the SSZ
+/// specification.
+pub fn mix_in_length(root: &Chunk, length: usize) -> Chunk {
+    hash_pair(root, &uint64_chunk(length as u64))
+}
+
+/// Serialize a `uint64` into a chunk, little-endian and zero-padded.
+pub fn uint64_chunk(value: u64) -> Chunk {
+    let mut chunk = [0u8; 32];
+    chunk[..8].copy_from_slice(&value.to_le_bytes());
+    chunk
+}
+
+/// Compute the `hash_tree_root` of a fixed-size byte vector.
+pub fn bytes_root(bytes: &[u8]) -> Chunk {
+    let chunks: Vec<Chunk> = bytes
+        .chunks(32)
+        .map(|part| {
+            let mut chunk = [0u8; 32];
+            chunk[..part.len()].copy_from_slice(part);
+            chunk
+        })
+        .collect();
+    merkleize(&chunks, None)
+}
+
+/// Check a Merkle branch, as `is_valid_merkle_branch` in the consensus specs.
+/// # Arguments
+/// * `leaf` - The leaf the branch starts from.
+/// * `branch` - The siblings of the nodes on the path from the leaf, bottom-up.
+/// * `depth` - The depth of the leaf.
+/// * `index` - The index of the leaf at that depth.
+/// * `root` - The expected root.
+pub fn is_valid_merkle_branch(
+    leaf: &Chunk,
+    branch: &[Chunk],
+    depth: usize,
+    index: u64,
+    root: &Chunk,
+) -> bool {
+    if branch.len() != depth {
+        return false;
+    }
+    let computed = branch
+        .iter()
+        .enumerate()
+        .fold(*leaf, |node, (level, sibling)| {
+            if (index >> level) & 1 == 1 {
+                hash_pair(sibling, &node)
+            } else {
+                hash_pair(&node, sibling)
+            }
+        });
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkleize() {
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+        let c = [3u8; 32];
+        assert_eq!(merkleize(&[a], None), a);
+        assert_eq!(merkleize(&[a, b], None), hash_pair(&a, &b));
+        assert_eq!(
+            merkleize(&[a, b, c], None),
+            hash_pair(&hash_pair(&a, &b), &hash_pair(&c, &[0u8; 32]))
+        );
+        assert_eq!(merkleize(&[], Some(4)), zero_hash(2));
+        assert_eq!(
+            merkleize(&[a], Some(4)),
+            hash_pair(&hash_pair(&a, &[0u8; 32]), &zero_hash(1))
+        );
+    }
+
+    #[test]
+    fn test_is_valid_merkle_branch() {
+        let leaves = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+        let root = merkleize(&leaves, None);
+        let branch = [leaves[2], hash_pair(&leaves[0], &leaves[1])];
+        assert!(is_valid_merkle_branch(&leaves[3], &branch, 2, 3, &root));
+        assert!(!is_valid_merkle_branch(&leaves[3], &branch, 2, 2, &root));
+        assert!(!is_valid_merkle_branch(&leaves[2], &branch, 2, 3, &root));
+    }
+}
@@ -1,7 +1,8 @@
-use num_bigint::BigUint;
-use serde::{Deserialize, Serialize, Serializer};
+use num_bigint::{BigUint, ParseBigIntError};
+use num_traits::Num;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
+use std::hash::Hash;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct DataJson {
@@ -13,33 +14,52 @@ pub struct DataJson {
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ContractUpdate {
-    #[serde(serialize_with = "serialize_biguint")]
+    #[serde(
+        serialize_with = "serialize_biguint",
+        deserialize_with = "deserialize_biguint"
+    )]
     pub address: BigUint,
     pub nonce: u64,
     pub number_of_storage_updates: u64,
-    #[serde(serialize_with = "serialize_option_biguint")]
+    #[serde(
+        default,
+        serialize_with = "serialize_option_biguint",
+        deserialize_with = "deserialize_option_biguint"
+    )]
     pub new_class_hash: Option<BigUint>, // Present only if class_info_flag is 1
     pub storage_updates: Vec<StorageUpdate>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
 pub struct StorageUpdate {
-    #[serde(serialize_with = "serialize_biguint")]
+    #[serde(
+        serialize_with = "serialize_biguint",
+        deserialize_with = "deserialize_biguint"
+    )]
     pub key: BigUint,
-    #[serde(serialize_with = "serialize_biguint")]
+    #[serde(
+        serialize_with = "serialize_biguint",
+        deserialize_with = "deserialize_biguint"
+    )]
     pub value: BigUint,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ClassDeclaration {
-    #[serde(serialize_with = "serialize_biguint")]
+    #[serde(
+        serialize_with = "serialize_biguint",
+        deserialize_with = "deserialize_biguint"
+    )]
     pub class_hash: BigUint,
-    #[serde(serialize_with = "serialize_biguint")]
+    #[serde(
+        serialize_with = "serialize_biguint",
+        deserialize_with = "deserialize_biguint"
+    )]
     pub compiled_class_hash: BigUint,
 }
 
 // Custom serializer for BigUint
-fn serialize_biguint<S>(biguint: &BigUint, serializer: S) -> Result<S::Ok, S::Error>
+pub fn serialize_biguint<S>(biguint: &BigUint, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
@@ -47,7 +67,7 @@ where
 }
 
 // Custom serializer for Option<BigUint>
-fn serialize_option_biguint<S>(
+pub fn serialize_option_biguint<S>(
     option_biguint: &Option<BigUint>,
     serializer: S,
 ) -> Result<S::Ok, S::Error>
@@ -60,6 +80,34 @@ where
     }
 }
 
+// Parser for felts given as decimal or 0x-prefixed hex strings
+pub fn parse_felt(value: &str) -> Result<BigUint, ParseBigIntError> {
+    match value.strip_prefix("0x") {
+        Some(hex) => BigUint::from_str_radix(hex, 16),
+        None => BigUint::from_str_radix(value, 10),
+    }
+}
+
+// Custom deserializer for BigUint, accepting decimal or 0x-prefixed hex strings
+pub fn deserialize_biguint<'de, D>(deserializer: D) -> Result<BigUint, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_felt(&value).map_err(serde::de::Error::custom)
+}
+
+// Custom deserializer for Option<BigUint>
+pub fn deserialize_option_biguint<'de, D>(deserializer: D) -> Result<Option<BigUint>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "deserialize_biguint")] BigUint);
+
+    Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(value)| value))
+}
+
 // Trait for unordered equality
 pub trait UnorderedEq {
     fn unordered_eq(&self, other: &Self) -> bool;